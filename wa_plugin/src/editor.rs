@@ -3,7 +3,11 @@
 // - when analysis + correction is available, add a toggle to switch between analysis or correction (with icons? [magnifier glass] <toggle> [pen and paper])
 // TODO: show correction: for every beat, show the note that was played, and highlight green or red for correct or wrong
 
-use std::sync::{Arc, RwLock};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{Arc, RwLock},
+};
 
 // use iced::PickList;
 use nih_plug::prelude::{Editor, GuiContext};
@@ -15,14 +19,18 @@ use walkanalysis::{
         mistake::{Mistake, MistakeKind},
     },
     form::{
+        chord_name::SymbolStyle,
         form::{Form, FormPiece},
         key,
+        meter::Meter,
         note::{Note, Spelling},
     },
 };
 
 use crate::{
     colors, fonts,
+    forms::Choruses,
+    inputs::InputKind,
     styles::{MyContainerStyle, MyPicklistStyle, MyTogglerStyle},
     ExerciseKind, FormKind,
 };
@@ -39,15 +47,23 @@ pub(crate) fn create(
 }
 
 fn ascii(str: String) -> String {
-    str.replace("♭", "b").replace("♯", "#")
+    str.replace("𝄫", "bb")
+        .replace("𝄪", "x")
+        .replace("♭", "b")
+        .replace("♯", "#")
 }
 
 /// All the state shared between audio and UI thread
 #[derive(Debug, Clone)]
 pub struct WalkanalysisSharedState {
     pub selected_form: FormKind,
+    pub selected_choruses: Choruses,
+    /// The selected form, played the selected amount of choruses
     pub form: Form,
     pub selected_exercise: ExerciseKind,
+    pub selected_input: InputKind,
+    /// Whether drums and piano are played along with the form
+    pub play_backing_track: bool,
     pub correction: Option<Correction>,
     pub analysis: Option<Analysis>,
     pub beat_pos: Option<f64>,
@@ -74,43 +90,68 @@ pub struct WalkanalysisEditor {
     show_correction_instead_of_analysis: bool,
     show_chord_tone_instead_of_note_in_analysis: bool,
     show_expected_instead_of_found_in_correction: bool,
+    show_jazz_chord_symbols: bool,
 
     context: Arc<dyn GuiContext>,
     form_selector_state: pick_list::State<FormKind>,
     exercise_selector_state: pick_list::State<ExerciseKind>,
+    choruses_selector_state: pick_list::State<Choruses>,
+    input_selector_state: pick_list::State<InputKind>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     FormSelected(FormKind),
     ExerciseSelected(ExerciseKind),
+    ChorusesSelected(Choruses),
+    InputSelected(InputKind),
     AnalysisOrCorrection(bool),
     ExpectedOrFound(bool),
     ChordToneOrDegree(bool),
+    PlainOrJazzSymbols(bool),
+    BackingTrack(bool),
+}
+
+/// The beats of one of the times a bar is played, bars within repeats are played more than once
+pub struct BarPass {
+    analyzed_beats: Option<Vec<Option<NoteAnalysis>>>,
+    correction_beats: Vec<Option<Mistake>>,
+    /// Beats the transcription was unsure of, they are marked with a question mark
+    uncertain_beats: Vec<bool>,
 }
 
-pub struct WrittenBar<'a> {
+pub struct WrittenBar {
     form_piece: FormPiece,
-    analyzed_beats: Option<&'a [Option<NoteAnalysis>; 4]>,
-    correction_beats: &'a [Option<Mistake>; 4],
+    passes: Vec<BarPass>,
+    meter: Meter,
     /// None if this bar is not being recorded right now,
     /// Some(beat) if the current recording is at beat beat of this bar.
     current_beat: Option<u32>,
 }
 
-impl<'a> WrittenBar<'a> {
+fn mark_uncertain(text: String, uncertain: bool) -> String {
+    if uncertain {
+        format!("{text}?")
+    } else {
+        text
+    }
+}
+
+impl WrittenBar {
     fn view_mistake<'b>(
         note: Option<Note>,
         mistake: Option<Mistake>,
+        uncertain: bool,
         show_expected_instead_of_found_in_correction: bool,
         spelling: Spelling,
     ) -> Element<'b, Message> {
         let Some(mistake) = mistake else {
             return Container::new(
-                Text::new(
+                Text::new(mark_uncertain(
                     note.map(|n| ascii(format!("{}", n.spell(spelling))))
                         .unwrap_or("?".into()),
-                )
+                    uncertain,
+                ))
                 .color(Color::WHITE),
             )
             .style(MyContainerStyle {
@@ -146,9 +187,12 @@ impl<'a> WrittenBar<'a> {
             _ => note,
         };
 
-        let note_text = note_to_display
-            .map(|n| ascii(format!("{}", n.spell(spelling))))
-            .unwrap_or("?".into());
+        let note_text = mark_uncertain(
+            note_to_display
+                .map(|n| ascii(format!("{}", n.spell(spelling))))
+                .unwrap_or("?".into()),
+            uncertain,
+        );
 
         let color = match mistake.mistake {
             MistakeKind::WrongNote {
@@ -162,6 +206,8 @@ impl<'a> WrittenBar<'a> {
                 played_note: _,
                 expected_example: _,
             } => colors::ORANGE,
+            MistakeKind::ExpectedOrnament { subdivision: _ } => colors::YELLOW,
+            MistakeKind::UnexpectedOrnament { subdivision: _ } => colors::YELLOW,
         };
 
         // TODO: display what the mistake was exactly somehow (color, probably?)
@@ -179,6 +225,7 @@ impl<'a> WrittenBar<'a> {
 
     fn view_note_analysis<'b>(
         analysis: &NoteAnalysis,
+        uncertain: bool,
         show_chord_tone_instead_of_note_in_analysis: bool,
         spelling: Spelling,
     ) -> Element<'b, Message> {
@@ -188,19 +235,14 @@ impl<'a> WrittenBar<'a> {
                 note,
                 degree_in_key,
                 role_in_chord,
+                ..
             } => {
                 // chord tone is shown as a number
                 // degree scale as a color:
                 // 1     2    3    4      5   6      7
                 // green cyan blue purple red orange yellow
                 // chromatic grayed out version of inbetween color
-                let chord_tone_str = match role_in_chord {
-                    walkanalysis::form::chord::ChordTone::Root => "1",
-                    walkanalysis::form::chord::ChordTone::Third => "3",
-                    walkanalysis::form::chord::ChordTone::Fifth => "5",
-                    walkanalysis::form::chord::ChordTone::Seventh => "7",
-                    walkanalysis::form::chord::ChordTone::NoChordTone => "-",
-                };
+                let chord_tone_str = role_in_chord.short_name();
 
                 let background_color = match degree_in_key {
                     key::Degree::First => colors::GREEN,
@@ -214,10 +256,11 @@ impl<'a> WrittenBar<'a> {
                 };
 
                 let beat_text = if show_chord_tone_instead_of_note_in_analysis {
-                    format!("{}", chord_tone_str)
+                    ascii(chord_tone_str.to_string())
                 } else {
                     ascii(format!("{}", note.spell(spelling)))
                 };
+                let beat_text = mark_uncertain(beat_text, uncertain);
 
                 Container::new(Text::new(beat_text).color(Color::WHITE))
                     .style(MyContainerStyle {
@@ -230,13 +273,14 @@ impl<'a> WrittenBar<'a> {
                     .center_y()
                     .into()
             }
-            NoteAnalysis::NoteDuringSilence { note: _ } => Row::new().into(), // TODO: this
+            NoteAnalysis::NoteDuringSilence { .. } => Row::new().into(), // TODO: this
         }
     }
 
     pub fn view<'b>(
         &self,
         spelling: Spelling,
+        symbol_style: SymbolStyle,
         show_correction_instead_of_analysis: bool,
         show_chord_tone_instead_of_degree_in_analysis: bool,
         show_expected_instead_of_found_in_correction: bool,
@@ -244,39 +288,84 @@ impl<'a> WrittenBar<'a> {
         const CHORD_SYMBOL_SIZE: u16 = 24;
         let chord_symbol: Element<'b, Message> = match &self.form_piece {
             FormPiece::Key(_) => unreachable!(),
+            FormPiece::Meter(_) => unreachable!(),
             FormPiece::LineBreak => unreachable!(),
             FormPiece::CountOff => unreachable!(),
+            FormPiece::Section(_)
+            | FormPiece::RepeatStart
+            | FormPiece::RepeatEnd(_)
+            | FormPiece::Ending(_)
+            | FormPiece::Segno
+            | FormPiece::Coda
+            | FormPiece::ToCoda
+            | FormPiece::DaCapo
+            | FormPiece::DalSegno
+            | FormPiece::Fine => unreachable!(),
             FormPiece::ChordBar(chord) => {
-                Text::new(ascii(format!("{}", chord.spell_symbol(spelling))))
+                Text::new(ascii(chord.styled_symbol(spelling, symbol_style)))
                     .font(fonts::EB_GARAMOND_MEDIUM)
                     .size(CHORD_SYMBOL_SIZE)
                     .into()
             } // TODO: determine sharp / flat from form
             FormPiece::HalfBar(chord1, chord2) => Row::new()
                 .push(
-                    Text::new(ascii(format!("{}", chord1.spell_symbol(spelling))))
+                    Text::new(ascii(chord1.styled_symbol(spelling, symbol_style)))
                         .font(fonts::EB_GARAMOND_MEDIUM)
                         .size(CHORD_SYMBOL_SIZE)
                         .width(Length::Fill),
                 )
                 .push(
-                    Text::new(ascii(format!("{}", chord2.spell_symbol(spelling))))
+                    Text::new(ascii(chord2.styled_symbol(spelling, symbol_style)))
                         .font(fonts::EB_GARAMOND_MEDIUM)
                         .size(CHORD_SYMBOL_SIZE)
                         .width(Length::Fill),
                 )
                 .into(),
+            FormPiece::SplitBar(chords) => chords
+                .iter()
+                .fold(Row::new(), |row, (chord, beats)| {
+                    row.push(
+                        Text::new(ascii(chord.styled_symbol(spelling, symbol_style)))
+                            .font(fonts::EB_GARAMOND_MEDIUM)
+                            .size(CHORD_SYMBOL_SIZE)
+                            .width(Length::FillPortion(*beats as u16)),
+                    )
+                })
+                .into(),
         };
 
-        let mut beats = Row::new().spacing(1).padding(Padding {
-            top: 1,
-            right: 0,
-            bottom: 1,
-            left: 0,
-        });
+        let new_beats_row = || {
+            Row::new().spacing(1).padding(Padding {
+                top: 1,
+                right: 0,
+                bottom: 1,
+                left: 0,
+            })
+        };
 
-        if let Some(analyzed_beats) = self.analyzed_beats.as_ref() {
-            for (beat, mistake) in analyzed_beats.iter().zip(self.correction_beats.iter()) {
+        let mut column = Column::new().push(chord_symbol).width(Length::Fill);
+
+        let analyzed_passes: Vec<_> = self
+            .passes
+            .iter()
+            .filter_map(|pass| {
+                pass.analyzed_beats.as_ref().map(|analyzed_beats| {
+                    (
+                        analyzed_beats,
+                        &pass.correction_beats,
+                        &pass.uncertain_beats,
+                    )
+                })
+            })
+            .collect();
+
+        for (analyzed_beats, correction_beats, uncertain_beats) in analyzed_passes.iter() {
+            let mut beats = new_beats_row();
+            for ((beat, mistake), &uncertain) in analyzed_beats
+                .iter()
+                .zip(correction_beats.iter())
+                .zip(uncertain_beats.iter())
+            {
                 let Some(beat) = beat else {
                     beats = beats.push(
                         Container::new(Text::new("?").font(fonts::ROBOTO_MONO_REGULAR))
@@ -292,19 +381,25 @@ impl<'a> WrittenBar<'a> {
                     beats = beats.push(Self::view_mistake(
                         beat.note(),
                         *mistake,
+                        uncertain,
                         show_expected_instead_of_found_in_correction,
                         spelling,
                     ));
                 } else {
                     beats = beats.push(Self::view_note_analysis(
                         beat,
+                        uncertain,
                         show_chord_tone_instead_of_degree_in_analysis,
                         spelling,
                     ));
                 }
             }
-        } else {
-            for i in 0..4 {
+            column = column.push(beats);
+        }
+
+        if analyzed_passes.is_empty() {
+            let mut beats = new_beats_row();
+            for i in 0..self.meter.beats_per_bar() {
                 beats = beats.push(
                     Container::new(Text::new(format!("{}", i + 1)).color(
                         if self.current_beat == Some(i) {
@@ -327,16 +422,22 @@ impl<'a> WrittenBar<'a> {
                     .center_y(),
                 )
             }
+            column = column.push(beats);
         }
 
-        Column::new()
-            .push(chord_symbol)
-            .push(beats)
-            .width(Length::Fill)
-            .into()
+        column.into()
     }
 }
 
+/// Structural markers such as repeat signs are shown as small text in between the bars
+fn view_marker<'a>(text: String) -> Element<'a, Message> {
+    Text::new(text)
+        .font(fonts::EB_GARAMOND_MEDIUM)
+        .size(16)
+        .color(colors::GREY)
+        .into()
+}
+
 type WalkanalysisInitializationType = Arc<RwLock<WalkanalysisSharedState>>;
 
 impl WalkanalysisEditor {
@@ -360,46 +461,86 @@ impl WalkanalysisEditor {
                 .spacing(1)
         };
 
-        let mut row = new_row();
+        // The chart is shown compactly, so every bar needs the beats of each time it is played
+        let mut passes_per_piece: HashMap<usize, Vec<Range<u32>>> = HashMap::new();
         let mut form_beat_counter: u32 = 0;
-        for form_piece in form.music() {
+        let unrolled = form.unrolled();
+        for (index, (form_piece, meter)) in form
+            .unrolled_indices()
+            .into_iter()
+            .zip(unrolled.pieces_with_meter())
+        {
+            let beats_in_piece = form_piece.length_in_beats(meter);
+            passes_per_piece
+                .entry(index)
+                .or_default()
+                .push(form_beat_counter..form_beat_counter + beats_in_piece);
+            form_beat_counter += beats_in_piece;
+        }
+
+        let mut row = new_row();
+        for (index, (form_piece, meter)) in form.pieces_with_meter().into_iter().enumerate() {
             let new_form_piece = form_piece.clone();
             match form_piece {
-                FormPiece::Key(_) => (), // TODO: display key
+                FormPiece::Key(_) => (),   // TODO: display key
+                FormPiece::Meter(_) => (), // TODO: display time signature
                 FormPiece::CountOff => {}
-                FormPiece::ChordBar(_) | FormPiece::HalfBar(_, _) => {
+                FormPiece::ChordBar(_) | FormPiece::HalfBar(_, _) | FormPiece::SplitBar(_) => {
+                    let pass_beats = passes_per_piece.remove(&index).unwrap_or_default();
+
                     let current_beat: Option<_> = current_state.beat_pos.and_then(|beat_pos| {
                         let beat_pos = beat_pos.floor() as u32;
-                        if beat_pos >= form_beat_counter && beat_pos < form_beat_counter + 4 {
-                            Some(beat_pos % 4)
-                        } else {
-                            None
-                        }
-                    });
-
-                    let beats: [u32; 4] = [
-                        form_beat_counter,
-                        form_beat_counter + 1,
-                        form_beat_counter + 2,
-                        form_beat_counter + 3,
-                    ];
-
-                    let analyzed_beats = current_state.analysis.as_ref().map(|analysis| {
-                        beats.map(|beat| analysis.beat_analysis.get(&beat).cloned().map(|n| n.1))
+                        pass_beats
+                            .iter()
+                            .find(|beats| beats.contains(&beat_pos))
+                            .map(|beats| beat_pos - beats.start)
                     });
 
-                    let correction_beats = current_state.correction.as_ref().map(|correction| {
-                        beats.map(|beat| correction.mistakes.get(&beat).copied())
-                    });
+                    let passes = pass_beats
+                        .into_iter()
+                        .map(|beats| BarPass {
+                            analyzed_beats: current_state.analysis.as_ref().map(|analysis| {
+                                beats
+                                    .clone()
+                                    .map(|beat| {
+                                        analysis.beat_analysis.get(&beat).cloned().map(|n| n.1)
+                                    })
+                                    .collect()
+                            }),
+                            uncertain_beats: beats
+                                .clone()
+                                .map(|beat| {
+                                    current_state.analysis.as_ref().is_some_and(|analysis| {
+                                        analysis
+                                            .confidence
+                                            .get(&beat)
+                                            .is_some_and(|confidence| confidence.is_uncertain())
+                                    })
+                                })
+                                .collect(),
+                            correction_beats: beats
+                                .map(|beat| {
+                                    current_state.correction.as_ref().and_then(|correction| {
+                                        correction.mistakes.get(&beat).copied()
+                                    })
+                                })
+                                .collect(),
+                        })
+                        .collect();
 
                     let bar = WrittenBar {
                         form_piece: new_form_piece,
-                        analyzed_beats: analyzed_beats.as_ref(),
-                        correction_beats: correction_beats.as_ref().unwrap_or(&[const { None }; 4]),
+                        passes,
+                        meter,
                         current_beat,
                     };
                     row = row.push(bar.view(
                         current_state.form.key().spell_preference(),
+                        if self.show_jazz_chord_symbols {
+                            SymbolStyle::Jazz
+                        } else {
+                            SymbolStyle::Plain
+                        },
                         self.show_correction_instead_of_analysis,
                         self.show_chord_tone_instead_of_note_in_analysis,
                         self.show_expected_instead_of_found_in_correction,
@@ -409,8 +550,20 @@ impl WalkanalysisEditor {
                     column = column.push(row);
                     row = new_row()
                 }
+                FormPiece::Section(label) => row = row.push(view_marker(format!("[{}]", label))),
+                FormPiece::RepeatStart => row = row.push(view_marker("|:".into())),
+                FormPiece::RepeatEnd(2) => row = row.push(view_marker(":|".into())),
+                FormPiece::RepeatEnd(times) => {
+                    row = row.push(view_marker(format!(":| x{}", times)))
+                }
+                FormPiece::Ending(number) => row = row.push(view_marker(format!("{}.", number))),
+                FormPiece::Segno => row = row.push(view_marker("Segno".into())),
+                FormPiece::Coda => row = row.push(view_marker("Coda".into())),
+                FormPiece::ToCoda => row = row.push(view_marker("To Coda".into())),
+                FormPiece::DaCapo => row = row.push(view_marker("D.C.".into())),
+                FormPiece::DalSegno => row = row.push(view_marker("D.S.".into())),
+                FormPiece::Fine => row = row.push(view_marker("Fine".into())),
             }
-            form_beat_counter += form_piece.length_in_beats();
         }
         column = column.push(row);
 
@@ -433,9 +586,12 @@ impl IcedEditor for WalkanalysisEditor {
             context,
             form_selector_state: Default::default(),
             exercise_selector_state: Default::default(),
+            choruses_selector_state: Default::default(),
+            input_selector_state: Default::default(),
             show_correction_instead_of_analysis: false,
             show_chord_tone_instead_of_note_in_analysis: true,
             show_expected_instead_of_found_in_correction: false,
+            show_jazz_chord_symbols: false,
         };
 
         (editor, Command::none())
@@ -454,13 +610,22 @@ impl IcedEditor for WalkanalysisEditor {
         match message {
             Message::FormSelected(form_kind) => {
                 state.selected_form = form_kind;
-                state.form = form_kind.form();
+                state.form = form_kind.form().with_choruses(state.selected_choruses.0);
+                state.clear();
+            }
+            Message::ChorusesSelected(choruses) => {
+                state.selected_choruses = choruses;
+                state.form = state.selected_form.form().with_choruses(choruses.0);
                 state.clear();
             }
             Message::ExerciseSelected(exercise) => {
                 state.selected_exercise = exercise;
                 state.clear();
             }
+            Message::InputSelected(input) => {
+                state.selected_input = input;
+                state.clear();
+            }
             Message::AnalysisOrCorrection(choice) => {
                 self.show_correction_instead_of_analysis = choice;
             }
@@ -470,6 +635,8 @@ impl IcedEditor for WalkanalysisEditor {
             Message::ChordToneOrDegree(choice) => {
                 self.show_chord_tone_instead_of_note_in_analysis = choice
             }
+            Message::PlainOrJazzSymbols(choice) => self.show_jazz_chord_symbols = choice,
+            Message::BackingTrack(choice) => state.play_backing_track = choice,
         }
 
         Command::none()
@@ -516,6 +683,44 @@ impl IcedEditor for WalkanalysisEditor {
         .font(fonts::EB_GARAMOND_MEDIUM)
         .style(MyPicklistStyle {});
 
+        // Dropdown to select how many choruses are played
+        let choruses_picker = PickList::new(
+            &mut self.choruses_selector_state,
+            &Choruses::ALL[..],
+            Some(current_state.selected_choruses),
+            Message::ChorusesSelected,
+        )
+        .font(fonts::EB_GARAMOND_MEDIUM)
+        .style(MyPicklistStyle {});
+
+        // Dropdown to select whether audio or MIDI notes are analyzed
+        let input_picker = PickList::new(
+            &mut self.input_selector_state,
+            &InputKind::ALL[..],
+            Some(current_state.selected_input),
+            Message::InputSelected,
+        )
+        .font(fonts::EB_GARAMOND_MEDIUM)
+        .style(MyPicklistStyle {});
+
+        // Score of every chorus, to see improvement during a take
+        let chorus_scores = Text::new(
+            current_state
+                .correction
+                .as_ref()
+                .map(|correction| {
+                    correction
+                        .chorus_scores()
+                        .iter()
+                        .enumerate()
+                        .map(|(chorus, score)| format!("{}: {:.0}%", chorus + 1, score * 100.))
+                        .collect::<Vec<_>>()
+                        .join("   ")
+                })
+                .unwrap_or_default(),
+        )
+        .size(18);
+
         // Row with dropdowns
         let mut picker_row = Row::new()
             .padding(4)
@@ -526,7 +731,10 @@ impl IcedEditor for WalkanalysisEditor {
         // Count off and recording symbol at the bottom
         let count_off_text = format!(
             "{}",
-            current_state.beat_pos.map(|b| countoff(b)).unwrap_or("")
+            current_state
+                .beat_pos
+                .map(|b| countoff(b, current_state.form.meter()))
+                .unwrap_or_default()
         );
         let count_off = Container::new(Text::new(count_off_text).color(Color::WHITE))
             .style(MyContainerStyle {
@@ -617,9 +825,61 @@ impl IcedEditor for WalkanalysisEditor {
             )
             .spacing(4);
 
+        let plain_or_jazz_toggle = Row::new()
+            .push(
+                Text::new("PLAIN")
+                    .size(18)
+                    .width(Length::Fill)
+                    .horizontal_alignment(Horizontal::Right),
+            )
+            .push(
+                Toggler::new(
+                    self.show_jazz_chord_symbols,
+                    None,
+                    Message::PlainOrJazzSymbols,
+                )
+                .style(MyTogglerStyle::default())
+                .width(Length::Shrink),
+            )
+            .push(
+                Text::new("JAZZ")
+                    .size(18)
+                    .width(Length::Fill)
+                    .horizontal_alignment(Horizontal::Left),
+            )
+            .spacing(4);
+
+        let backing_track_toggle = Row::new()
+            .push(
+                Text::new("BASS ONLY")
+                    .size(18)
+                    .width(Length::Fill)
+                    .horizontal_alignment(Horizontal::Right),
+            )
+            .push(
+                Toggler::new(
+                    current_state.play_backing_track,
+                    None,
+                    Message::BackingTrack,
+                )
+                .style(MyTogglerStyle::default())
+                .width(Length::Shrink),
+            )
+            .push(
+                Text::new("BAND")
+                    .size(18)
+                    .width(Length::Fill)
+                    .horizontal_alignment(Horizontal::Left),
+            )
+            .spacing(4);
+
         // Integrate the dropdown, toggle switch, and count off in a single menu
         if !current_state.is_recording() {
-            picker_row = picker_row.push(form_picker).push(exercise_picker);
+            picker_row = picker_row
+                .push(form_picker)
+                .push(choruses_picker)
+                .push(exercise_picker)
+                .push(input_picker);
         } else {
             picker_row = picker_row.push(count_off);
         }
@@ -630,12 +890,21 @@ impl IcedEditor for WalkanalysisEditor {
             menu_column = menu_column.push(analysis_or_correction_toggle);
 
             if self.show_correction_instead_of_analysis {
+                if current_state.selected_choruses.0 > 1 {
+                    menu_column = menu_column.push(chorus_scores);
+                }
                 menu_column = menu_column.push(expected_or_found_toggle)
             } else {
                 menu_column = menu_column.push(chord_tone_or_degree_toggle)
             }
         }
 
+        if !current_state.is_recording() {
+            menu_column = menu_column
+                .push(plain_or_jazz_toggle)
+                .push(backing_track_toggle);
+        }
+
         menu_column = menu_column.push(picker_row);
 
         Column::new()
@@ -657,17 +926,15 @@ impl IcedEditor for WalkanalysisEditor {
     }
 }
 
-fn countoff(beat_pos: f64) -> &'static str {
-    let beat_pos = beat_pos.floor() as usize;
-    match beat_pos {
-        0 => "1",
-        1 => "1",
-        2 => "2",
-        3 => "2",
-        4 => "1",
-        5 => "2",
-        6 => "3",
-        7 => "4",
-        _ => "",
+/// Counts the first bar in halves ("1, 2") and the second bar in beats ("1, 2, 3, 4")
+fn countoff(beat_pos: f64, meter: Meter) -> String {
+    let beat_pos = beat_pos.floor() as u32;
+    let beats_per_bar = meter.beats_per_bar();
+    if beat_pos < beats_per_bar {
+        format!("{}", beat_pos * 2 / beats_per_bar + 1)
+    } else if beat_pos < 2 * beats_per_bar {
+        format!("{}", beat_pos - beats_per_bar + 1)
+    } else {
+        String::new()
     }
 }
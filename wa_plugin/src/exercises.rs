@@ -4,6 +4,7 @@ use nih_plug::prelude::Enum;
 use walkanalysis::exercise::{
     arpeggios_up::ArpeggiosUp,
     chord_tones::ChordTones,
+    skips::Skips,
     two_beat::{two_beat, two_beat_fifths, two_beat_thirds},
     Exercise,
 };
@@ -15,6 +16,7 @@ pub enum ExerciseKind {
     TwoBeatFifths,
     TwoBeatThirds,
     TwoBeat,
+    Skips,
 }
 
 unsafe impl Sync for ExerciseKind {}
@@ -27,15 +29,17 @@ impl ExerciseKind {
             ExerciseKind::TwoBeatFifths => Box::new(two_beat_fifths()),
             ExerciseKind::TwoBeatThirds => Box::new(two_beat_thirds()),
             ExerciseKind::TwoBeat => Box::new(two_beat()),
+            ExerciseKind::Skips => Box::new(Skips {}),
         }
     }
 
-    pub const ALL: [ExerciseKind; 5] = [
+    pub const ALL: [ExerciseKind; 6] = [
         ExerciseKind::ArpeggiosUp,
         ExerciseKind::ChordTones,
         ExerciseKind::TwoBeatFifths,
         ExerciseKind::TwoBeatThirds,
         ExerciseKind::TwoBeat,
+        ExerciseKind::Skips,
     ];
 }
 
@@ -47,6 +51,7 @@ impl Display for ExerciseKind {
             ExerciseKind::TwoBeatFifths => write!(f, "Two Beat, Fifths"),
             ExerciseKind::TwoBeatThirds => write!(f, "Two Beat, Thirds"),
             ExerciseKind::TwoBeat => write!(f, "Two Beat, Any chord tone"),
+            ExerciseKind::Skips => write!(f, "Chord Tones with Skips"),
         }
     }
 }
@@ -51,3 +51,26 @@ impl FormKind {
 }
 
 unsafe impl Sync for FormKind {}
+
+/// How many times the form is played in one take
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub struct Choruses(pub u32);
+
+impl Default for Choruses {
+    fn default() -> Self {
+        Choruses(1)
+    }
+}
+
+impl Display for Choruses {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            1 => write!(f, "1 chorus"),
+            n => write!(f, "{} choruses", n),
+        }
+    }
+}
+
+impl Choruses {
+    pub const ALL: [Choruses; 4] = [Choruses(1), Choruses(2), Choruses(3), Choruses(4)];
+}
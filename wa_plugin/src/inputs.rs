@@ -0,0 +1,28 @@
+use std::fmt::Display;
+
+use nih_plug::prelude::Enum;
+
+/// Where the notes of a take are read from
+#[derive(Default, Debug, Enum, PartialEq, Clone, Copy, Eq)]
+pub enum InputKind {
+    /// Transcribe the audio input
+    #[default]
+    Audio,
+    /// Note events from the host, e.g. from a MIDI bass or a pitch-to-MIDI plugin
+    Midi,
+}
+
+impl Display for InputKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputKind::Audio => write!(f, "Audio"),
+            InputKind::Midi => write!(f, "MIDI"),
+        }
+    }
+}
+
+impl InputKind {
+    pub const ALL: [InputKind; 2] = [InputKind::Audio, InputKind::Midi];
+}
+
+unsafe impl Sync for InputKind {}
@@ -3,17 +3,23 @@ mod editor;
 pub mod exercises;
 pub mod fonts;
 pub mod forms;
+pub mod inputs;
 pub mod styles;
 
 use exercises::ExerciseKind;
-use forms::FormKind;
+use forms::{Choruses, FormKind};
+use inputs::InputKind;
 use nih_plug::prelude::*;
 use nih_plug_iced::IcedState;
 use std::sync::{Arc, RwLock};
 use walkanalysis::{
     analysis::analysis::Analysis,
-    form::form::Form,
-    transcribe::transcribe::{AudioSettings, Transcription, DEFAULT_SETTINGS},
+    form::{form::Form, pitch::Pitch},
+    synth::backing::BackingTrack,
+    transcribe::{
+        midi::MidiEvent,
+        transcribe::{AudioSettings, Transcription, DEFAULT_SETTINGS},
+    },
 };
 
 use crate::editor::WalkanalysisSharedState;
@@ -27,6 +33,10 @@ pub struct WalkAnalysis {
     data: DataToAnalyze,
     form_cache: Option<FormCache>,
     state: Arc<RwLock<WalkanalysisSharedState>>,
+    /// Rendered in the background, so it is ready before the user starts playing
+    backing_track: Arc<RwLock<Option<RenderedBackingTrack>>>,
+    /// The backing track that was last asked for, it might still be rendering
+    requested_backing_track: Option<BackingTrackSettings>,
 }
 
 impl WalkAnalysis {
@@ -35,6 +45,82 @@ impl WalkAnalysis {
         let mut state = self.state.write().unwrap();
         state.clear();
     }
+
+    /// Asks for a new backing track when the form or the tempo of the host changed
+    fn prepare_backing_track(&mut self, context: &mut impl ProcessContext<Self>) {
+        let Some(tempo) = context.transport().tempo else {
+            return;
+        };
+        let settings = {
+            let state = self.state.read().unwrap();
+            if !state.play_backing_track {
+                return;
+            }
+            BackingTrackSettings {
+                form: state.selected_form,
+                choruses: state.selected_choruses,
+                tempo,
+                sample_rate: context.transport().sample_rate,
+            }
+        };
+
+        if self.requested_backing_track != Some(settings) {
+            self.requested_backing_track = Some(settings);
+            context.execute_background(Task::RenderBackingTrack(settings));
+        }
+    }
+
+    /// Adds the backing track to the output, from where the host is in the form
+    fn play_backing_track(&self, buffer: &mut Buffer, context: &mut impl ProcessContext<Self>) {
+        if !self.state.read().unwrap().play_backing_track {
+            return;
+        }
+        let Some(start) = context.transport().pos_samples() else {
+            return;
+        };
+        // Still rendering, skip it rather than wait on the audio thread
+        let Ok(backing_track) = self.backing_track.try_read() else {
+            return;
+        };
+        let Some(backing_track) = backing_track
+            .as_ref()
+            .filter(|rendered| Some(rendered.settings) == self.requested_backing_track)
+        else {
+            return;
+        };
+
+        for (i, channel_samples) in buffer.iter_samples().enumerate() {
+            let Some(&sample) = usize::try_from(start + i as i64)
+                .ok()
+                .and_then(|position| backing_track.samples.get(position))
+            else {
+                continue;
+            };
+            for output in channel_samples {
+                *output += sample;
+            }
+        }
+    }
+}
+
+/// Everything the backing track depends on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackingTrackSettings {
+    form: FormKind,
+    choruses: Choruses,
+    tempo: f64,
+    sample_rate: f32,
+}
+
+pub struct RenderedBackingTrack {
+    settings: BackingTrackSettings,
+    samples: Vec<f32>,
+}
+
+/// Work that is too slow for the audio thread
+#[derive(Debug, Clone, Copy)]
+pub enum Task {
+    RenderBackingTrack(BackingTrackSettings),
 }
 
 pub struct FormCache {
@@ -54,6 +140,8 @@ pub struct DataToAnalyze {
     tempo: Option<f64>,
     last_saved_beat_pos: Option<f64>,
     samples: Vec<f32>,
+    /// Notes from the host, timed in beats since the start of the take
+    midi_events: Vec<MidiEvent>,
 }
 
 impl DataToAnalyze {
@@ -62,6 +150,7 @@ impl DataToAnalyze {
         self.tempo = None;
         self.last_saved_beat_pos = None;
         self.samples.clear();
+        self.midi_events.clear();
     }
 }
 
@@ -78,13 +167,19 @@ impl Default for WalkAnalysis {
             data: DataToAnalyze {
                 acquizition_state: DataAcquizitionState::WaitingForStart,
                 samples: Vec::with_capacity(48_000 * 60 * 3), // Allocate for 3 minutes of data, should be enough for most forms
+                midi_events: Vec::with_capacity(4096),
                 tempo: None,
                 last_saved_beat_pos: None,
             },
             form_cache: None,
+            backing_track: Arc::new(RwLock::new(None)),
+            requested_backing_track: None,
             state: Arc::new(RwLock::new(WalkanalysisSharedState {
                 selected_form: FormKind::default(),
+                selected_choruses: Choruses::default(),
                 selected_exercise: ExerciseKind::ArpeggiosUp,
+                selected_input: InputKind::default(),
+                play_backing_track: false,
                 correction: None,
                 beat_pos: None,
                 analysis: None,
@@ -120,13 +215,13 @@ impl Plugin for WalkAnalysis {
         names: PortNames::const_default(),
     }];
 
-    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
 
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
-    type BackgroundTask = ();
+    type BackgroundTask = Task;
 
     fn params(&self) -> Arc<dyn Params> {
         self.params.clone()
@@ -136,6 +231,21 @@ impl Plugin for WalkAnalysis {
         editor::create(self.state.clone(), self.params.editor_state.clone())
     }
 
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let backing_track = self.backing_track.clone();
+        Box::new(move |task| match task {
+            Task::RenderBackingTrack(settings) => {
+                let form = settings.form.form().with_choruses(settings.choruses.0);
+                let samples = BackingTrack {
+                    sample_rate: settings.sample_rate as u32,
+                    ..Default::default()
+                }
+                .render(&form, settings.tempo as f32);
+                *backing_track.write().unwrap() = Some(RenderedBackingTrack { settings, samples });
+            }
+        })
+    }
+
     fn initialize(
         &mut self,
         _audio_io_layout: &AudioIOLayout,
@@ -159,10 +269,23 @@ impl Plugin for WalkAnalysis {
         _aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
+        self.prepare_backing_track(context);
+
         if !(context.transport().playing) {
             return ProcessStatus::Normal;
         }
 
+        self.acquire(buffer, context);
+        // Only after the input is saved, the backing track must not be analyzed
+        self.play_backing_track(buffer, context);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl WalkAnalysis {
+    /// Saves the input while the form is played, and analyzes it once the form is done
+    fn acquire(&mut self, buffer: &mut Buffer, context: &mut impl ProcessContext<Self>) {
         if let DataAcquizitionState::WaitingForStart = self.data.acquizition_state {
             // determine whether to start data acquisition
             if context.transport().playing
@@ -174,9 +297,12 @@ impl Plugin for WalkAnalysis {
                     state.clear();
                 }
                 self.data.acquizition_state = DataAcquizitionState::Acquiring;
-                let current_form = self.state.read().unwrap().selected_form;
+                let (current_form, form) = {
+                    let state = self.state.read().unwrap();
+                    (state.selected_form, state.form.clone())
+                };
 
-                let form = current_form.form();
+                // Covers every chorus, acquisition only stops after the last one
                 let form_length = form.length_in_beats();
                 self.form_cache = Some(FormCache {
                     kind: current_form,
@@ -184,9 +310,8 @@ impl Plugin for WalkAnalysis {
                     length: form_length,
                 });
                 println!(
-                    "Started data acquisition for {}, {} measures",
-                    current_form,
-                    form_length / 4
+                    "Started data acquisition for {}, {} beats",
+                    current_form, form_length
                 );
             }
         }
@@ -208,7 +333,7 @@ impl Plugin for WalkAnalysis {
                             data_tempo, transport_tempo
                         );
                         self.clear();
-                        return ProcessStatus::Normal;
+                        return;
                     }
                 }
                 _ => (),
@@ -217,7 +342,7 @@ impl Plugin for WalkAnalysis {
             let Some(tempo) = self.data.tempo else {
                 println!("No tempo known at this point, cannot analyze.");
                 self.clear();
-                return ProcessStatus::Normal;
+                return;
             };
 
             // If time went backwards, throw out data
@@ -229,7 +354,35 @@ impl Plugin for WalkAnalysis {
                         last_saved_beat_pos, current_beat_pos
                     );
                     self.clear();
-                    return ProcessStatus::Normal;
+                    return;
+                }
+            }
+
+            let beats_per_second = tempo / 60.;
+            let samples_per_beat = context.transport().sample_rate / beats_per_second as f32;
+
+            // Save the incoming notes, timed from the start of the buffer
+            let buffer_start = context.transport().pos_beats().unwrap_or(0.);
+            let position = |timing: u32| buffer_start as f32 + timing as f32 / samples_per_beat;
+            while let Some(event) = context.next_event() {
+                match event {
+                    NoteEvent::NoteOn {
+                        timing,
+                        note,
+                        velocity,
+                        ..
+                    } => self.data.midi_events.push(MidiEvent::NoteOn {
+                        position: position(timing),
+                        pitch: Pitch::from_midi(note as i32),
+                        velocity: (velocity * 127.).round() as u8,
+                    }),
+                    NoteEvent::NoteOff { timing, note, .. } => {
+                        self.data.midi_events.push(MidiEvent::NoteOff {
+                            position: position(timing),
+                            pitch: Pitch::from_midi(note as i32),
+                        })
+                    }
+                    _ => (),
                 }
             }
 
@@ -245,11 +398,9 @@ impl Plugin for WalkAnalysis {
             let Some(ref form_cache) = self.form_cache else {
                 println!("No form cache found even though state is acquiring. Deleting data.");
                 self.clear();
-                return ProcessStatus::Normal;
+                return;
             };
 
-            let beats_per_second = tempo / 60.;
-            let samples_per_beat = context.transport().sample_rate / beats_per_second as f32;
             let form_length_in_samples =
                 (form_cache.length as f32 * samples_per_beat).ceil() as usize;
 
@@ -257,14 +408,25 @@ impl Plugin for WalkAnalysis {
                 println!("Finished data acquisition for {:?}", form_cache.kind);
                 self.data.acquizition_state = DataAcquizitionState::WaitingForStart;
 
-                let (transcription, _) = Transcription::transcribe(
-                    &self.data.samples,
-                    tempo as f32,
-                    DEFAULT_SETTINGS,
-                    AudioSettings {
-                        sample_rate: context.transport().sample_rate as u32,
-                    },
-                );
+                // Samples are always saved, they keep track of the length of the take
+                let input = self.state.read().unwrap().selected_input;
+                let transcription = match input {
+                    InputKind::Audio => {
+                        Transcription::transcribe(
+                            &self.data.samples,
+                            tempo as f32,
+                            DEFAULT_SETTINGS,
+                            AudioSettings {
+                                sample_rate: context.transport().sample_rate as u32,
+                            },
+                        )
+                        .0
+                    }
+                    InputKind::Midi => {
+                        Transcription::transcribe_midi_events(self.data.midi_events.iter().copied())
+                            .0
+                    }
+                };
 
                 let analysis = Analysis::analyze(transcription, &form_cache.form);
                 let correction = self
@@ -286,8 +448,6 @@ impl Plugin for WalkAnalysis {
                 }
             }
         }
-
-        ProcessStatus::Normal
     }
 }
 
@@ -5,16 +5,28 @@ use crate::{
         chord::{self, Chord},
         form::{Form, FormPiece},
         key,
+        meter::BeatPosition,
         note::Note,
+        pitch::Pitch,
     },
-    transcribe::transcribe::{PlayedNote, Transcription},
+    transcribe::transcribe::{Confidence, PlayedNote, Subdivision, Transcription},
 };
 
+/// A pitch the transcription was at least this likely to have heard might have been played
+const BENEFIT_OF_THE_DOUBT: f32 = 0.25;
+
 #[derive(Debug, Clone)]
 pub struct Analysis {
     /// Maps beats to a note analysis
     pub beat_analysis: HashMap<u32, (FormPiece, NoteAnalysis)>,
     pub form_analysis: Vec<(FormPiece, Vec<NoteAnalysis>)>,
+    /// Maps beats to their bar and beat in the bar
+    pub beat_positions: HashMap<u32, BeatPosition>,
+    /// How sure the transcription is of the note on a beat, beats without one are sure
+    pub confidence: HashMap<u32, Confidence>,
+    /// Maps beats to the notes played between them and the next beat, in the order
+    /// they were played
+    pub ornaments: HashMap<u32, Vec<OrnamentAnalysis>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -22,79 +34,143 @@ pub enum NoteAnalysis {
     Silence,
     Note {
         note: Note,
+        /// The note as it was played, including the octave
+        pitch: Pitch,
         degree_in_key: key::Degree,
         role_in_chord: chord::ChordTone,
     },
     NoteDuringSilence {
         note: Note,
+        pitch: Pitch,
     },
 }
+/// A note played between two beats, analyzed in the context of the beat before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrnamentAnalysis {
+    pub subdivision: Subdivision,
+    /// None for a ghost note, that has no clear pitch
+    pub note: Option<NoteAnalysis>,
+}
+
 impl NoteAnalysis {
     pub fn note(&self) -> Option<Note> {
         match self {
             NoteAnalysis::Silence => None,
-            NoteAnalysis::Note {
-                note,
-                degree_in_key: _,
-                role_in_chord: _,
-            } => Some(*note),
-            NoteAnalysis::NoteDuringSilence { note } => Some(*note),
+            NoteAnalysis::Note { note, .. } => Some(*note),
+            NoteAnalysis::NoteDuringSilence { note, .. } => Some(*note),
+        }
+    }
+
+    pub fn pitch(&self) -> Option<Pitch> {
+        match self {
+            NoteAnalysis::Silence => None,
+            NoteAnalysis::Note { pitch, .. } => Some(*pitch),
+            NoteAnalysis::NoteDuringSilence { pitch, .. } => Some(*pitch),
         }
     }
 }
 
 impl Analysis {
+    /// Whether a note that fits could have been played on the beat, even though another
+    /// note was heard, so it is not counted as a mistake
+    pub fn might_have_played(&self, beat: u32, fits: impl Fn(Note) -> bool) -> bool {
+        self.confidence.get(&beat).is_some_and(|confidence| {
+            confidence.candidates.iter().any(|candidate| {
+                candidate.likelihood >= BENEFIT_OF_THE_DOUBT && fits(candidate.pitch.note())
+            })
+        })
+    }
+
     /// Analyzes the roles of the notes played according to the transcription
-    /// in the key and chord context of the given form
+    /// in the key and chord context of the given form, with its repeats and choruses unrolled
     pub fn analyze(transcription: Transcription, form: &Form) -> Analysis {
+        // Positions are taken before unrolling, the unrolled form no longer knows its choruses
+        let beat_positions: HashMap<u32, BeatPosition> = (0..).zip(form.beat_positions()).collect();
+        let form = &form.unrolled();
+
         let mut beat_analysis = HashMap::new();
         let mut form_analysis = vec![];
 
         let mut key = form.key().unwrite();
         let mut note_iter = transcription.notes.iter();
+        let confidence = (0..).zip(transcription.confidence).collect();
+        let mut ornaments: HashMap<u32, Vec<OrnamentAnalysis>> = HashMap::new();
 
         let mut beat_number = 0;
-        for form_piece in form.music().iter() {
+        for (form_piece, meter) in form.pieces_with_meter() {
             if let FormPiece::Key(new_key) = form_piece {
                 key = *new_key
             }
 
             let notes_in_this_form_piece: Vec<_> = note_iter
                 .by_ref()
-                .take(form_piece.length_in_beats() as usize)
+                .take(form_piece.length_in_beats(meter) as usize)
                 .collect();
 
-            let analyze_with_chord = |note, chord: &Chord| NoteAnalysis::Note {
-                note,
-                degree_in_key: key.role(note),
-                role_in_chord: chord.role(note),
+            let analyze_with_chord = |pitch: Pitch, chord: &Chord| {
+                let note = pitch.note();
+                NoteAnalysis::Note {
+                    note,
+                    pitch,
+                    degree_in_key: key.role(note),
+                    role_in_chord: chord.role(note),
+                }
+            };
+
+            let analyze = |note: PlayedNote, beat_in_bar: u32| match note {
+                PlayedNote::Surely(pitch) => match form_piece {
+                    FormPiece::CountOff => NoteAnalysis::NoteDuringSilence {
+                        note: pitch.note(),
+                        pitch,
+                    },
+                    // A split bar without chords has no chord to play over
+                    FormPiece::ChordBar(_) | FormPiece::HalfBar(_, _) | FormPiece::SplitBar(_) => {
+                        match form_piece.chord_at(beat_in_bar, meter) {
+                            Some(chord) => analyze_with_chord(pitch, chord),
+                            None => NoteAnalysis::NoteDuringSilence {
+                                note: pitch.note(),
+                                pitch,
+                            },
+                        }
+                    }
+                    // Only bars and the count off last any beats
+                    _ => unreachable!(),
+                },
+                PlayedNote::Silence => match form_piece {
+                    FormPiece::CountOff => NoteAnalysis::Silence,
+                    FormPiece::ChordBar(_) => NoteAnalysis::Silence,
+                    FormPiece::HalfBar(_, _) => NoteAnalysis::Silence,
+                    FormPiece::SplitBar(_) => NoteAnalysis::Silence,
+                    _ => unreachable!(),
+                },
+                PlayedNote::Unknown => NoteAnalysis::Silence,
             };
 
             let analyses = notes_in_this_form_piece
                 .iter()
-                .map(|&&note| match note {
-                    PlayedNote::Surely(note) => match form_piece {
-                        FormPiece::Key(_) => unreachable!(),
-                        FormPiece::CountOff => NoteAnalysis::NoteDuringSilence { note },
-                        FormPiece::ChordBar(chord) => analyze_with_chord(note, chord),
-                        FormPiece::HalfBar(chord1, chord2) => match beat_number % 4 {
-                            0 | 1 => analyze_with_chord(note, chord1),
-                            2 | 3 => analyze_with_chord(note, chord2),
-                            _ => unreachable!(),
-                        },
-                        FormPiece::LineBreak => unreachable!(),
-                    },
-                    PlayedNote::Silence => match form_piece {
-                        FormPiece::Key(_) => unreachable!(),
-                        FormPiece::CountOff => NoteAnalysis::Silence,
-                        FormPiece::ChordBar(_) => NoteAnalysis::Silence,
-                        FormPiece::HalfBar(_, _) => NoteAnalysis::Silence,
-                        FormPiece::LineBreak => unreachable!(),
-                    },
-                    PlayedNote::Unknown => NoteAnalysis::Silence,
-                })
+                .zip(0..)
+                .map(|(&&note, beat_in_bar)| analyze(note, beat_in_bar))
                 .collect::<Vec<_>>();
 
+            for ornament in transcription.ornaments.iter() {
+                let Some(beat_in_bar) = ornament.beat.checked_sub(beat_number) else {
+                    continue;
+                };
+                if beat_in_bar >= analyses.len() as u32 {
+                    continue;
+                }
+                ornaments
+                    .entry(ornament.beat)
+                    .or_default()
+                    .push(OrnamentAnalysis {
+                        subdivision: ornament.subdivision,
+                        note: match ornament.note {
+                            PlayedNote::Surely(_) => Some(analyze(ornament.note, beat_in_bar)),
+                            PlayedNote::Unknown | PlayedNote::Silence => None,
+                        },
+                    });
+            }
+
             form_analysis.push((form_piece.clone(), analyses.clone()));
 
             for analysis in analyses.into_iter() {
@@ -106,6 +182,9 @@ impl Analysis {
         Self {
             beat_analysis,
             form_analysis,
+            beat_positions,
+            confidence,
+            ornaments,
         }
     }
 }
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, fmt::Display};
 
-use super::mistake::Mistake;
+use super::{analysis::Analysis, mistake::Mistake};
 
 /// To what extent an analysis of a transcription conformes to the exercise
 #[derive(Debug, Clone)]
@@ -9,12 +9,47 @@ pub struct Correction {
     /// All beats where something incorrect was played
     /// Maps beat to mistake (mistakes also save the beat, so there's some double administration that needs to be done correctly)
     pub mistakes: HashMap<u32, Mistake>,
+    /// Amount of beats in every chorus, the count off is counted with the first chorus
+    pub beats_per_chorus: Vec<usize>,
 }
 
 impl Correction {
+    pub fn new(analysis: &Analysis, mistakes: HashMap<u32, Mistake>) -> Self {
+        let mut beats_per_chorus = vec![];
+        for position in analysis.beat_positions.values() {
+            let chorus = position.chorus as usize;
+            if beats_per_chorus.len() <= chorus {
+                beats_per_chorus.resize(chorus + 1, 0);
+            }
+            beats_per_chorus[chorus] += 1;
+        }
+
+        Self {
+            amount_of_beats: analysis.beat_analysis.len(),
+            mistakes,
+            beats_per_chorus,
+        }
+    }
+
     pub fn score(&self) -> f32 {
         1.0 - (self.mistakes.len() as f32 / self.amount_of_beats as f32)
     }
+
+    /// The score of every chorus separately, to see how playing improved during a take
+    pub fn chorus_scores(&self) -> Vec<f32> {
+        self.beats_per_chorus
+            .iter()
+            .zip(0..)
+            .map(|(&beats, chorus)| {
+                let mistakes = self
+                    .mistakes
+                    .values()
+                    .filter(|mistake| mistake.position.chorus == chorus)
+                    .count();
+                1.0 - (mistakes as f32 / beats as f32)
+            })
+            .collect()
+    }
 }
 
 impl Display for Correction {
@@ -26,6 +61,11 @@ impl Display for Correction {
         for mistake in sortable.iter() {
             writeln!(f, "{}", mistake)?
         }
+        if self.beats_per_chorus.len() > 1 {
+            for (chorus, score) in self.chorus_scores().iter().enumerate() {
+                writeln!(f, "Chorus {}: {:.1}% correct.", chorus + 1, score * 100.)?;
+            }
+        }
         writeln!(f, "{:.1}% correct.", self.score() * 100.)?;
         Ok(())
     }
@@ -1,25 +1,22 @@
 use std::fmt::Display;
 
-use crate::form::{chord::ChordTone, note::Note};
+use crate::{
+    form::{chord::ChordTone, meter::BeatPosition, note::Note},
+    transcribe::transcribe::Subdivision,
+};
 
 use super::analysis::NoteAnalysis;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Mistake {
     pub beat: u32,
+    pub position: BeatPosition,
     pub mistake: MistakeKind,
 }
 
 impl Display for Mistake {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "[{}.{} | {}] {}",
-            self.beat / 4 + 1,
-            self.beat % 4 + 1,
-            self.beat,
-            self.mistake
-        )
+        write!(f, "[{} | {}] {}", self.position, self.beat, self.mistake)
     }
 }
 
@@ -40,6 +37,14 @@ pub enum MistakeKind {
         played_note: Note,
         expected_example: Note, // A random note that would've been correct
     },
+    /// A note between this beat and the next was asked for, but not played
+    ExpectedOrnament {
+        subdivision: Subdivision,
+    },
+    /// A note between this beat and the next was played where the exercise does not allow it
+    UnexpectedOrnament {
+        subdivision: Subdivision,
+    },
 }
 
 impl Display for MistakeKind {
@@ -68,6 +73,12 @@ impl Display for MistakeKind {
                 played_note.flat(),
                 expected_example.flat()
             )?,
+            MistakeKind::ExpectedOrnament { subdivision } => {
+                write!(f, "Expected a note on the {} after the beat", subdivision)?
+            }
+            MistakeKind::UnexpectedOrnament { subdivision } => {
+                write!(f, "Expected no note on the {} after the beat", subdivision)?
+            }
         }
         Ok(())
     }
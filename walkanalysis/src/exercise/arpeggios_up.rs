@@ -7,63 +7,97 @@ use crate::{
         mistake::{Mistake, MistakeKind},
     },
     exercise::Exercise,
-    form::chord::ChordTone,
+    form::{
+        chord::{Chord, ChordTone},
+        form::Form,
+        meter::BeatPosition,
+        note::Note,
+    },
+    transcribe::transcribe::PlayedNote,
 };
 
-use super::common_mistakes;
+use super::{above, common_mistakes, form_beats, lowest};
 
 pub struct ArpeggiosUp {}
 
+impl ArpeggiosUp {
+    fn expected_role(position: BeatPosition, chord: &Chord) -> ChordTone {
+        match position.beat_in_bar {
+            0 => ChordTone::Bass,
+            1 => ChordTone::Third,
+            2 => ChordTone::Fifth,
+            3 => {
+                if chord.has_seventh() {
+                    ChordTone::Seventh
+                } else {
+                    ChordTone::Root
+                }
+            }
+            // Bars longer than four beats start over from the root
+            _ => ChordTone::Root,
+        }
+    }
+
+    /// Chords without the expected role, such as sus and power chords, play the root instead
+    fn expected_note(position: BeatPosition, chord: &Chord) -> Note {
+        chord
+            .note(Self::expected_role(position, chord))
+            .unwrap_or(chord.root())
+    }
+}
+
 impl Exercise for ArpeggiosUp {
     fn explain(&self) -> String {
-        "On every first beat, play the root of the chord. On every second beat the third, then the fifth, then the seventh. If the chord defines no seventh, play the root again.".into()
+        "On every first beat, play the root of the chord, or the bass note of a slash chord. On every second beat the third, then the fifth, then the seventh. If the chord defines no seventh, play the root again.".into()
     }
 
     fn correct(&mut self, analysis: &Analysis) -> Correction {
         let mut mistakes = HashMap::new();
 
         for (&beat, (form_piece, note_analysis)) in analysis.beat_analysis.iter() {
-            let Some((note, _degree, chord_tone, chord)) =
-                common_mistakes(&mut mistakes, beat, form_piece, *note_analysis)
+            let position = analysis.beat_positions[&beat];
+            let Some((note, _degree, _chord_tone, chord)) =
+                common_mistakes(&mut mistakes, beat, position, form_piece, *note_analysis)
             else {
                 continue;
             };
 
-            let beat_in_bar = beat % 4;
-            let correct_role_in_chord = match beat_in_bar {
-                0 => ChordTone::Root,
-                1 => ChordTone::Third,
-                2 => ChordTone::Fifth,
-                3 => {
-                    if chord.has_seventh() {
-                        ChordTone::Seventh
-                    } else {
-                        ChordTone::Root
-                    }
-                }
-                _ => unreachable!(),
-            };
+            let expected = Self::expected_note(position, chord);
 
-            if chord_tone != correct_role_in_chord {
+            if note != expected && !analysis.might_have_played(beat, |note| note == expected) {
                 let mistake = Mistake {
                     beat,
+                    position,
                     mistake: MistakeKind::WrongNote {
                         played: note,
-                        expected: chord.note(correct_role_in_chord).expect(&format!(
-                            "Expect {}/{} to have a {:?}",
-                            chord.sharp(),
-                            chord.flat(),
-                            correct_role_in_chord
-                        )),
+                        expected,
                     },
                 };
                 mistakes.insert(beat, mistake);
             }
         }
 
-        Correction {
-            amount_of_beats: analysis.beat_analysis.len(),
-            mistakes,
-        }
+        Correction::new(analysis, mistakes)
+    }
+
+    fn generate(&self, form: &Form) -> Vec<PlayedNote> {
+        let mut previous = None;
+        form_beats(form)
+            .iter()
+            .map(|beat| {
+                let Some(chord) = &beat.chord else {
+                    return PlayedNote::Silence;
+                };
+                let role = Self::expected_role(beat.position, chord);
+                let note = Self::expected_note(beat.position, chord);
+
+                let pitch = match previous {
+                    Some(previous) if role != ChordTone::Bass => above(note, previous),
+                    _ => lowest(note),
+                };
+                previous = Some(pitch);
+                PlayedNote::Surely(pitch)
+            })
+            .collect()
     }
 }
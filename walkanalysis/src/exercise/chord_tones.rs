@@ -8,52 +8,60 @@ use crate::{
         mistake::{Mistake, MistakeKind},
     },
     exercise::Exercise,
-    form::chord::ChordTone,
+    form::{chord::ChordTone, form::Form},
+    transcribe::transcribe::PlayedNote,
 };
 
-use super::common_mistakes;
+use super::{above, common_mistakes, form_beats, lowest};
 
 pub struct ChordTones {}
 
 impl Exercise for ChordTones {
     fn explain(&self) -> String {
-        "Play the root on the 1, and any chord tone on any beat.".into()
+        "Play the root (or the bass note of a slash chord) on the 1, and any chord tone on any beat.".into()
     }
 
     fn correct(&mut self, analysis: &Analysis) -> Correction {
         let mut mistakes = HashMap::new();
 
         for (&beat, (form_piece, note_analysis)) in analysis.beat_analysis.iter() {
+            let position = analysis.beat_positions[&beat];
             let Some((note, _degree, chord_tone, chord)) =
-                common_mistakes(&mut mistakes, beat, form_piece, *note_analysis)
+                common_mistakes(&mut mistakes, beat, position, form_piece, *note_analysis)
             else {
                 continue;
             };
 
-            let beat_in_bar = beat % 4;
-            if beat_in_bar == 0 {
-                if chord_tone != ChordTone::Root {
+            if position.is_downbeat() {
+                if note != chord.bass()
+                    && !analysis.might_have_played(beat, |note| note == chord.bass())
+                {
                     mistakes.insert(
                         beat,
                         Mistake {
                             beat,
+                            position,
                             mistake: MistakeKind::WrongNote {
                                 played: note,
-                                expected: chord.note(ChordTone::Root).unwrap(), // TODO: exercise incompatibility with a form causes unwraps like these to trigger
+                                expected: chord.bass(),
                             },
                         },
                     );
                 }
             } else {
-                if chord_tone == ChordTone::NoChordTone {
+                if chord_tone == ChordTone::NoChordTone
+                    && !analysis
+                        .might_have_played(beat, |note| chord.role(note) != ChordTone::NoChordTone)
+                {
                     mistakes.insert(
                         beat,
                         Mistake {
                             beat,
+                            position,
                             mistake: MistakeKind::ExpectedChordTone {
                                 played_chord_tone: chord_tone,
                                 played_note: note,
-                                expected_example: *chord.notes.choose(&mut rand::rng()).unwrap(),
+                                expected_example: *chord.notes().choose(&mut rand::rng()).unwrap(),
                             },
                         },
                     );
@@ -61,9 +69,29 @@ impl Exercise for ChordTones {
             }
         }
 
-        Correction {
-            amount_of_beats: analysis.beat_analysis.len(),
-            mistakes,
-        }
+        Correction::new(analysis, mistakes)
+    }
+
+    /// Walks up from the bass note through the chord tones, for as long as the chord lasts
+    fn generate(&self, form: &Form) -> Vec<PlayedNote> {
+        let mut previous = None;
+        form_beats(form)
+            .iter()
+            .map(|beat| {
+                let Some(chord) = &beat.chord else {
+                    return PlayedNote::Silence;
+                };
+                let notes = chord.notes();
+
+                let pitch = match previous {
+                    Some(previous) if beat.beat_in_chord != 0 => {
+                        above(notes[beat.beat_in_chord as usize % notes.len()], previous)
+                    }
+                    _ => lowest(chord.bass()),
+                };
+                previous = Some(pitch);
+                PlayedNote::Surely(pitch)
+            })
+            .collect()
     }
 }
@@ -8,15 +8,20 @@ use crate::{
     },
     form::{
         chord::{Chord, ChordTone},
-        form::FormPiece,
+        form::{Form, FormPiece},
         key::Degree,
+        meter::BeatPosition,
         note::Note,
+        pitch::Pitch,
     },
+    transcribe::transcribe::{Ornament, PlayedNote},
 };
 
 pub mod arpeggios_up;
 pub mod chord_tones;
+pub mod skips;
 pub mod two_beat;
+pub mod written_line;
 
 pub trait Exercise {
     /// User facing explanation about what must be played for a perfect score
@@ -24,11 +29,78 @@ pub trait Exercise {
     /// Correction function: given an analysis of a transcription, how many beats fit
     /// both the form and the exercise?
     fn correct(&mut self, analysis: &Analysis) -> Correction;
+    /// A line that would score 100%, with a note for every beat of the form as it is
+    /// played. Notes that are held are repeated on every beat, as a transcription hears them.
+    fn generate(&self, form: &Form) -> Vec<PlayedNote>;
+    /// The notes between the beats that complete the generated line, for exercises
+    /// that ask for them
+    fn generate_ornaments(&self, _form: &Form) -> Vec<Ornament> {
+        vec![]
+    }
+}
+
+/// Generated lines start every chord from the low E string
+pub const LOWEST_NOTE: Pitch = Pitch::from_midi(28);
+
+/// A beat of the form as it is played, to generate a line over
+pub struct FormBeat {
+    pub position: BeatPosition,
+    /// None during the count off
+    pub chord: Option<Chord>,
+    /// Beats since the chord started, starting over at every bar line
+    pub beat_in_chord: u32,
+}
+
+/// Every beat of the form with its repeats and choruses unrolled, starting with the count off
+pub fn form_beats(form: &Form) -> Vec<FormBeat> {
+    let mut positions = form.beat_positions().into_iter();
+    let mut beats: Vec<FormBeat> = vec![];
+
+    for (form_piece, meter) in form.unrolled().pieces_with_meter() {
+        for beat_in_piece in 0..form_piece.length_in_beats(meter) {
+            let Some(position) = positions.next() else {
+                break;
+            };
+            let chord = form_piece
+                .chord_at(beat_in_piece % meter.beats_per_bar(), meter)
+                .cloned();
+            let beat_in_chord = match beats.last() {
+                Some(previous) if !position.is_downbeat() && previous.chord == chord => {
+                    previous.beat_in_chord + 1
+                }
+                _ => 0,
+            };
+
+            beats.push(FormBeat {
+                position,
+                chord,
+                beat_in_chord,
+            });
+        }
+    }
+
+    beats
+}
+
+/// The first pitch of the note above the previous one, to walk up through a chord
+pub fn above(note: Note, previous: Pitch) -> Pitch {
+    let pitch = note.at_octave(previous.octave());
+    if pitch <= previous {
+        pitch.add_steps(12)
+    } else {
+        pitch
+    }
+}
+
+/// The lowest pitch of the note that can be played on a bass
+pub fn lowest(note: Note) -> Pitch {
+    above(note, LOWEST_NOTE.add_steps(-1))
 }
 
 pub fn common_mistakes<'a>(
     mistakes: &mut HashMap<u32, Mistake>,
     beat: u32,
+    position: BeatPosition,
     form_piece: &'a FormPiece,
     note_analysis: NoteAnalysis,
 ) -> Option<(Note, Degree, ChordTone, &'a Chord)> {
@@ -37,12 +109,14 @@ pub fn common_mistakes<'a>(
             note,
             degree_in_key: _degree_in_key,
             role_in_chord,
+            ..
         } = note_analysis
         else {
             mistakes.insert(
                 beat,
                 Mistake {
                     beat: beat,
+                    position,
                     mistake: MistakeKind::ExpectedNote {
                         found: note_analysis,
                     },
@@ -62,6 +136,7 @@ pub fn common_mistakes<'a>(
                     beat,
                     Mistake {
                         beat,
+                        position,
                         mistake: MistakeKind::ExpectedSilence {
                             found: note_analysis,
                         },
@@ -70,13 +145,8 @@ pub fn common_mistakes<'a>(
             };
             None
         }
-        FormPiece::ChordBar(chord) => is_note_or_mistake().map(|(n, d, c)| (n, d, c, chord)),
-        FormPiece::HalfBar(chord1, chord2) => {
-            let active_chord = match beat % 4 {
-                0 | 1 => chord1,
-                2 | 3 => chord2,
-                _ => unreachable!(),
-            };
+        FormPiece::ChordBar(_) | FormPiece::HalfBar(_, _) | FormPiece::SplitBar(_) => {
+            let active_chord = form_piece.chord_at(position.beat_in_bar, position.meter)?;
 
             is_note_or_mistake().map(|(n, d, c)| (n, d, c, active_chord))
         }
@@ -0,0 +1,99 @@
+use crate::{
+    analysis::{
+        analysis::Analysis,
+        correction::Correction,
+        mistake::{Mistake, MistakeKind},
+    },
+    exercise::{chord_tones::ChordTones, Exercise},
+    form::{chord::Chord, form::Form},
+    transcribe::transcribe::{Ornament, PlayedNote, Subdivision},
+};
+
+use super::form_beats;
+
+/// The swung eighth note the skip is played on
+const SKIP: Subdivision = Subdivision::SecondTriplet;
+
+/// Chord tones on the beats, like `ChordTones`, with a skip leading into every chord change.
+/// Triplets are allowed between any beats, straight eighth notes are not.
+pub struct Skips {}
+
+impl Skips {
+    fn chord_at(analysis: &Analysis, beat: u32) -> Option<&Chord> {
+        let (form_piece, _) = analysis.beat_analysis.get(&beat)?;
+        let position = analysis.beat_positions.get(&beat)?;
+        form_piece.chord_at(position.beat_in_bar, position.meter)
+    }
+}
+
+impl Exercise for Skips {
+    fn explain(&self) -> String {
+        "Play the root (or the bass note of a slash chord) on the 1, and any chord tone on any beat. Lead into every new chord with a skip: an extra note on the swung eighth before the chord changes. Triplets are allowed anywhere, but swing the eighth notes.".into()
+    }
+
+    fn correct(&mut self, analysis: &Analysis) -> Correction {
+        let mut mistakes = ChordTones {}.correct(analysis).mistakes;
+
+        for &beat in analysis.beat_analysis.keys() {
+            let Some(chord) = Self::chord_at(analysis, beat) else {
+                continue;
+            };
+            let position = analysis.beat_positions[&beat];
+            let ornaments = analysis
+                .ornaments
+                .get(&beat)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            let chord_changes =
+                Self::chord_at(analysis, beat + 1).is_some_and(|next| next != chord);
+            let mistake = if chord_changes
+                && !ornaments
+                    .iter()
+                    .any(|ornament| ornament.subdivision == SKIP)
+            {
+                Some(MistakeKind::ExpectedOrnament { subdivision: SKIP })
+            } else {
+                ornaments
+                    .iter()
+                    .find(|ornament| ornament.subdivision == Subdivision::Eighth)
+                    .map(|ornament| MistakeKind::UnexpectedOrnament {
+                        subdivision: ornament.subdivision,
+                    })
+            };
+
+            // A wrong note on the beat is the bigger mistake
+            if let Some(mistake) = mistake {
+                mistakes.entry(beat).or_insert(Mistake {
+                    beat,
+                    position,
+                    mistake,
+                });
+            }
+        }
+
+        Correction::new(analysis, mistakes)
+    }
+
+    fn generate(&self, form: &Form) -> Vec<PlayedNote> {
+        ChordTones {}.generate(form)
+    }
+
+    /// The note on the beat before the chord changes, played again on the skip
+    fn generate_ornaments(&self, form: &Form) -> Vec<Ornament> {
+        let line = self.generate(form);
+        let beats = form_beats(form);
+        beats
+            .windows(2)
+            .zip(0..)
+            .filter(|(pair, _)| {
+                pair[0].chord.is_some() && pair[1].chord.is_some() && pair[0].chord != pair[1].chord
+            })
+            .map(|(_, beat)| Ornament {
+                beat,
+                subdivision: SKIP,
+                note: line[beat as usize],
+            })
+            .collect()
+    }
+}
@@ -8,10 +8,14 @@ use crate::{
         mistake::{Mistake, MistakeKind},
     },
     exercise::Exercise,
-    form::chord::ChordTone,
+    form::{
+        chord::{Chord, ChordTone},
+        form::Form,
+    },
+    transcribe::transcribe::PlayedNote,
 };
 
-use super::common_mistakes;
+use super::{above, common_mistakes, form_beats, lowest};
 
 pub fn two_beat_thirds() -> TwoBeat {
     TwoBeat {
@@ -33,6 +37,24 @@ pub struct TwoBeat {
     pub allowed_chord_tones_in_second_half: Vec<ChordTone>,
 }
 
+impl TwoBeat {
+    /// The allowed chord tones the chord has. Chords without any of them, such as
+    /// a sus chord when thirds are asked for, take the root instead.
+    fn second_half_tones(&self, chord: &Chord) -> Vec<ChordTone> {
+        let tones: Vec<ChordTone> = self
+            .allowed_chord_tones_in_second_half
+            .iter()
+            .copied()
+            .filter(|&tone| chord.note(tone).is_some())
+            .collect();
+        if tones.is_empty() {
+            vec![ChordTone::Root]
+        } else {
+            tones
+        }
+    }
+}
+
 impl Exercise for TwoBeat {
     fn explain(&self) -> String {
         let second_half_explanation = if self.allowed_chord_tones_in_second_half.len() == 1 {
@@ -49,61 +71,60 @@ impl Exercise for TwoBeat {
                 .iter()
                 .fold("".to_string(), |acc, tone| format!("{}, {}", acc, tone));
             let trimmed = &allowed_tones[..allowed_tones.len() - 1];
-            format!("The second must be either {trimmed}").into()
+            format!("The second must be either {trimmed}")
         };
 
-        format!("Play two half notes per measure. The first half note must be the root. {second_half_explanation}").into()
+        format!("Play two half notes per measure. The first half note must be the root, or the bass note of a slash chord. {second_half_explanation}")
     }
 
     fn correct(&mut self, analysis: &Analysis) -> Correction {
         let mut mistakes = HashMap::new();
 
         for (&beat, (form_piece, note_analysis)) in analysis.beat_analysis.iter() {
+            let position = analysis.beat_positions[&beat];
             let Some((note, _degree, chord_tone, chord)) =
-                common_mistakes(&mut mistakes, beat, form_piece, *note_analysis)
+                common_mistakes(&mut mistakes, beat, position, form_piece, *note_analysis)
             else {
                 continue;
             };
 
-            let beat_in_bar = beat % 4;
-            if beat_in_bar == 0 {
+            if position.is_downbeat() {
                 for b in 0..1 {
-                    if chord_tone != ChordTone::Root {
+                    if note != chord.bass()
+                        && !analysis.might_have_played(beat, |note| note == chord.bass())
+                    {
                         mistakes.insert(
                             beat + b,
                             Mistake {
                                 beat: beat + b,
+                                position,
                                 mistake: MistakeKind::WrongNote {
                                     played: note,
-                                    expected: chord.note(ChordTone::Root).unwrap(), // TODO: exercise incompatibility with a form causes unwraps like these to trigger
+                                    expected: chord.bass(),
                                 },
                             },
                         );
                     }
                 }
-            } else if beat_in_bar == 2 {
-                if !self
-                    .allowed_chord_tones_in_second_half
-                    .contains(&chord_tone)
+            } else if position.beat_in_bar == position.meter.second_half_start() {
+                let tones = self.second_half_tones(chord);
+                let allowed = |chord_tone| tones.contains(&chord_tone);
+                if !allowed(chord_tone)
+                    && !analysis.might_have_played(beat, |note| allowed(chord.role(note)))
                 {
                     for b in 0..1 {
                         mistakes.insert(
                             beat + b,
                             Mistake {
                                 beat: beat + b,
+                                position,
                                 mistake: MistakeKind::ExpectedChordTone {
                                     played_chord_tone: chord_tone,
                                     played_note: note,
-                                    expected_example: chord
-                                        .note(
-                                            **self
-                                                .allowed_chord_tones_in_second_half
-                                                .iter()
-                                                .collect::<Vec<_>>()
-                                                .choose(&mut rand::rng())
-                                                .unwrap(),
-                                        )
-                                        .unwrap(),
+                                    expected_example: tones
+                                        .choose(&mut rand::rng())
+                                        .and_then(|&tone| chord.note(tone))
+                                        .unwrap_or(chord.root()),
                                 },
                             },
                         );
@@ -112,9 +133,34 @@ impl Exercise for TwoBeat {
             }
         }
 
-        Correction {
-            amount_of_beats: analysis.beat_analysis.len(),
-            mistakes,
-        }
+        Correction::new(analysis, mistakes)
+    }
+
+    /// The bass note and the first allowed chord tone above it, each held for half a bar
+    fn generate(&self, form: &Form) -> Vec<PlayedNote> {
+        let mut previous = None;
+        form_beats(form)
+            .iter()
+            .map(|beat| {
+                let Some(chord) = &beat.chord else {
+                    return PlayedNote::Silence;
+                };
+                let position = beat.position;
+
+                let pitch = match previous {
+                    Some(previous) if !position.is_downbeat() => {
+                        if position.beat_in_bar == position.meter.second_half_start() {
+                            let tone = self.second_half_tones(chord)[0];
+                            above(chord.note(tone).unwrap_or(chord.root()), previous)
+                        } else {
+                            previous
+                        }
+                    }
+                    _ => lowest(chord.bass()),
+                };
+                previous = Some(pitch);
+                PlayedNote::Surely(pitch)
+            })
+            .collect()
     }
 }
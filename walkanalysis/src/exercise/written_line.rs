@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::{
+    analysis::{
+        analysis::Analysis,
+        correction::Correction,
+        mistake::{Mistake, MistakeKind},
+    },
+    exercise::Exercise,
+    form::{
+        form::{Form, FormPiece},
+        pitch::Pitch,
+    },
+    transcribe::transcribe::PlayedNote,
+};
+
+use super::{common_mistakes, form_beats};
+
+/// Play a bass line that is written out, such as one imported from MusicXML
+pub struct WrittenLine {
+    /// The note that must start on every beat of one chorus, after the count off.
+    /// Beats where a note is held or rested are `None` and are not checked.
+    /// The line is repeated for every chorus of the form.
+    pub notes: Vec<Option<Pitch>>,
+}
+
+impl Exercise for WrittenLine {
+    fn explain(&self) -> String {
+        "Play the written bass line. Only the note names are checked, not the octave.".into()
+    }
+
+    fn correct(&mut self, analysis: &Analysis) -> Correction {
+        let mut mistakes = HashMap::new();
+
+        let count_off_length = analysis
+            .beat_analysis
+            .values()
+            .filter(|(form_piece, _)| *form_piece == FormPiece::CountOff)
+            .count() as u32;
+
+        for (&beat, (form_piece, note_analysis)) in analysis.beat_analysis.iter() {
+            let position = analysis.beat_positions[&beat];
+
+            let expected = if *form_piece == FormPiece::CountOff || self.notes.is_empty() {
+                None
+            } else {
+                self.notes[((beat - count_off_length) as usize) % self.notes.len()]
+            };
+            if expected.is_none() && *form_piece != FormPiece::CountOff {
+                continue;
+            }
+
+            let Some((note, _degree, _chord_tone, _chord)) =
+                common_mistakes(&mut mistakes, beat, position, form_piece, *note_analysis)
+            else {
+                continue;
+            };
+
+            if let Some(expected) = expected {
+                if note != expected.note()
+                    && !analysis.might_have_played(beat, |note| note == expected.note())
+                {
+                    mistakes.insert(
+                        beat,
+                        Mistake {
+                            beat,
+                            position,
+                            mistake: MistakeKind::WrongNote {
+                                played: note,
+                                expected: expected.note(),
+                            },
+                        },
+                    );
+                }
+            }
+        }
+
+        Correction::new(analysis, mistakes)
+    }
+
+    /// The written line, with held notes repeated
+    fn generate(&self, form: &Form) -> Vec<PlayedNote> {
+        let mut previous = PlayedNote::Silence;
+        let mut line = self.notes.iter().cycle();
+        form_beats(form)
+            .iter()
+            .map(|beat| {
+                if beat.chord.is_none() {
+                    return PlayedNote::Silence;
+                }
+                if let Some(Some(pitch)) = line.next() {
+                    previous = PlayedNote::Surely(*pitch);
+                }
+                previous
+            })
+            .collect()
+    }
+}
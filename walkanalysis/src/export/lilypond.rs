@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use crate::{
+    analysis::{analysis::Analysis, correction::Correction},
+    form::{
+        chord_name::SymbolStyle,
+        form::Form,
+        key::Quality,
+        note::{Accidental, Spelling, WrittenNote},
+        pitch::Pitch,
+    },
+};
+
+use super::{annotation, notate, MISTAKE_COLOR};
+
+/// Writes the take as LilyPond source: one bass staff with the chord symbols above the
+/// notes and the mistakes colored, with what was expected written underneath.
+pub fn to_lilypond(
+    title: &str,
+    form: &Form,
+    analysis: &Analysis,
+    correction: &Correction,
+) -> String {
+    let mut ly = String::new();
+    ly += "\\version \"2.24.0\"\n\\language \"english\"\n\n";
+    writeln!(
+        ly,
+        "\\header {{\n  title = \"{}\"\n  tagline = ##f\n}}\n",
+        escape(title)
+    )
+    .unwrap();
+    ly += "\\score {\n  \\new Staff {\n    \\clef \"bass_8\"\n";
+
+    let mut previous_meter = None;
+    for (number, bar) in (1..).zip(notate(form, analysis, correction)) {
+        let meter = bar.meter;
+        if bar.line_break && number != 1 {
+            ly += "    \\break\n";
+        }
+
+        let mut attributes = vec![];
+        if let Some(key) = bar.key {
+            let mode = match key.quality() {
+                Quality::Major => "major",
+                Quality::Minor => "minor",
+            };
+            attributes.push(format!("\\key {} \\{}", note_name(key.root()), mode));
+        }
+        if previous_meter != Some(meter) {
+            attributes.push(format!(
+                "\\time {}/{}",
+                meter.beats_per_bar(),
+                meter.beat_unit()
+            ));
+        }
+        if number == 1 {
+            attributes.push(format!("\\tempo {} = {}", meter.beat_unit(), form.tempo()));
+        }
+        if !attributes.is_empty() {
+            writeln!(ly, "    {}", attributes.join(" ")).unwrap();
+        }
+        previous_meter = Some(meter);
+
+        let notes: Vec<String> = bar
+            .beats
+            .iter()
+            .map(|beat| {
+                let mut note = String::new();
+                if beat.mistake.is_some() {
+                    write!(note, "\\tweak color #(rgb-color {}) ", rgb(MISTAKE_COLOR)).unwrap();
+                }
+                match beat.pitch {
+                    Some(pitch) => note += &pitch_name(pitch, bar.spelling),
+                    None => note += "r",
+                }
+                write!(note, "{}", meter.beat_unit()).unwrap();
+                if let Some(chord) = &beat.chord {
+                    write!(
+                        note,
+                        "^\\markup {{ \"{}\" }}",
+                        escape(&chord.styled_symbol(bar.spelling, SymbolStyle::Plain))
+                    )
+                    .unwrap();
+                }
+                if let Some(mistake) = &beat.mistake {
+                    write!(
+                        note,
+                        "_\\markup {{ \\with-color #(rgb-color {}) \"{}\" }}",
+                        rgb(MISTAKE_COLOR),
+                        escape(&annotation(mistake))
+                    )
+                    .unwrap();
+                }
+                note
+            })
+            .collect();
+        writeln!(ly, "    {} |", notes.join(" ")).unwrap();
+    }
+
+    ly += "    \\bar \"|.\"\n  }\n  \\layout { }\n}\n";
+    ly
+}
+
+/// The pitch as it sounds, with LilyPond's octave marks: `c` is C3 and `c'` is middle C
+fn pitch_name(pitch: Pitch, spelling: Spelling) -> String {
+    let octaves_above_c3 = pitch.octave() - 3;
+    let marks = if octaves_above_c3 >= 0 {
+        "'".repeat(octaves_above_c3 as usize)
+    } else {
+        ",".repeat(-octaves_above_c3 as usize)
+    };
+    format!("{}{}", note_name(pitch.note().spell(spelling)), marks)
+}
+
+fn note_name(note: WrittenNote) -> String {
+    let accidental = match note.accidental {
+        Accidental::Natural => "",
+        Accidental::Sharp => "s",
+        Accidental::Flat => "f",
+        Accidental::DoubleSharp => "ss",
+        Accidental::DoubleFlat => "ff",
+    };
+    format!("{}{}", note.name.to_string().to_lowercase(), accidental)
+}
+
+/// A color such as "#D32F2F" as the arguments of LilyPond's `rgb-color`
+fn rgb(color: &str) -> String {
+    let channel = |i: usize| {
+        let value = u8::from_str_radix(&color[1 + 2 * i..3 + 2 * i], 16).unwrap_or(0);
+        format!("{:.3}", value as f32 / 255.)
+    };
+    format!("{} {} {}", channel(0), channel(1), channel(2))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,112 @@
+use crate::{
+    analysis::{analysis::Analysis, correction::Correction, mistake::MistakeKind},
+    form::{
+        chord::Chord,
+        form::{Form, FormPiece},
+        key::WrittenKey,
+        meter::Meter,
+        note::Spelling,
+        pitch::Pitch,
+    },
+    transcribe::transcribe::Subdivision,
+};
+
+pub mod lilypond;
+pub mod musicxml;
+
+/// Color of notes that were corrected
+const MISTAKE_COLOR: &str = "#D32F2F";
+
+/// A bar of the take as it is written down
+struct NotatedBar {
+    meter: Meter,
+    /// Set on the first bar and where the key changes
+    key: Option<WrittenKey>,
+    /// Whether to start a new line with this bar
+    line_break: bool,
+    spelling: Spelling,
+    beats: Vec<NotatedBeat>,
+}
+
+struct NotatedBeat {
+    pitch: Option<Pitch>,
+    /// Set where a new chord starts
+    chord: Option<Chord>,
+    mistake: Option<MistakeKind>,
+}
+
+/// Lays out the analyzed take bar by bar, leaving out the count off
+fn notate(form: &Form, analysis: &Analysis, correction: &Correction) -> Vec<NotatedBar> {
+    let mut bars = vec![];
+
+    let mut key = Some(form.key());
+    let mut spelling = form.key().spell_preference();
+    let mut line_break = false;
+    let mut beat = 0;
+
+    for (form_piece, note_analyses) in &analysis.form_analysis {
+        match form_piece {
+            FormPiece::Key(new_key) => {
+                spelling = new_key.flat().spell_preference();
+                key = Some(new_key.spell(spelling));
+            }
+            FormPiece::LineBreak => line_break = true,
+            FormPiece::ChordBar(_) | FormPiece::HalfBar(_, _) | FormPiece::SplitBar(_) => {
+                let Some(meter) = analysis.beat_positions.get(&beat).map(|p| p.meter) else {
+                    break;
+                };
+
+                let mut previous_chord = None;
+                let beats = (0..form_piece.length_in_beats(meter))
+                    .map(|beat_in_bar| {
+                        let chord = form_piece.chord_at(beat_in_bar, meter).cloned();
+                        let new_chord = (chord != previous_chord).then(|| chord.clone()).flatten();
+                        previous_chord = chord;
+
+                        NotatedBeat {
+                            pitch: note_analyses
+                                .get(beat_in_bar as usize)
+                                .and_then(|note_analysis| note_analysis.pitch()),
+                            chord: new_chord,
+                            mistake: correction
+                                .mistakes
+                                .get(&(beat + beat_in_bar))
+                                .map(|mistake| mistake.mistake),
+                        }
+                    })
+                    .collect();
+
+                bars.push(NotatedBar {
+                    meter,
+                    key: key.take(),
+                    line_break,
+                    spelling,
+                    beats,
+                });
+                line_break = false;
+            }
+            _ => (),
+        }
+        beat += note_analyses.len() as u32;
+    }
+
+    bars
+}
+
+/// Short text to put under a corrected note
+fn annotation(mistake: &MistakeKind) -> String {
+    match mistake {
+        MistakeKind::WrongNote { expected, .. } => format!("{}?", expected.flat()),
+        MistakeKind::ExpectedSilence { .. } => "rest".into(),
+        MistakeKind::ExpectedNote { .. } => "note?".into(),
+        MistakeKind::ExpectedChordTone { .. } => "chord tone?".into(),
+        MistakeKind::ExpectedOrnament {
+            subdivision: Subdivision::SecondTriplet,
+        } => "skip?".into(),
+        MistakeKind::ExpectedOrnament { .. } => "pickup?".into(),
+        MistakeKind::UnexpectedOrnament {
+            subdivision: Subdivision::Eighth,
+        } => "swing".into(),
+        MistakeKind::UnexpectedOrnament { .. } => "no pickup".into(),
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Write;
+
+use crate::{
+    analysis::{analysis::Analysis, correction::Correction},
+    form::{
+        chord::Chord, chord_name::SymbolStyle, form::Form, key::Quality, musicxml, note::Spelling,
+        note::WrittenNote, pitch::Pitch,
+    },
+};
+
+use super::{annotation, notate, MISTAKE_COLOR};
+
+/// Writes the take as MusicXML: one bass staff with the chord symbols above it and the
+/// mistakes colored, with what was expected written underneath as lyrics.
+/// Bass is written an octave higher than it sounds, as usual.
+pub fn to_musicxml(
+    title: &str,
+    form: &Form,
+    analysis: &Analysis,
+    correction: &Correction,
+) -> String {
+    let mut xml = String::new();
+    xml += r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<!DOCTYPE score-partwise PUBLIC "-//Recordare//DTD MusicXML 4.0 Partwise//EN" "http://www.musicxml.org/dtds/partwise.dtd">
+<score-partwise version="4.0">
+"#;
+    writeln!(
+        xml,
+        "  <work><work-title>{}</work-title></work>",
+        escape(title)
+    )
+    .unwrap();
+    xml += r#"  <part-list>
+    <score-part id="P1"><part-name>Bass</part-name></score-part>
+  </part-list>
+  <part id="P1">
+"#;
+
+    let mut previous_meter = None;
+    let mut previous_divisions = None;
+    for (number, bar) in (1..).zip(notate(form, analysis, correction)) {
+        let meter = bar.meter;
+        // Divisions per quarter note, so every beat is a whole number of divisions
+        let divisions = (meter.beat_unit() / 4).max(1);
+        let duration = 4 * divisions / meter.beat_unit();
+
+        writeln!(xml, "    <measure number=\"{}\">", number).unwrap();
+        if bar.line_break {
+            xml += "      <print new-system=\"yes\"/>\n";
+        }
+
+        let meter_changed = previous_meter != Some(meter);
+        let divisions_changed = previous_divisions != Some(divisions);
+        if number == 1 || meter_changed || divisions_changed || bar.key.is_some() {
+            xml += "      <attributes>\n";
+            if divisions_changed {
+                writeln!(xml, "        <divisions>{}</divisions>", divisions).unwrap();
+            }
+            if let Some(key) = bar.key {
+                let mode = match key.quality() {
+                    Quality::Major => "major",
+                    Quality::Minor => "minor",
+                };
+                writeln!(
+                    xml,
+                    "        <key><fifths>{}</fifths><mode>{}</mode></key>",
+                    musicxml::fifths(key),
+                    mode
+                )
+                .unwrap();
+            }
+            if meter_changed {
+                writeln!(
+                    xml,
+                    "        <time><beats>{}</beats><beat-type>{}</beat-type></time>",
+                    meter.beats_per_bar(),
+                    meter.beat_unit()
+                )
+                .unwrap();
+            }
+            if number == 1 {
+                xml += "        <clef><sign>F</sign><line>4</line></clef>\n";
+                xml += "        <transpose><diatonic>0</diatonic><chromatic>0</chromatic><octave-change>-1</octave-change></transpose>\n";
+            }
+            xml += "      </attributes>\n";
+        }
+        if number == 1 {
+            writeln!(
+                xml,
+                "      <direction placement=\"above\"><direction-type><metronome><beat-unit>{}</beat-unit><per-minute>{}</per-minute></metronome></direction-type><sound tempo=\"{}\"/></direction>",
+                note_type(meter.beat_unit()),
+                form.tempo(),
+                form.tempo()
+            )
+            .unwrap();
+        }
+        previous_meter = Some(meter);
+        previous_divisions = Some(divisions);
+
+        for beat in &bar.beats {
+            if let Some(chord) = &beat.chord {
+                write_harmony(&mut xml, chord, bar.spelling);
+            }
+
+            match beat.mistake {
+                Some(_) => writeln!(xml, "      <note color=\"{}\">", MISTAKE_COLOR).unwrap(),
+                None => xml += "      <note>\n",
+            }
+            match beat.pitch {
+                // Written an octave higher than it sounds
+                Some(pitch) => write_pitch(&mut xml, pitch.add_steps(12), bar.spelling),
+                None => xml += "        <rest/>\n",
+            }
+            writeln!(xml, "        <duration>{}</duration>", duration).unwrap();
+            xml += "        <voice>1</voice>\n";
+            writeln!(xml, "        <type>{}</type>", note_type(meter.beat_unit())).unwrap();
+            if let Some(mistake) = &beat.mistake {
+                writeln!(
+                    xml,
+                    "        <lyric><text>{}</text></lyric>",
+                    escape(&annotation(mistake))
+                )
+                .unwrap();
+            }
+            xml += "      </note>\n";
+        }
+
+        xml += "    </measure>\n";
+    }
+
+    xml += "  </part>\n</score-partwise>\n";
+    xml
+}
+
+fn write_pitch(xml: &mut String, pitch: Pitch, spelling: Spelling) {
+    let note = pitch.note().spell(spelling);
+    xml.push_str("        <pitch>");
+    write!(xml, "<step>{}</step>", note.name).unwrap();
+    if note.accidental.value() != 0 {
+        write!(xml, "<alter>{}</alter>", note.accidental.value()).unwrap();
+    }
+    writeln!(xml, "<octave>{}</octave></pitch>", pitch.octave()).unwrap();
+}
+
+fn write_harmony(xml: &mut String, chord: &Chord, spelling: Spelling) {
+    let root = chord.spell(spelling).root();
+    let suffix = chord.symbol(SymbolStyle::Plain);
+
+    xml.push_str("      <harmony>\n");
+    writeln!(xml, "        <root>{}</root>", step_and_alter(root, "root")).unwrap();
+    match musicxml::suffix_kind(&suffix) {
+        Some(kind) => writeln!(xml, "        <kind>{}</kind>", kind).unwrap(),
+        None => writeln!(
+            xml,
+            "        <kind text=\"{}\">other</kind>",
+            escape(&suffix)
+        )
+        .unwrap(),
+    }
+    if let Some(bass) = chord.slash_bass() {
+        writeln!(
+            xml,
+            "        <bass>{}</bass>",
+            step_and_alter(bass.spell(spelling), "bass")
+        )
+        .unwrap();
+    }
+    xml.push_str("      </harmony>\n");
+}
+
+/// `<root-step>` and `<root-alter>`, or the same for the bass
+fn step_and_alter(note: WrittenNote, element: &str) -> String {
+    let mut xml = format!("<{0}-step>{1}</{0}-step>", element, note.name);
+    if note.accidental.value() != 0 {
+        write!(
+            xml,
+            "<{0}-alter>{1}</{0}-alter>",
+            element,
+            note.accidental.value()
+        )
+        .unwrap();
+    }
+    xml
+}
+
+fn note_type(beat_unit: u32) -> &'static str {
+    match beat_unit {
+        1 => "whole",
+        2 => "half",
+        8 => "eighth",
+        16 => "16th",
+        _ => "quarter",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,399 @@
+use std::{error::Error, fmt::Display, fs, path::Path, str::FromStr};
+
+use crate::form::{
+    chord::Chord,
+    form::{Form, FormPiece},
+    key::{Quality, WrittenKey},
+    meter::Meter,
+    note::WrittenNote,
+};
+
+/// Why a chord chart could not be read, lines and columns are counted from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChartError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ChartError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ChartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ChartError {}
+
+const HEADERS: [&str; 4] = ["key", "tempo", "meter", "choruses"];
+
+impl FromStr for Form {
+    type Err = ChartError;
+
+    /// Reads a chord chart such as
+    ///
+    /// ```text
+    /// key: Gm
+    /// tempo: 140
+    /// [A]
+    /// |: Cm7 | F7 | Bbmaj7 | Ebmaj7 |
+    /// | Am7b5 | D7 | 1. Gm | Gm :| 2. Gm7 C7 | Fmaj7 |
+    /// ```
+    ///
+    /// The `key` and `tempo` headers are required, `meter` (4/4) and `choruses` (1) are optional.
+    /// A bar with one chord is a `ChordBar`, with two chords a `HalfBar`. Any other harmonic
+    /// rhythm is written with beats, as in `| Am7*3 D7*1 |`. Every new line of the chart is a
+    /// `LineBreak`, lines starting with `#` are comments.
+    /// Markers are written as `[A]`, `|:`, `:|`, `:|x3`, `1.`, `Segno`, `Coda`, `ToCoda`, `D.C.`,
+    /// `D.S.` and `Fine`, key and meter changes as `key:Eb` and `meter:3/4`.
+    fn from_str(chart: &str) -> Result<Self, Self::Err> {
+        let mut key = None;
+        let mut tempo = None;
+        let mut meter = Meter::default();
+        let mut choruses = 1;
+        let mut music = vec![];
+        let mut in_body = false;
+
+        for (line_number, line) in (1..).zip(chart.split_inclusive('\n')) {
+            let content = line.trim();
+            if content.is_empty() || content.starts_with('#') {
+                continue;
+            }
+
+            if !in_body {
+                if let Some((name, value, column)) = header(line) {
+                    let error = |message: String| ChartError::new(line_number, column, message);
+                    match name {
+                        "key" => {
+                            key = Some(
+                                parse_key(value)
+                                    .ok_or_else(|| error(format!("Invalid key `{}`", value)))?,
+                            )
+                        }
+                        "tempo" => {
+                            tempo = Some(
+                                value
+                                    .parse()
+                                    .map_err(|_| error(format!("Invalid tempo `{}`", value)))?,
+                            )
+                        }
+                        "meter" => {
+                            meter = parse_meter(value)
+                                .ok_or_else(|| error(format!("Invalid meter `{}`", value)))?
+                        }
+                        "choruses" => {
+                            choruses = value.parse().map_err(|_| {
+                                error(format!("Invalid amount of choruses `{}`", value))
+                            })?
+                        }
+                        _ => unreachable!(),
+                    }
+                    continue;
+                }
+                in_body = true;
+            }
+
+            parse_line(line, line_number, &mut music)?;
+            if line.ends_with('\n') {
+                music.push(FormPiece::LineBreak);
+            }
+        }
+
+        let key = key.ok_or_else(|| ChartError::new(1, 1, "Missing `key:` header"))?;
+        let tempo = tempo.ok_or_else(|| ChartError::new(1, 1, "Missing `tempo:` header"))?;
+
+        Ok(Form::new(tempo, key, music)
+            .with_meter(meter)
+            .with_choruses(choruses))
+    }
+}
+
+/// Splits a header line such as "tempo: 120" into its name, value and the column of the value.
+/// Lines with more than one word after the colon are not headers.
+fn header(line: &str) -> Option<(&str, &str, usize)> {
+    let (name, value) = line.split_once(':')?;
+    let name = name.trim();
+    if !HEADERS.contains(&name) || value.split_whitespace().count() != 1 {
+        return None;
+    }
+
+    let offset = line.len() - value.trim_start().len();
+    Some((name, value.trim(), line[..offset].chars().count() + 1))
+}
+
+fn parse_key(s: &str) -> Option<WrittenKey> {
+    let (root, quality) = WrittenNote::parse_prefix(s)?;
+    let quality = match quality {
+        "" => Quality::Major,
+        "m" | "-" => Quality::Minor,
+        _ => return None,
+    };
+    Some(WrittenKey::new(root, quality))
+}
+
+fn parse_meter(s: &str) -> Option<Meter> {
+    let (beats_per_bar, beat_unit) = s.split_once('/')?;
+    Meter::new(beats_per_bar.parse().ok()?, beat_unit.parse().ok()?)
+}
+
+/// A word of the chart with the column it starts at
+struct Token<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+/// Splits a line into words. Bar lines don't need to be surrounded by spaces, so "|Cm7|F7|"
+/// is read the same as "| Cm7 | F7 |".
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = line;
+    let mut column = 1;
+
+    loop {
+        let trimmed = rest.trim_start();
+        column += rest[..rest.len() - trimmed.len()].chars().count();
+        rest = trimmed;
+        if rest.is_empty() {
+            return tokens;
+        }
+
+        let length = token_length(rest);
+        tokens.push(Token {
+            text: &rest[..length],
+            column,
+        });
+        column += rest[..length].chars().count();
+        rest = &rest[length..];
+    }
+}
+
+fn token_length(s: &str) -> usize {
+    if s.starts_with("|:") {
+        return 2;
+    }
+    if let Some(after) = s.strip_prefix(":|") {
+        // ":|x3" is a repeat that is played three times
+        let digits = after
+            .strip_prefix('x')
+            .map(|times| times.len() - times.trim_start_matches(|c: char| c.is_ascii_digit()).len())
+            .unwrap_or(0);
+        return if digits > 0 { 3 + digits } else { 2 };
+    }
+    if s.starts_with('|') {
+        return 1;
+    }
+    // Section labels may contain spaces
+    if s.starts_with('[') {
+        return s.find(']').map(|end| end + 1).unwrap_or(s.len());
+    }
+
+    s.char_indices()
+        .find(|&(i, c)| c.is_whitespace() || c == '|' || s[i..].starts_with(":|"))
+        .map(|(i, _)| i)
+        .unwrap_or(s.len())
+}
+
+/// Reads the bars and markers of a single line of the chart into `music`
+fn parse_line(
+    line: &str,
+    line_number: usize,
+    music: &mut Vec<FormPiece>,
+) -> Result<(), ChartError> {
+    // Chords of the bar that is being read, with their number of beats if given
+    let mut chords = vec![];
+
+    for token in tokenize(line) {
+        let error = |message: String| ChartError::new(line_number, token.column, message);
+
+        let marker = match token.text {
+            "|" => None,
+            "|:" => Some(FormPiece::RepeatStart),
+            "Segno" => Some(FormPiece::Segno),
+            "Coda" => Some(FormPiece::Coda),
+            "ToCoda" => Some(FormPiece::ToCoda),
+            "D.C." => Some(FormPiece::DaCapo),
+            "D.S." => Some(FormPiece::DalSegno),
+            "Fine" => Some(FormPiece::Fine),
+            "count-off" => Some(FormPiece::CountOff),
+            text if text.starts_with(":|") => {
+                let times = match &text[2..] {
+                    "" => 2,
+                    times => times[1..]
+                        .parse()
+                        .map_err(|_| error(format!("Invalid repeat `{}`", text)))?,
+                };
+                Some(FormPiece::RepeatEnd(times))
+            }
+            text if text.starts_with('[') => {
+                let label = text
+                    .strip_prefix('[')
+                    .and_then(|label| label.strip_suffix(']'))
+                    .ok_or_else(|| error(format!("Unclosed section label `{}`", text)))?;
+                Some(FormPiece::Section(label.trim().into()))
+            }
+            text if text.ends_with('.') && text[..text.len() - 1].parse::<u32>().is_ok() => {
+                Some(FormPiece::Ending(text[..text.len() - 1].parse().unwrap()))
+            }
+            text if text.starts_with("key:") => {
+                let key = parse_key(&text[4..])
+                    .ok_or_else(|| error(format!("Invalid key `{}`", &text[4..])))?;
+                Some(FormPiece::Key(key.unwrite()))
+            }
+            text if text.starts_with("meter:") => {
+                let meter = parse_meter(&text[6..])
+                    .ok_or_else(|| error(format!("Invalid meter `{}`", &text[6..])))?;
+                Some(FormPiece::Meter(meter))
+            }
+            text => {
+                chords.push(parse_chord(text).map_err(error)?);
+                continue;
+            }
+        };
+
+        // Anything that isn't a chord ends the bar
+        if let Some(bar) = finish_bar(&mut chords) {
+            music.push(bar);
+        }
+        music.extend(marker);
+    }
+
+    if let Some(bar) = finish_bar(&mut chords) {
+        music.push(bar);
+    }
+
+    Ok(())
+}
+
+/// Reads "Am7" or "Am7*3", a chord held for three beats
+fn parse_chord(text: &str) -> Result<(Chord, Option<u32>), String> {
+    let (symbol, beats) = match text.rsplit_once('*') {
+        Some((symbol, beats)) => {
+            let beats = beats
+                .parse()
+                .ok()
+                .filter(|&beats| beats > 0)
+                .ok_or_else(|| format!("Invalid number of beats `{}`", beats))?;
+            (symbol, Some(beats))
+        }
+        None => (text, None),
+    };
+
+    let chord = symbol.parse().map_err(|e| format!("{}", e))?;
+    Ok((chord, beats))
+}
+
+fn finish_bar(chords: &mut Vec<(Chord, Option<u32>)>) -> Option<FormPiece> {
+    let bar = match chords.as_slice() {
+        [] => return None,
+        [(chord, None)] => FormPiece::ChordBar(chord.clone()),
+        [(chord1, None), (chord2, None)] => FormPiece::HalfBar(chord1.clone(), chord2.clone()),
+        _ => FormPiece::SplitBar(
+            chords
+                .iter()
+                .map(|(chord, beats)| (chord.clone(), beats.unwrap_or(1)))
+                .collect(),
+        ),
+    };
+    chords.clear();
+    Some(bar)
+}
+
+fn is_bar_line(token: &str) -> bool {
+    token.starts_with('|') || token.starts_with(":|")
+}
+
+/// Puts a repeat sign in place of the bar line that closes the previous bar
+fn replace_bar_line(line: &mut Vec<String>, token: String) {
+    if line.last().map(String::as_str) == Some("|") {
+        line.pop();
+    }
+    line.push(token);
+}
+
+impl Form {
+    pub fn open_chart<P: AsRef<Path>>(path: P) -> Result<Form, Box<dyn std::error::Error>> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+
+    pub fn save_chart<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        fs::write(path, self.to_chart())
+    }
+
+    /// Writes the form as a chord chart, which parses back into the same form
+    pub fn to_chart(&self) -> String {
+        let mut chart = format!("key: {}\ntempo: {}\n", self.key(), self.tempo());
+        if self.meter() != Meter::default() {
+            chart += &format!("meter: {}\n", self.meter());
+        }
+        if self.choruses() > 1 {
+            chart += &format!("choruses: {}\n", self.choruses());
+        }
+
+        let mut spelling = self.key().spell_preference();
+        let mut line: Vec<String> = vec![];
+
+        for (index, form_piece) in self.music().iter().enumerate() {
+            match form_piece {
+                // Added by `Form::new` when parsing
+                FormPiece::CountOff if index == 0 => (),
+                FormPiece::CountOff => line.push("count-off".into()),
+                FormPiece::Key(key) => {
+                    spelling = key.flat().spell_preference();
+                    line.push(format!("key:{}", key.spell(spelling)));
+                }
+                FormPiece::Meter(meter) => line.push(format!("meter:{}", meter)),
+                FormPiece::ChordBar(_) | FormPiece::HalfBar(_, _) | FormPiece::SplitBar(_) => {
+                    if !line.iter().any(|token| is_bar_line(token)) {
+                        line.push("|".into());
+                    }
+                    match form_piece {
+                        FormPiece::SplitBar(chords) => {
+                            line.extend(chords.iter().map(|(chord, beats)| {
+                                format!("{}*{}", chord.spell_symbol(spelling), beats)
+                            }))
+                        }
+                        _ => line.extend(
+                            form_piece
+                                .chords()
+                                .iter()
+                                .map(|chord| chord.spell_symbol(spelling)),
+                        ),
+                    }
+                    line.push("|".into());
+                }
+                FormPiece::LineBreak => {
+                    chart += &line.join(" ");
+                    chart.push('\n');
+                    line.clear();
+                }
+                FormPiece::Section(label) => line.push(format!("[{}]", label)),
+                FormPiece::RepeatStart => replace_bar_line(&mut line, "|:".into()),
+                FormPiece::RepeatEnd(2) => replace_bar_line(&mut line, ":|".into()),
+                FormPiece::RepeatEnd(times) => replace_bar_line(&mut line, format!(":|x{}", times)),
+                FormPiece::Ending(number) => line.push(format!("{}.", number)),
+                FormPiece::Segno => line.push("Segno".into()),
+                FormPiece::Coda => line.push("Coda".into()),
+                FormPiece::ToCoda => line.push("ToCoda".into()),
+                FormPiece::DaCapo => line.push("D.C.".into()),
+                FormPiece::DalSegno => line.push("D.S.".into()),
+                FormPiece::Fine => line.push("Fine".into()),
+            }
+        }
+
+        chart += &line.join(" ");
+        chart
+    }
+}
@@ -1,8 +1,9 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
 use crate::form::{
+    chord_name::SymbolStyle,
     interval::Interval,
     note::{Note, WrittenNote},
 };
@@ -11,21 +12,88 @@ use super::note::Spelling;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Chord {
-    pub notes: Vec<Note>,
+    /// Every note in the chord, keyed by the role it plays. Always contains the root.
+    tones: BTreeMap<ChordTone, Note>,
+    /// Note in the bass if it's not the root, as in slash chords like C/E
+    #[serde(default)]
+    bass: Option<Note>,
     pub symbol: Option<String>,
 }
 
 impl Chord {
-    pub fn new(notes: Vec<Note>) -> Self {
+    /// A chord with only a root, add the other chord tones with `with` or `with_interval`
+    pub fn new(root: Note) -> Self {
         Self {
-            notes,
+            tones: BTreeMap::from([(ChordTone::Root, root)]),
+            bass: None,
             symbol: None,
         }
     }
 
+    /// Sets (or replaces) the note of a chord tone.
+    /// Since this changes the chord, an explicitly set symbol is dropped.
+    pub fn with(mut self, chord_tone: ChordTone, note: Note) -> Self {
+        match chord_tone {
+            ChordTone::Root | ChordTone::NoChordTone => (),
+            ChordTone::Bass => return self.with_bass(note),
+            _ => {
+                self.tones.insert(chord_tone, note);
+                self.symbol = None;
+            }
+        }
+        self
+    }
+
+    /// Sets (or replaces) the note of a chord tone as an interval above the root
+    pub fn with_interval(self, chord_tone: ChordTone, interval: Interval) -> Self {
+        let note = self.root().add_interval(interval);
+        self.with(chord_tone, note)
+    }
+
+    /// Removes a chord tone, the root cannot be removed
+    pub fn without(mut self, chord_tone: ChordTone) -> Self {
+        if chord_tone != ChordTone::Root {
+            self.tones.remove(&chord_tone);
+            self.symbol = None;
+        }
+        self
+    }
+
+    /// Sets the note to be played in the bass, making this a slash chord.
+    pub fn with_bass(mut self, bass: Note) -> Self {
+        self.bass = if bass == self.root() {
+            None
+        } else {
+            Some(bass)
+        };
+        self
+    }
+
+    /// The written bass note if this is a slash chord
+    pub fn slash_bass(&self) -> Option<Note> {
+        self.bass
+    }
+
+    /// The note the bass should land on: the slash bass if there is one, otherwise the root
+    pub fn bass(&self) -> Note {
+        self.bass.unwrap_or(self.root())
+    }
+
+    pub fn root(&self) -> Note {
+        self.tones[&ChordTone::Root]
+    }
+
+    pub fn tones(&self) -> &BTreeMap<ChordTone, Note> {
+        &self.tones
+    }
+
+    /// All notes in the chord, stacked from the root upwards
+    pub fn notes(&self) -> Vec<Note> {
+        self.tones.values().copied().collect()
+    }
+
     pub fn has_seventh(&self) -> bool {
-        // TODO: big chord refactor where we define the notes not as a vec but as a hashmap of chordtone to note
-        self.notes.len() > 3
+        self.tones.contains_key(&ChordTone::Seventh)
     }
 
     pub fn spell(&self, spelling: Spelling) -> WrittenChord {
@@ -35,180 +103,228 @@ impl Chord {
         }
     }
 
+    /// Spells the chord from the given spelling of its root: every chord tone gets the note
+    /// name its degree implies, using double sharps and flats where needed (C°7 is C E♭ G♭ B𝄫).
+    pub fn spell_from_root(&self, root: WrittenNote) -> WrittenChord {
+        let bass_spelling = if root.accidental.value() < 0 {
+            Spelling::Flat
+        } else {
+            Spelling::Sharp
+        };
+        self.spell_from(root, bass_spelling)
+    }
+
+    fn spell_from(&self, root: WrittenNote, bass_spelling: Spelling) -> WrittenChord {
+        let root_note = self.root();
+        let notes: Vec<(ChordTone, WrittenNote)> = self
+            .tones
+            .iter()
+            .map(|(&tone, note)| {
+                let letters = tone.letters_above_root().unwrap_or(0);
+                let steps = note.index() - root_note.index();
+                (tone, root.above(letters, steps))
+            })
+            .collect();
+
+        // A bass that is also a chord tone is written the same way as that chord tone
+        let bass = self.bass.map(|bass| {
+            notes
+                .iter()
+                .find(|(_, note)| note.unwrite() == bass)
+                .map(|&(_, note)| note)
+                .unwrap_or_else(|| bass.spell(bass_spelling))
+        });
+
+        WrittenChord { notes, bass }
+    }
+
+    /// Spells the chord from the flat spelling of its root
     pub fn flat(&self) -> WrittenChord {
-        WrittenChord {
-            notes: self.notes.iter().map(|n| n.flat()).collect(),
-        }
+        self.spell_from(self.root().flat(), Spelling::Flat)
     }
 
+    /// Spells the chord from the sharp spelling of its root
     pub fn sharp(&self) -> WrittenChord {
-        WrittenChord {
-            notes: self.notes.iter().map(|n| n.sharp()).collect(),
-        }
+        self.spell_from(self.root().sharp(), Spelling::Sharp)
     }
 
+    /// Picks whichever of the sharp and flat spelling needs the fewest accidentals,
+    /// preferring sharps when they are equal.
     pub fn auto_spell(&self) -> WrittenChord {
         let sharp = self.sharp();
         let flat = self.flat();
 
-        if sharp.is_spelled_correctly() {
-            sharp
-        } else if flat.is_spelled_correctly() {
+        if flat.accidentals() < sharp.accidentals() {
             flat
         } else {
-            unimplemented!("Cannot spell {}/{} automatically", sharp, flat)
+            sharp
         }
     }
 
+    /// The role the note plays in this chord. If a note fulfills multiple roles
+    /// (e.g. the sixth and the thirteenth), the lowest role is returned.
+    /// The slash bass is only reported as `ChordTone::Bass` if it isn't a chord tone already.
     pub fn role(&self, note: Note) -> ChordTone {
-        self.notes
+        self.tones
             .iter()
-            .position(|&n| n == note)
-            .map(|index| ChordTone::from_note_index(index))
+            .find(|(_, &n)| n == note)
+            .map(|(&tone, _)| tone)
+            .or(self
+                .bass
+                .filter(|&bass| bass == note)
+                .map(|_| ChordTone::Bass))
             .unwrap_or(ChordTone::NoChordTone)
     }
 
     pub fn note(&self, chord_tone: ChordTone) -> Option<Note> {
-        self.notes.get(chord_tone.to_note_index()).copied()
-    }
-
-    pub fn symbol(&self) -> Option<String> {
-        // start with root
-        // if it has a minor third, add min
-        // if it has a tritone, add b5
-        // if it has a minor seventh, add 7
-        // if it has a major seventh, add maj7
-
-        if self.symbol.is_some() {
-            return self.symbol.clone();
-        }
-
-        let interval = |note_index_bottom, note_index_top| {
-            self.notes
-                .get(note_index_bottom)
-                .copied()
-                .and_then(|root| {
-                    self.notes
-                        .get(note_index_top)
-                        .copied()
-                        .map(|third| (root, third))
-                })
-                .and_then(|(root, third)| Interval::find(root, third))
-        };
-
-        let third = interval(0, 1);
-        let fifth = interval(0, 2);
-        let seventh = interval(0, 3);
-
-        let mut symbol = "".to_string();
-
-        if third == Some(Interval::MinorThird) {
-            symbol.push_str("m");
-        }
-
-        if seventh == Some(Interval::MinorSeventh) {
-            symbol.push_str("7");
+        match chord_tone {
+            ChordTone::Bass => Some(self.bass()),
+            _ => self.tones.get(&chord_tone).copied(),
         }
+    }
 
-        if fifth == Some(Interval::Tritone) {
-            symbol.push_str("♭5");
-        }
+    /// The quality part of the chord symbol, unless it was set explicitly
+    pub fn symbol(&self, style: SymbolStyle) -> String {
+        self.symbol
+            .clone()
+            .unwrap_or_else(|| self.quality_symbol(style))
+    }
 
-        if seventh == Some(Interval::MajorSeventh) {
-            symbol.push_str("maj7");
-        }
-        Some(symbol)
+    /// The full chord symbol including the root and slash bass, e.g. "B♭m7/F"
+    pub fn styled_symbol(&self, spelling: Spelling, style: SymbolStyle) -> String {
+        let written = self.spell(spelling);
+        format!(
+            "{}{}{}",
+            written.root(),
+            self.symbol(style),
+            written
+                .bass
+                .map(|bass| format!("/{}", bass))
+                .unwrap_or_default()
+        )
     }
 
     pub fn spell_symbol(&self, spelling: Spelling) -> String {
-        match spelling {
-            Spelling::Sharp => self.sharp_symbol(),
-            Spelling::Flat => self.flat_symbol(),
-        }
+        self.styled_symbol(spelling, SymbolStyle::default())
     }
 
     pub fn flat_symbol(&self) -> String {
-        format!(
-            "{}{}",
-            self.flat().root(),
-            self.symbol().unwrap_or("".to_string())
-        )
+        self.spell_symbol(Spelling::Flat)
     }
+
     pub fn sharp_symbol(&self) -> String {
-        format!(
-            "{}{}",
-            self.sharp().root(),
-            self.symbol().unwrap_or("".to_string())
-        )
+        self.spell_symbol(Spelling::Sharp)
     }
 }
 
 pub struct WrittenChord {
-    notes: Vec<WrittenNote>,
+    notes: Vec<(ChordTone, WrittenNote)>,
+    bass: Option<WrittenNote>,
 }
 
 impl WrittenChord {
     pub fn root(&self) -> WrittenNote {
-        self.notes.get(0).copied().unwrap()
+        self.notes.first().map(|&(_, note)| note).unwrap()
     }
 
+    /// The total number of sharps and flats in the chord, double accidentals count twice
+    pub fn accidentals(&self) -> u32 {
+        self.notes
+            .iter()
+            .map(|(_, note)| note)
+            .chain(self.bass.iter())
+            .map(|note| note.accidental.value().unsigned_abs())
+            .sum()
+    }
+
+    /// A chord is spelled correctly if every chord tone uses the note name its
+    /// degree implies, e.g. the third of any chord on C is some kind of E.
+    /// Chords from `Chord::sharp`, `Chord::flat` and `Chord::auto_spell` always are, only
+    /// `Chord::spell_from_root` with an unusual root (F♭°7 would need a triple flat) can fall short.
     pub fn is_spelled_correctly(&self) -> bool {
-        let Some(&root) = self.notes.first() else {
+        let Some(&(_, root)) = self.notes.first() else {
             return false;
         };
 
-        let mut correct_spelling = vec![];
-        let mut current_spelling = vec![];
-
-        for (i, note) in self.notes.iter().enumerate() {
-            current_spelling.push(note.name);
-            if i == 0 {
-                correct_spelling.push(root.name);
-            } else {
-                correct_spelling.push(correct_spelling.last().unwrap().spell_next())
-            }
-        }
-
-        correct_spelling == current_spelling
+        self.notes.iter().all(|(tone, note)| {
+            tone.letters_above_root()
+                .is_some_and(|letters| root.name.add_letters(letters) == note.name)
+        })
     }
 }
 
 impl std::fmt::Display for WrittenChord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for note in self.notes.iter() {
+        for (_, note) in self.notes.iter() {
             write!(f, "{} ", note)?;
         }
+        if let Some(bass) = self.bass {
+            write!(f, "/{} ", bass)?;
+        }
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The role of a note in a chord, ordered from the root upwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ChordTone {
     Root,
+    /// Replaces the third in sus2 chords
+    Second,
     Third,
+    /// Replaces the third in sus4 chords
+    Fourth,
     Fifth,
+    Sixth,
     Seventh,
+    FlatNinth,
+    Ninth,
+    SharpNinth,
+    Eleventh,
+    SharpEleventh,
+    FlatThirteenth,
+    Thirteenth,
+    /// The written bass of a slash chord, when it is not one of the other chord tones
+    Bass,
     NoChordTone,
 }
 
 impl ChordTone {
-    fn from_note_index(index: usize) -> Self {
-        match index {
-            0 => ChordTone::Root,
-            1 => ChordTone::Third,
-            2 => ChordTone::Fifth,
-            3 => ChordTone::Seventh,
-            _ => ChordTone::NoChordTone,
+    /// How many note names this chord tone is above the root, e.g. 2 for the third (C -> E)
+    pub fn letters_above_root(&self) -> Option<usize> {
+        match self {
+            ChordTone::Root => Some(0),
+            ChordTone::Second => Some(1),
+            ChordTone::Fourth => Some(3),
+            ChordTone::FlatNinth | ChordTone::Ninth | ChordTone::SharpNinth => Some(1),
+            ChordTone::Third => Some(2),
+            ChordTone::Eleventh | ChordTone::SharpEleventh => Some(3),
+            ChordTone::Fifth => Some(4),
+            ChordTone::Sixth | ChordTone::FlatThirteenth | ChordTone::Thirteenth => Some(5),
+            ChordTone::Seventh => Some(6),
+            ChordTone::Bass | ChordTone::NoChordTone => None,
         }
     }
 
-    fn to_note_index(&self) -> usize {
+    /// Short name as used in chord symbols
+    pub fn short_name(&self) -> &str {
         match self {
-            ChordTone::Root => 0,
-            ChordTone::Third => 1,
-            ChordTone::Fifth => 2,
-            ChordTone::Seventh => 3,
-            ChordTone::NoChordTone => 99,
+            ChordTone::Root => "1",
+            ChordTone::Second => "2",
+            ChordTone::Third => "3",
+            ChordTone::Fourth => "4",
+            ChordTone::Fifth => "5",
+            ChordTone::Sixth => "6",
+            ChordTone::Seventh => "7",
+            ChordTone::FlatNinth => "♭9",
+            ChordTone::Ninth => "9",
+            ChordTone::SharpNinth => "♯9",
+            ChordTone::Eleventh => "11",
+            ChordTone::SharpEleventh => "♯11",
+            ChordTone::FlatThirteenth => "♭13",
+            ChordTone::Thirteenth => "13",
+            ChordTone::Bass => "/",
+            ChordTone::NoChordTone => "-",
         }
     }
 }
@@ -217,9 +333,20 @@ impl Display for ChordTone {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ChordTone::Root => write!(f, "Root"),
+            ChordTone::Second => write!(f, "Second"),
             ChordTone::Third => write!(f, "Third"),
+            ChordTone::Fourth => write!(f, "Fourth"),
             ChordTone::Fifth => write!(f, "Fifth"),
+            ChordTone::Sixth => write!(f, "Sixth"),
             ChordTone::Seventh => write!(f, "Seventh"),
+            ChordTone::FlatNinth => write!(f, "Flat ninth"),
+            ChordTone::Ninth => write!(f, "Ninth"),
+            ChordTone::SharpNinth => write!(f, "Sharp ninth"),
+            ChordTone::Eleventh => write!(f, "Eleventh"),
+            ChordTone::SharpEleventh => write!(f, "Sharp eleventh"),
+            ChordTone::FlatThirteenth => write!(f, "Flat thirteenth"),
+            ChordTone::Thirteenth => write!(f, "Thirteenth"),
+            ChordTone::Bass => write!(f, "Bass"),
             ChordTone::NoChordTone => write!(f, "Not a chord tone"),
         }
     }
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::form::chord::{Chord, ChordTone};
+
+/// How chord qualities are written down
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolStyle {
+    /// Jazz shorthand: "−7", "ø", "Δ", "°", "+"
+    Jazz,
+    /// Spelled out: "m7", "m7b5", "maj7", "dim", "aug"
+    #[default]
+    Plain,
+}
+
+impl SymbolStyle {
+    fn minor(&self) -> &str {
+        match self {
+            SymbolStyle::Jazz => "−",
+            SymbolStyle::Plain => "m",
+        }
+    }
+
+    fn flat(&self) -> &str {
+        match self {
+            SymbolStyle::Jazz => "♭",
+            SymbolStyle::Plain => "b",
+        }
+    }
+
+    fn sharp(&self) -> &str {
+        match self {
+            SymbolStyle::Jazz => "♯",
+            SymbolStyle::Plain => "#",
+        }
+    }
+}
+
+/// Removes the chord tone from the tones still to be named if it is the given amount
+/// of steps above the root
+fn take(remaining: &mut BTreeMap<ChordTone, i32>, chord_tone: ChordTone, steps: i32) -> bool {
+    if remaining.get(&chord_tone) == Some(&steps) {
+        remaining.remove(&chord_tone);
+        true
+    } else {
+        false
+    }
+}
+
+impl Chord {
+    /// Names the quality of the chord (everything after the root) from the intervals it contains.
+    /// Chord tones that don't fit a known quality are added as alterations, so
+    /// every chord tone is present in the symbol.
+    pub fn quality_symbol(&self, style: SymbolStyle) -> String {
+        let root = self.root();
+        let mut remaining: BTreeMap<ChordTone, i32> = self
+            .tones()
+            .iter()
+            .filter(|(&tone, _)| tone != ChordTone::Root)
+            .map(|(&tone, note)| (tone, (note.index() - root.index()).rem_euclid(12)))
+            .collect();
+
+        let is_alt = [
+            (ChordTone::Third, 4),
+            (ChordTone::Seventh, 10),
+            (ChordTone::FlatNinth, 1),
+            (ChordTone::SharpNinth, 3),
+            (ChordTone::SharpEleventh, 6),
+            (ChordTone::FlatThirteenth, 8),
+        ]
+        .into_iter()
+        .collect::<BTreeMap<_, _>>()
+            == remaining;
+        if is_alt {
+            return "7alt".into();
+        }
+
+        let minor = take(&mut remaining, ChordTone::Third, 3);
+        let major = !minor && take(&mut remaining, ChordTone::Third, 4);
+        let sus4 = take(&mut remaining, ChordTone::Fourth, 5);
+        let sus2 = take(&mut remaining, ChordTone::Second, 2);
+        let no_third = !minor && !major && !sus4 && !sus2;
+
+        let diminished = minor && take(&mut remaining, ChordTone::Fifth, 6);
+        let diminished_seventh = diminished && take(&mut remaining, ChordTone::Seventh, 9);
+        let minor_seventh = !diminished_seventh && take(&mut remaining, ChordTone::Seventh, 10);
+        let major_seventh =
+            !diminished_seventh && !minor_seventh && take(&mut remaining, ChordTone::Seventh, 11);
+        let has_seventh = diminished_seventh || minor_seventh || major_seventh;
+        let augmented = major && !has_seventh && take(&mut remaining, ChordTone::Fifth, 8);
+        let natural_fifth = take(&mut remaining, ChordTone::Fifth, 7);
+        let no_fifth = !natural_fifth
+            && !diminished
+            && !augmented
+            && !remaining.contains_key(&ChordTone::Fifth);
+
+        // The number in the symbol: 7, 9, 11, 13, or 6 and 6/9 for chords without a seventh
+        let number = if has_seventh {
+            if take(&mut remaining, ChordTone::Ninth, 2) {
+                if take(&mut remaining, ChordTone::Thirteenth, 9) {
+                    "13"
+                } else if take(&mut remaining, ChordTone::Eleventh, 5) {
+                    "11"
+                } else {
+                    "9"
+                }
+            } else {
+                "7"
+            }
+        } else if take(&mut remaining, ChordTone::Sixth, 9) {
+            if take(&mut remaining, ChordTone::Ninth, 2) {
+                "6/9"
+            } else {
+                "6"
+            }
+        } else {
+            ""
+        };
+        // Only the root and the fifth, anything more keeps its number and is written `no3`
+        let power_chord =
+            no_third && natural_fifth && !has_seventh && number.is_empty() && remaining.is_empty();
+
+        let mut symbol = match style {
+            SymbolStyle::Plain => {
+                if diminished_seventh {
+                    "dim7".to_string()
+                } else if diminished && minor_seventh {
+                    format!("m{}b5", number)
+                } else if diminished && !has_seventh {
+                    format!("dim{}", number)
+                } else if augmented {
+                    format!("aug{}", number)
+                } else if minor && major_seventh {
+                    format!("{}(maj{})", style.minor(), number)
+                } else if major_seventh {
+                    format!("maj{}", number)
+                } else if minor {
+                    format!("{}{}", style.minor(), number)
+                } else if power_chord {
+                    "5".to_string()
+                } else {
+                    number.to_string()
+                }
+            }
+            SymbolStyle::Jazz => {
+                let number_after_delta = if number == "7" { "" } else { number };
+                if diminished_seventh {
+                    "°7".to_string()
+                } else if diminished && minor_seventh {
+                    format!("ø{}", number_after_delta)
+                } else if diminished && !has_seventh {
+                    format!("°{}", number)
+                } else if augmented {
+                    format!("+{}", number)
+                } else if minor && major_seventh {
+                    format!("{}Δ{}", style.minor(), number_after_delta)
+                } else if major_seventh {
+                    format!("Δ{}", number_after_delta)
+                } else if minor {
+                    format!("{}{}", style.minor(), number)
+                } else if power_chord {
+                    "5".to_string()
+                } else {
+                    number.to_string()
+                }
+            }
+        };
+
+        if sus4 {
+            symbol.push_str("sus4");
+        }
+        if sus2 {
+            symbol.push_str("sus2");
+        }
+        if no_third && !sus4 && !sus2 && !power_chord {
+            symbol.push_str("no3");
+        }
+        if no_fifth && !power_chord {
+            symbol.push_str("no5");
+        }
+        if diminished && major_seventh {
+            symbol.push_str(&format!("{}5", style.flat()));
+        }
+
+        // Whatever is left are alterations or added tones
+        for (tone, steps) in remaining {
+            let alteration = match (tone, steps) {
+                (ChordTone::Fifth, 6) => format!("{}5", style.flat()),
+                (ChordTone::Fifth, 8) => format!("{}5", style.sharp()),
+                (ChordTone::FlatNinth, _) => format!("{}9", style.flat()),
+                (ChordTone::SharpNinth, _) => format!("{}9", style.sharp()),
+                (ChordTone::SharpEleventh, _) => format!("{}11", style.sharp()),
+                (ChordTone::FlatThirteenth, _) => format!("{}13", style.flat()),
+                (ChordTone::Second, _) => "sus2".to_string(),
+                (ChordTone::Fourth, _) => "sus4".to_string(),
+                (tone, _) => format!("add{}", tone.short_name()),
+            };
+            symbol.push_str(&alteration);
+        }
+
+        symbol
+    }
+}
@@ -0,0 +1,254 @@
+use std::{collections::BTreeMap, error::Error, fmt::Display, str::FromStr};
+
+use crate::form::{
+    chord::{Chord, ChordTone},
+    note::WrittenNote,
+};
+
+/// Why a chord symbol could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordParseError {
+    Empty,
+    InvalidRoot(String),
+    UnknownToken(String),
+    InvalidBass(String),
+}
+
+impl Display for ChordParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChordParseError::Empty => write!(f, "Empty chord symbol"),
+            ChordParseError::InvalidRoot(token) => write!(f, "Invalid root note `{}`", token),
+            ChordParseError::UnknownToken(token) => {
+                write!(f, "Unknown token `{}` in chord symbol", token)
+            }
+            ChordParseError::InvalidBass(token) => write!(f, "Invalid bass note `{}`", token),
+        }
+    }
+}
+
+impl Error for ChordParseError {}
+
+impl FromStr for Chord {
+    type Err = ChordParseError;
+
+    /// Reads lead sheet chord symbols such as "Bbmaj7", "F#m7b5", "G7alt", "C6/9" or "C/E"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ChordParseError::Empty);
+        }
+
+        let (root, rest) =
+            WrittenNote::parse_prefix(s).ok_or_else(|| ChordParseError::InvalidRoot(s.into()))?;
+        let root = root.unwrite();
+
+        let (quality, bass) = split_bass(rest)?;
+
+        let chord = parse_quality(quality)?
+            .into_iter()
+            .fold(Chord::new(root), |chord, (tone, steps)| {
+                chord.with(tone, root.add_steps(steps))
+            });
+
+        Ok(match bass {
+            Some(bass) => chord.with_bass(bass.unwrite()),
+            None => chord,
+        })
+    }
+}
+
+/// Splits "m7/G" into "m7" and G, taking care not to mistake 6/9 for a slash chord
+fn split_bass(rest: &str) -> Result<(&str, Option<WrittenNote>), ChordParseError> {
+    let Some(slash) = rest.rfind('/') else {
+        return Ok((rest, None));
+    };
+
+    let (quality, after_slash) = (&rest[..slash], &rest[slash + 1..]);
+
+    match WrittenNote::parse_prefix(after_slash) {
+        Some((bass, "")) => Ok((quality, Some(bass))),
+        _ if quality.ends_with('6') && after_slash.starts_with('9') => Ok((rest, None)),
+        _ => Err(ChordParseError::InvalidBass(after_slash.into())),
+    }
+}
+
+/// Reads everything after the root (and before the bass) into chord tones,
+/// given as steps above the root.
+fn parse_quality(quality: &str) -> Result<BTreeMap<ChordTone, i32>, ChordParseError> {
+    let mut tones = BTreeMap::from([(ChordTone::Third, 4), (ChordTone::Fifth, 7)]);
+
+    // Set by "maj" and "dim", change which seventh a following number implies
+    let mut major_seventh = false;
+    let mut diminished_seventh = false;
+
+    let mut rest = quality;
+    let mut first_token = true;
+
+    while !rest.is_empty() {
+        let at_start = first_token;
+        first_token = false;
+
+        if let Some(after) = strip_any(rest, &["(", ")", ",", " "]) {
+            first_token = at_start;
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["maj", "Maj", "MAJ", "M"]) {
+            major_seventh = true;
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["Δ"]) {
+            major_seventh = true;
+            if !after.starts_with(|c: char| c.is_ascii_digit()) {
+                tones.insert(ChordTone::Seventh, 11);
+            }
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["min", "mi", "m"]) {
+            tones.insert(ChordTone::Third, 3);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["-", "−"]).filter(|_| at_start) {
+            tones.insert(ChordTone::Third, 3);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["no", "omit"]) {
+            let (tone, after) = if let Some(after) = after.strip_prefix('3') {
+                (ChordTone::Third, after)
+            } else if let Some(after) = after.strip_prefix('5') {
+                (ChordTone::Fifth, after)
+            } else {
+                return Err(unknown_token(rest));
+            };
+            tones.remove(&tone);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["dim", "°", "o"]) {
+            tones.insert(ChordTone::Third, 3);
+            tones.insert(ChordTone::Fifth, 6);
+            diminished_seventh = true;
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["ø", "Ø"]) {
+            tones.insert(ChordTone::Third, 3);
+            tones.insert(ChordTone::Fifth, 6);
+            tones.insert(ChordTone::Seventh, 10);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["aug"]) {
+            tones.insert(ChordTone::Fifth, 8);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["+"]).filter(|_| at_start) {
+            tones.insert(ChordTone::Fifth, 8);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["sus2"]) {
+            tones.remove(&ChordTone::Third);
+            tones.insert(ChordTone::Second, 2);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["sus4", "sus"]) {
+            tones.remove(&ChordTone::Third);
+            tones.insert(ChordTone::Fourth, 5);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["alt"]) {
+            tones.entry(ChordTone::Seventh).or_insert(10);
+            tones.remove(&ChordTone::Fifth);
+            tones.insert(ChordTone::FlatNinth, 1);
+            tones.insert(ChordTone::SharpNinth, 3);
+            tones.insert(ChordTone::SharpEleventh, 6);
+            tones.insert(ChordTone::FlatThirteenth, 8);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["add"]) {
+            let (tone, steps, after) =
+                parse_alteration(after).ok_or_else(|| unknown_token(rest))?;
+            tones.insert(tone, steps);
+            rest = after;
+        } else if let Some(after) = strip_any(rest, &["6/9", "69"]) {
+            tones.insert(ChordTone::Sixth, 9);
+            tones.insert(ChordTone::Ninth, 2);
+            rest = after;
+        } else if let Some((number, after)) = parse_number(rest) {
+            let seventh = if major_seventh {
+                11
+            } else if diminished_seventh {
+                9
+            } else {
+                10
+            };
+
+            match number {
+                5 => {
+                    tones.remove(&ChordTone::Third);
+                }
+                6 => {
+                    tones.insert(ChordTone::Sixth, 9);
+                }
+                7 | 9 | 11 | 13 => {
+                    tones.entry(ChordTone::Seventh).or_insert(seventh);
+                }
+                _ => unreachable!(),
+            }
+            if number >= 9 {
+                tones.insert(ChordTone::Ninth, 2);
+            }
+            if number == 11 {
+                tones.insert(ChordTone::Eleventh, 5);
+            }
+            if number == 13 {
+                tones.insert(ChordTone::Thirteenth, 9);
+            }
+            rest = after;
+        } else if let Some((tone, steps, after)) = parse_alteration(rest) {
+            tones.insert(tone, steps);
+            rest = after;
+        } else {
+            return Err(unknown_token(rest));
+        }
+    }
+
+    Ok(tones)
+}
+
+fn strip_any<'a>(s: &'a str, prefixes: &[&str]) -> Option<&'a str> {
+    prefixes.iter().find_map(|prefix| s.strip_prefix(prefix))
+}
+
+fn parse_number(s: &str) -> Option<(u32, &str)> {
+    [13, 11, 9, 7, 6, 5]
+        .into_iter()
+        .find_map(|number| Some((number, s.strip_prefix(&number.to_string())?)))
+}
+
+/// Reads an (optionally altered) chord tone such as "b9", "#11" or "9"
+fn parse_alteration(s: &str) -> Option<(ChordTone, i32, &str)> {
+    let (alteration, after) = if let Some(after) = strip_any(s, &["b", "♭", "-"]) {
+        (-1, after)
+    } else if let Some(after) = strip_any(s, &["#", "♯", "+"]) {
+        (1, after)
+    } else {
+        (0, s)
+    };
+
+    let (number, after) = parse_number(after)
+        .or_else(|| Some((2, after.strip_prefix('2')?)))
+        .or_else(|| Some((4, after.strip_prefix('4')?)))?;
+
+    let (tone, steps) = match (number, alteration) {
+        (2, 0) => (ChordTone::Ninth, 2),
+        (4, 0) => (ChordTone::Eleventh, 5),
+        (5, -1) => (ChordTone::Fifth, 6),
+        (5, 1) => (ChordTone::Fifth, 8),
+        (6, 0) => (ChordTone::Sixth, 9),
+        (9, -1) => (ChordTone::FlatNinth, 1),
+        (9, 0) => (ChordTone::Ninth, 2),
+        (9, 1) => (ChordTone::SharpNinth, 3),
+        (11, 0) => (ChordTone::Eleventh, 5),
+        (11, 1) => (ChordTone::SharpEleventh, 6),
+        (13, -1) => (ChordTone::FlatThirteenth, 8),
+        (13, 0) => (ChordTone::Thirteenth, 9),
+        _ => return None,
+    };
+
+    Some((tone, steps, after))
+}
+
+/// The offending token: everything up to the next separator
+fn unknown_token(rest: &str) -> ChordParseError {
+    let token = rest
+        .split(['(', ')', ',', ' '])
+        .next()
+        .unwrap_or(rest)
+        .to_string();
+    ChordParseError::UnknownToken(token)
+}
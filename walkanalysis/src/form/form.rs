@@ -1,16 +1,25 @@
 use std::{fmt::Display, fs::File, path::Path};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::form::{chord::Chord, key::Key};
+use crate::form::{
+    chord::Chord,
+    key::Key,
+    meter::{BeatPosition, Meter},
+};
 
 use super::key::WrittenKey;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(try_from = "FormFields")]
 pub struct Form {
     tempo: u32,
     key: WrittenKey,
+    /// Meter at the start of the form, `FormPiece::Meter` can change it later on
+    meter: Meter,
     music: Vec<FormPiece>,
+    /// How many times the whole form is played, the count off is only played once
+    choruses: u32,
     // TODO: excuses: bars that are non-standard or may be interpreted more freely and are not checked?
 }
 
@@ -19,42 +28,222 @@ impl Form {
         if music.get(0).cloned() != Some(FormPiece::CountOff) {
             music.insert(0, FormPiece::CountOff);
         }
-        Self { tempo, key, music }
+        Self {
+            tempo,
+            key,
+            meter: Meter::default(),
+            music,
+            choruses: 1,
+        }
+    }
+
+    /// Sets how many times the form is played in a row
+    pub fn with_choruses(mut self, choruses: u32) -> Self {
+        self.choruses = choruses.max(1);
+        self
+    }
+
+    /// Sets the meter the form starts in, 4/4 if not set
+    pub fn with_meter(mut self, meter: Meter) -> Self {
+        self.meter = meter;
+        self
+    }
+
+    pub fn tempo(&self) -> u32 {
+        self.tempo
     }
 
     pub fn key(&self) -> WrittenKey {
         self.key
     }
 
+    pub fn meter(&self) -> Meter {
+        self.meter
+    }
+
     pub fn music(&self) -> &Vec<FormPiece> {
         &self.music
     }
+
+    pub fn choruses(&self) -> u32 {
+        self.choruses
+    }
+}
+
+/// A form as it is written in a file, checked before it becomes a `Form`
+#[derive(Deserialize)]
+struct FormFields {
+    tempo: u32,
+    key: WrittenKey,
+    #[serde(default)]
+    meter: Meter,
+    music: Vec<FormPiece>,
+    #[serde(default = "one_chorus", deserialize_with = "at_least_one_chorus")]
+    choruses: u32,
+}
+
+impl TryFrom<FormFields> for Form {
+    type Error = String;
+
+    fn try_from(fields: FormFields) -> Result<Self, Self::Error> {
+        let form = Form {
+            tempo: fields.tempo,
+            key: fields.key,
+            meter: fields.meter,
+            music: fields.music,
+            choruses: fields.choruses,
+        };
+
+        for (piece, meter) in form.pieces_with_meter() {
+            let FormPiece::SplitBar(chords) = piece else {
+                continue;
+            };
+            if chords.iter().any(|&(_, beats)| beats == 0) {
+                return Err("a chord in a split bar is held for at least a beat".into());
+            }
+            let beats: u32 = chords.iter().map(|&(_, beats)| beats).sum();
+            if beats > meter.beats_per_bar() {
+                return Err(format!(
+                    "a split bar of {} beats does not fit in a bar of {}",
+                    beats, meter
+                ));
+            }
+        }
+
+        Ok(form)
+    }
 }
 
-// Defines a bar of 4/4 form
+fn one_chorus() -> u32 {
+    1
+}
+
+fn at_least_one_chorus<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    match u32::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom("a form is played at least once")),
+        choruses => Ok(choruses),
+    }
+}
+
+// Defines a bar of the form, in whatever meter is active at that point
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum FormPiece {
     /// Set the key of the piece, if absent, assumes C major.
     /// This is necessary to determine note roles
     Key(Key),
-    /// expects 8 beats of counting of the tune
+    /// Change the meter from this point on
+    Meter(Meter),
+    /// expects two bars of counting of the tune
     CountOff,
     /// A bar where a single chord is played the whole time
     ChordBar(Chord),
-    /// A bar with two chords held for a half note
+    /// A bar with two chords, each held for half of the bar
     HalfBar(Chord, Chord),
+    /// A bar with any harmonic rhythm: every chord with the number of beats it is held for.
+    /// If the durations don't fill the bar, the last chord is held until the end of it.
+    /// Every chord is held for at least a beat and together they fit in the bar,
+    /// which is checked when a form is read.
+    /// Without chords, the bar is played over no chord.
+    SplitBar(Vec<(Chord, u32)>),
     /// Where to break the line of chords
     LineBreak,
+    /// Starts a labelled section of the form, such as "A" or "Bridge"
+    Section(String),
+    /// Start of a repeated part. Without one, a repeat goes back to the start
+    /// of the form or to the end of the previous repeat.
+    RepeatStart,
+    /// End of a repeated part, with how many times the part is played in total
+    RepeatEnd(u32),
+    /// Volta bracket: the bars up to the next ending or the end of the repeat
+    /// are only played on the pass with this number
+    Ending(u32),
+    /// Where a D.S. jumps back to
+    Segno,
+    /// Start of the coda, jumped to from `ToCoda`
+    Coda,
+    /// After a D.C. or D.S., continue at the coda from here
+    ToCoda,
+    /// Jump back to the start of the form, after which repeats are not taken
+    DaCapo,
+    /// Jump back to the segno, after which repeats are not taken
+    DalSegno,
+    /// After a D.C. or D.S., the form ends here
+    Fine,
 }
 
 impl FormPiece {
-    pub fn length_in_beats(&self) -> u32 {
+    pub fn length_in_beats(&self, meter: Meter) -> u32 {
         match self {
             FormPiece::Key(_) => 0,
-            FormPiece::CountOff => 8,
-            FormPiece::ChordBar(_) => 4,
-            FormPiece::HalfBar(_, _) => 4,
+            FormPiece::Meter(_) => 0,
+            FormPiece::CountOff => 2 * meter.beats_per_bar(),
+            FormPiece::ChordBar(_) => meter.beats_per_bar(),
+            FormPiece::HalfBar(_, _) => meter.beats_per_bar(),
+            FormPiece::SplitBar(_) => meter.beats_per_bar(),
             FormPiece::LineBreak => 0,
+            FormPiece::Section(_)
+            | FormPiece::RepeatStart
+            | FormPiece::RepeatEnd(_)
+            | FormPiece::Ending(_)
+            | FormPiece::Segno
+            | FormPiece::Coda
+            | FormPiece::ToCoda
+            | FormPiece::DaCapo
+            | FormPiece::DalSegno
+            | FormPiece::Fine => 0,
+        }
+    }
+
+    /// The chord that sounds on the given beat of this bar, if any
+    pub fn chord_at(&self, beat_in_bar: u32, meter: Meter) -> Option<&Chord> {
+        match self {
+            FormPiece::ChordBar(chord) => Some(chord),
+            FormPiece::HalfBar(chord1, chord2) => {
+                if beat_in_bar < meter.second_half_start() {
+                    Some(chord1)
+                } else {
+                    Some(chord2)
+                }
+            }
+            FormPiece::SplitBar(chords) => {
+                let mut start = 0;
+                chords
+                    .iter()
+                    .find(|(_, beats)| {
+                        start += beats;
+                        beat_in_bar < start
+                    })
+                    .or(chords.last())
+                    .map(|(chord, _)| chord)
+            }
+            _ => None,
+        }
+    }
+
+    /// Markers only describe the structure of the form, they are removed when it is unrolled
+    pub fn is_marker(&self) -> bool {
+        matches!(
+            self,
+            FormPiece::Section(_)
+                | FormPiece::RepeatStart
+                | FormPiece::RepeatEnd(_)
+                | FormPiece::Ending(_)
+                | FormPiece::Segno
+                | FormPiece::Coda
+                | FormPiece::ToCoda
+                | FormPiece::DaCapo
+                | FormPiece::DalSegno
+                | FormPiece::Fine
+        )
+    }
+
+    /// All chords in this bar, in the order they are played
+    pub fn chords(&self) -> Vec<&Chord> {
+        match self {
+            FormPiece::ChordBar(chord) => vec![chord],
+            FormPiece::HalfBar(chord1, chord2) => vec![chord1, chord2],
+            FormPiece::SplitBar(chords) => chords.iter().map(|(chord, _)| chord).collect(),
+            _ => vec![],
         }
     }
 }
@@ -63,6 +252,7 @@ impl Display for FormPiece {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FormPiece::Key(key) => write!(f, "{}", key),
+            FormPiece::Meter(meter) => write!(f, "{}", meter),
             FormPiece::CountOff => write!(f, "Count in"),
             FormPiece::ChordBar(chord) => write!(f, "𝅝 {}/{}", chord.sharp(), chord.flat()),
             FormPiece::HalfBar(chord1, chord2) => write!(
@@ -73,7 +263,23 @@ impl Display for FormPiece {
                 chord2.sharp(),
                 chord2.flat()
             ),
+            FormPiece::SplitBar(chords) => {
+                for (chord, beats) in chords {
+                    write!(f, "{}♩ {}/{} ", beats, chord.sharp(), chord.flat())?;
+                }
+                Ok(())
+            }
             FormPiece::LineBreak => write!(f, "\n"),
+            FormPiece::Section(label) => write!(f, "[{}]", label),
+            FormPiece::RepeatStart => write!(f, "𝄆"),
+            FormPiece::RepeatEnd(times) => write!(f, "𝄇 ×{}", times),
+            FormPiece::Ending(number) => write!(f, "{}.", number),
+            FormPiece::Segno => write!(f, "𝄋"),
+            FormPiece::Coda => write!(f, "𝄌"),
+            FormPiece::ToCoda => write!(f, "To 𝄌"),
+            FormPiece::DaCapo => write!(f, "D.C."),
+            FormPiece::DalSegno => write!(f, "D.S."),
+            FormPiece::Fine => write!(f, "Fine"),
         }
     }
 }
@@ -86,15 +292,74 @@ pub fn half_bar(chord1: Chord, chord2: Chord) -> FormPiece {
     FormPiece::HalfBar(chord1, chord2)
 }
 
+/// A bar with chords held for the given number of beats, e.g. `split_bar(vec![(A.min7(), 3), (D.dominant7(), 1)])`
+pub fn split_bar(chords: Vec<(Chord, u32)>) -> FormPiece {
+    FormPiece::SplitBar(chords)
+}
+
 impl Form {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Form, Box<dyn std::error::Error>> {
         let file = File::open(path)?;
         Ok(serde_json::from_reader(file)?)
     }
 
+    /// Length of the unrolled form, including all repeats and choruses
     pub fn length_in_beats(&self) -> u32 {
+        self.beat_positions().len() as u32
+    }
+
+    /// Every form piece with the meter that applies to it
+    pub fn pieces_with_meter(&self) -> Vec<(&FormPiece, Meter)> {
+        let mut meter = self.meter;
         self.music
             .iter()
-            .fold(0, |acc, elem| acc + elem.length_in_beats())
+            .map(|form_piece| {
+                if let FormPiece::Meter(new_meter) = form_piece {
+                    meter = *new_meter;
+                }
+                (form_piece, meter)
+            })
+            .collect()
+    }
+
+    /// The position in the unrolled form of every beat, starting with the count off
+    pub fn beat_positions(&self) -> Vec<BeatPosition> {
+        let mut positions = vec![];
+        let mut bar = 0;
+
+        // Every chorus is unrolled the same way, so they all have the same length
+        let unrolled = self.unrolled();
+        let count_off_length = unrolled
+            .pieces_with_meter()
+            .iter()
+            .filter(|(form_piece, _)| **form_piece == FormPiece::CountOff)
+            .map(|(form_piece, meter)| form_piece.length_in_beats(*meter))
+            .sum::<u32>();
+        let chorus_length = unrolled
+            .pieces_with_meter()
+            .iter()
+            .map(|(form_piece, meter)| form_piece.length_in_beats(*meter))
+            .sum::<u32>()
+            .saturating_sub(count_off_length)
+            / self.choruses;
+        let chorus_of = |beat: u32| {
+            beat.saturating_sub(count_off_length)
+                .checked_div(chorus_length)
+                .unwrap_or(0)
+        };
+
+        for (form_piece, meter) in unrolled.pieces_with_meter() {
+            for beat in 0..form_piece.length_in_beats(meter) {
+                positions.push(BeatPosition {
+                    chorus: chorus_of(positions.len() as u32),
+                    bar: bar + beat / meter.beats_per_bar(),
+                    beat_in_bar: beat % meter.beats_per_bar(),
+                    meter,
+                });
+            }
+            bar += form_piece.length_in_beats(meter) / meter.beats_per_bar();
+        }
+
+        positions
     }
 }
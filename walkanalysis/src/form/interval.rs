@@ -12,6 +12,7 @@ pub enum Interval {
     Tritone,
     DiminishedFifth,
     PerfectFifth,
+    AugmentedFifth,
     MinorSixth,
     MajorSixth,
     MinorSeventh,
@@ -29,7 +30,7 @@ impl Interval {
             Interval::PerfectFourth => 5,
             Interval::AugmentedFourth | Interval::Tritone | Interval::DiminishedFifth => 6,
             Interval::PerfectFifth => 7,
-            Interval::MinorSixth => 8,
+            Interval::AugmentedFifth | Interval::MinorSixth => 8,
             Interval::MajorSixth => 9,
             Interval::MinorSeventh => 10,
             Interval::MajorSeventh => 11,
@@ -0,0 +1,463 @@
+use std::{error::Error, fmt::Display};
+
+use crate::form::{
+    chord::Chord,
+    chord_parser::ChordParseError,
+    form::{Form, FormPiece},
+    key::{Quality, WrittenKey},
+    meter::Meter,
+    note::WrittenNote,
+};
+
+/// Tempo of songs that don't set one, iReal Pro then uses the tempo of the player
+const DEFAULT_TEMPO: u32 = 120;
+
+/// Every song is scrambled and prefixed with this
+const MUSIC_PREFIX: &str = "1r34LbKcu7";
+
+/// A tune from an iReal Pro link
+#[derive(Debug, Clone, PartialEq)]
+pub struct IRealSong {
+    pub title: String,
+    pub composer: String,
+    pub style: String,
+    pub form: Form,
+}
+
+/// All songs in an iReal Pro link, links with more than one song are playlists
+#[derive(Debug, Clone, PartialEq)]
+pub struct IRealPlaylist {
+    pub name: Option<String>,
+    pub songs: Vec<IRealSong>,
+}
+
+/// Why an iReal Pro link could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IRealError {
+    /// The link doesn't start with `irealb://` or `irealbook://`
+    NotAnIRealLink,
+    InvalidEncoding,
+    MissingField(&'static str),
+    InvalidKey(String),
+    InvalidChord {
+        chord: String,
+        error: ChordParseError,
+    },
+    /// A time signature such as `T40`, with no beats or a beat unit of zero
+    InvalidMeter(String),
+    /// The song has bars without a chord (N.C.), which can't be played over
+    NoChord,
+}
+
+impl Display for IRealError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IRealError::NotAnIRealLink => write!(f, "Not an irealb:// or irealbook:// link"),
+            IRealError::InvalidEncoding => write!(f, "Link is not correctly percent encoded"),
+            IRealError::MissingField(field) => write!(f, "Song has no {}", field),
+            IRealError::InvalidKey(key) => write!(f, "Invalid key `{}`", key),
+            IRealError::InvalidChord { chord, error } => {
+                write!(f, "Invalid chord `{}`: {}", chord, error)
+            }
+            IRealError::InvalidMeter(meter) => write!(f, "Invalid time signature `T{}`", meter),
+            IRealError::NoChord => write!(f, "Bars without a chord (N.C.) are not supported"),
+        }
+    }
+}
+
+impl Error for IRealError {}
+
+impl IRealPlaylist {
+    /// Reads an `irealb://` or (older) `irealbook://` link, as shared from iReal Pro
+    pub fn from_url(url: &str) -> Result<Self, IRealError> {
+        let url = url.trim();
+        let (data, scrambled) = if let Some(data) = url.strip_prefix("irealb://") {
+            (data, true)
+        } else if let Some(data) = url.strip_prefix("irealbook://") {
+            (data, false)
+        } else {
+            return Err(IRealError::NotAnIRealLink);
+        };
+
+        let data = urlencoding::decode(data).map_err(|_| IRealError::InvalidEncoding)?;
+        let mut parts: Vec<&str> = data.split("===").collect();
+
+        // In a playlist, the last part is the name of the playlist
+        let name = if parts.len() > 1 {
+            parts.pop().map(String::from)
+        } else {
+            None
+        };
+
+        let songs = parts
+            .into_iter()
+            .filter(|song| !song.is_empty())
+            .map(|song| {
+                if scrambled {
+                    IRealSong::from_irealb(song)
+                } else {
+                    IRealSong::from_irealbook(song)
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { name, songs })
+    }
+}
+
+impl IRealSong {
+    /// Reads a single song from an `irealb://` link, a song in a playlist is formatted as
+    /// "title=composer==style=key==music=groove=tempo=repeats"
+    fn from_irealb(song: &str) -> Result<Self, IRealError> {
+        let fields: Vec<&str> = song.split('=').collect();
+        let field = |index: usize, name| {
+            fields
+                .get(index)
+                .copied()
+                .ok_or(IRealError::MissingField(name))
+        };
+
+        let music = field(6, "music")?;
+        let music = unscramble(music.strip_prefix(MUSIC_PREFIX).unwrap_or(music));
+        let tempo = fields
+            .get(8)
+            .and_then(|tempo| tempo.parse().ok())
+            .filter(|&tempo| tempo > 0)
+            .unwrap_or(DEFAULT_TEMPO);
+
+        Ok(Self {
+            title: field(0, "title")?.into(),
+            composer: field(1, "composer")?.into(),
+            style: field(3, "style")?.into(),
+            form: parse_music(&music, parse_key(field(4, "key")?)?, tempo)?,
+        })
+    }
+
+    /// Reads a single song from an `irealbook://` link, formatted as
+    /// "title=composer=style=key=n=music" with the music not scrambled
+    fn from_irealbook(song: &str) -> Result<Self, IRealError> {
+        let fields: Vec<&str> = song.split('=').collect();
+        let field = |index: usize, name| {
+            fields
+                .get(index)
+                .copied()
+                .ok_or(IRealError::MissingField(name))
+        };
+
+        Ok(Self {
+            title: field(0, "title")?.into(),
+            composer: field(1, "composer")?.into(),
+            style: field(2, "style")?.into(),
+            form: parse_music(
+                field(5, "music")?,
+                parse_key(field(3, "key")?)?,
+                DEFAULT_TEMPO,
+            )?,
+        })
+    }
+}
+
+/// Keys are written as "Eb" or "C-" for minor keys
+fn parse_key(key: &str) -> Result<WrittenKey, IRealError> {
+    let invalid = || IRealError::InvalidKey(key.into());
+    let (root, quality) = WrittenNote::parse_prefix(key).ok_or_else(invalid)?;
+    let quality = match quality {
+        "" => Quality::Major,
+        "-" => Quality::Minor,
+        _ => return Err(invalid()),
+    };
+    Ok(WrittenKey::new(root, quality))
+}
+
+/// Undoes the scrambling of the music: every block of 50 characters has its first 5
+/// and characters 10 to 24 mirrored with the other end of the block, except for the
+/// last block. Afterwards some common sequences were replaced by shorter ones.
+fn unscramble(music: &str) -> String {
+    let mut rest: Vec<char> = music.chars().collect();
+    let mut result = String::new();
+
+    while rest.len() > 51 {
+        let mut block: Vec<char> = rest.drain(..50).collect();
+        for i in (0..5).chain(10..24) {
+            block.swap(i, 49 - i);
+        }
+        result.extend(block);
+    }
+    result.extend(rest);
+
+    result
+        .replace("Kcl", "| x")
+        .replace("LZ", " |")
+        .replace("XyQ", "   ")
+}
+
+/// Builds the form out of the unscrambled music, which is a sequence of bar lines, chords
+/// and markers such as "{*AT44C-7 F7 |Bb^7 |N1Ah7 D7 }N2G-6 Z"
+fn parse_music(music: &str, key: WrittenKey, tempo: u32) -> Result<Form, IRealError> {
+    let mut parser = MusicParser::default();
+    let mut rest = music;
+
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '|' | '[' | ']' | 'Z' => parser.finish_bar(),
+            '{' => {
+                parser.finish_bar();
+                parser.music.push(FormPiece::RepeatStart);
+            }
+            '}' => {
+                parser.finish_bar();
+                let times = parser.repeat_times.take().unwrap_or(2);
+                parser.music.push(FormPiece::RepeatEnd(times));
+            }
+            '*' => {
+                let Some(label) = rest.chars().next() else {
+                    break;
+                };
+                rest = &rest[label.len_utf8()..];
+                parser.finish_bar();
+                parser.start_section(match label {
+                    'i' => "Intro".into(),
+                    'v' => "Verse".into(),
+                    label => label.to_string(),
+                });
+            }
+            'T' => {
+                let digits: String = rest.chars().take(2).collect();
+                rest = &rest[digits.len()..];
+                parser.finish_bar();
+                let meter = match digits.as_str() {
+                    "12" => Meter::new(12, 8),
+                    digits => {
+                        let mut digits = digits.chars().filter_map(|d| d.to_digit(10));
+                        Meter::new(digits.next().unwrap_or(4), digits.next().unwrap_or(4))
+                    }
+                };
+                parser.set_meter(meter.ok_or(IRealError::InvalidMeter(digits))?);
+            }
+            'N' => {
+                let next = rest.chars().next();
+                rest = &rest[next.map_or(0, char::len_utf8)..];
+                let number = next.and_then(|n| n.to_digit(10));
+                parser.finish_bar();
+                // N0 is an ending without a number
+                if let Some(number @ 1..) = number {
+                    parser.music.push(FormPiece::Ending(number));
+                }
+            }
+            '<' => {
+                let end = rest.find('>').unwrap_or(rest.len());
+                parser.comment(&rest[..end]);
+                rest = &rest[(end + 1).min(rest.len())..];
+            }
+            '(' => {
+                // Alternative chords are not played
+                let end = rest.find(')').unwrap_or(rest.len());
+                rest = &rest[(end + 1).min(rest.len())..];
+            }
+            'S' => {
+                parser.finish_bar();
+                parser.music.push(FormPiece::Segno);
+            }
+            'Q' => {
+                // The first coda sign is where to jump from, the second where to jump to
+                if parser.coda_seen {
+                    parser.finish_bar();
+                    parser.music.push(FormPiece::Coda);
+                } else {
+                    parser.after_bar.push(FormPiece::ToCoda);
+                    parser.coda_seen = true;
+                }
+            }
+            // Repeat the previous bar
+            'x' => parser.bar = parser.previous_bars.last().cloned().unwrap_or_default(),
+            // Repeat the previous two bars
+            'r' => {
+                parser.finish_bar();
+                let previous_bars = parser.previous_bars.clone();
+                for bar in previous_bars.iter().rev().take(2).rev() {
+                    parser.bar = bar.clone();
+                    parser.finish_bar();
+                }
+            }
+            'n' => return Err(IRealError::NoChord),
+            // Slashes hold the previous chord
+            'p' => {
+                let chord = parser.last_chord();
+                parser.bar.extend(chord);
+            }
+            'A'..='G' | 'W' => {
+                let length = chord_length(&format!("{}{}", c, rest));
+                let symbol = format!("{}{}", c, &rest[..length - 1]);
+                rest = &rest[length - 1..];
+                let chord = parser.read_chord(&symbol)?;
+                parser.bar.push(chord);
+            }
+            // Spacing, fermatas, chord sizes and the end sign don't change the music
+            _ => (),
+        }
+    }
+    parser.finish_bar();
+    if parser.bars_on_line > 0 {
+        parser.music.push(FormPiece::LineBreak);
+    }
+
+    Ok(Form::new(tempo, key, parser.music).with_meter(parser.first_meter.unwrap_or_default()))
+}
+
+/// The length of the chord symbol at the start of `s`: a root, quality and slash bass
+fn chord_length(s: &str) -> usize {
+    let mut length = 1;
+    let rest = |length| &s[length..];
+
+    if rest(length).starts_with(['b', '#']) {
+        length += 1;
+    }
+    loop {
+        if let Some(word) = ["sus", "alt", "add"]
+            .into_iter()
+            .find(|word| rest(length).starts_with(word))
+        {
+            length += word.len();
+        } else if rest(length).starts_with(|c: char| "^-+ohb#0123456789".contains(c)) {
+            length += 1;
+        } else {
+            break;
+        }
+    }
+    if rest(length).starts_with('/') && rest(length + 1).starts_with(|c| ('A'..='G').contains(&c)) {
+        length += 2;
+        if rest(length).starts_with(['b', '#']) {
+            length += 1;
+        }
+    }
+    length
+}
+
+#[derive(Default)]
+struct MusicParser {
+    music: Vec<FormPiece>,
+    /// Chords of the bar that is being read
+    bar: Vec<Chord>,
+    /// Chords of all bars so far, to repeat them
+    previous_bars: Vec<Vec<Chord>>,
+    /// Jumps and endings take effect after the bar they are written in
+    after_bar: Vec<FormPiece>,
+    /// Set by comments such as "3x", for the next end of a repeat
+    repeat_times: Option<u32>,
+    coda_seen: bool,
+    first_meter: Option<Meter>,
+    meter: Meter,
+    bars_on_line: u32,
+}
+
+impl MusicParser {
+    fn last_chord(&self) -> Option<Chord> {
+        self.bar
+            .last()
+            .or(self.previous_bars.last().and_then(|bar| bar.last()))
+            .cloned()
+    }
+
+    /// Reads a chord symbol written with iReal Pro's shorthand, where "^" is a major seventh,
+    /// "h" half diminished and "W" an invisible root that repeats the previous chord
+    fn read_chord(&self, symbol: &str) -> Result<Chord, IRealError> {
+        if let Some(rest) = symbol.strip_prefix('W') {
+            let chord = self.last_chord().ok_or_else(|| IRealError::InvalidChord {
+                chord: symbol.into(),
+                error: ChordParseError::InvalidRoot(symbol.into()),
+            })?;
+            return Ok(
+                match rest.strip_prefix('/').and_then(WrittenNote::parse_prefix) {
+                    Some((bass, _)) => chord.with_bass(bass.unwrite()),
+                    None => chord,
+                },
+            );
+        }
+
+        symbol
+            .replace('^', "Δ")
+            .replace('h', "ø")
+            .parse()
+            .map_err(|error| IRealError::InvalidChord {
+                chord: symbol.into(),
+                error,
+            })
+    }
+
+    fn finish_bar(&mut self) {
+        if !self.bar.is_empty() {
+            if self.bars_on_line == 4 {
+                self.music.push(FormPiece::LineBreak);
+                self.bars_on_line = 0;
+            }
+
+            let chords = std::mem::take(&mut self.bar);
+            self.music.push(bar_with_chords(&chords, self.meter));
+            self.previous_bars.push(chords);
+            self.bars_on_line += 1;
+        }
+        self.music.append(&mut self.after_bar);
+    }
+
+    fn start_section(&mut self, label: String) {
+        if self.bars_on_line > 0 {
+            self.music.push(FormPiece::LineBreak);
+            self.bars_on_line = 0;
+        }
+        self.music.push(FormPiece::Section(label));
+    }
+
+    fn set_meter(&mut self, meter: Meter) {
+        if self.first_meter.is_none() && self.previous_bars.is_empty() {
+            self.first_meter = Some(meter);
+        } else if meter != self.meter {
+            self.music.push(FormPiece::Meter(meter));
+        }
+        self.meter = meter;
+    }
+
+    /// Comments hold jumps such as "D.C. al Fine" and how often a repeat is played, as in "3x"
+    fn comment(&mut self, comment: &str) {
+        let lowercase = comment.to_lowercase();
+        if lowercase.contains("d.c.") {
+            self.after_bar.push(FormPiece::DaCapo);
+        } else if lowercase.contains("d.s.") {
+            self.after_bar.push(FormPiece::DalSegno);
+        } else if lowercase.trim() == "fine" {
+            self.after_bar.push(FormPiece::Fine);
+        }
+
+        self.repeat_times = lowercase
+            .split_whitespace()
+            .find_map(|word| word.strip_suffix('x')?.parse().ok())
+            .or(self.repeat_times);
+    }
+}
+
+/// Chords are spread evenly over the bar, with the first chords held longer if they don't fit,
+/// so three chords in 4/4 are held for 2, 1 and 1 beats
+fn bar_with_chords(chords: &[Chord], meter: Meter) -> FormPiece {
+    match chords {
+        [chord] => FormPiece::ChordBar(chord.clone()),
+        [chord1, chord2] => FormPiece::HalfBar(chord1.clone(), chord2.clone()),
+        _ => {
+            let amount = chords.len() as u32;
+            let beats_per_bar = meter.beats_per_bar();
+            FormPiece::SplitBar(
+                chords
+                    .iter()
+                    .zip(0..)
+                    .map(|(chord, i)| {
+                        let beats = if amount >= beats_per_bar {
+                            1
+                        } else {
+                            beats_per_bar / amount + u32::from(i < beats_per_bar % amount)
+                        };
+                        (chord.clone(), beats)
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
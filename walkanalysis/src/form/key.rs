@@ -125,11 +125,23 @@ impl Display for WrittenKey {
 }
 
 impl WrittenKey {
+    pub fn new(root: WrittenNote, quality: Quality) -> WrittenKey {
+        Self { root, quality }
+    }
+
+    pub fn root(&self) -> WrittenNote {
+        self.root
+    }
+
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+
     pub fn spell_preference(&self) -> Spelling {
         match self.root.accidental {
             Accidental::Natural => (),
-            Accidental::Sharp => return Spelling::Sharp,
-            Accidental::Flat => return Spelling::Flat,
+            Accidental::Sharp | Accidental::DoubleSharp => return Spelling::Sharp,
+            Accidental::Flat | Accidental::DoubleFlat => return Spelling::Flat,
         }
 
         match self.quality {
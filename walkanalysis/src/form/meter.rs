@@ -0,0 +1,96 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Time signature of (a part of) a form, e.g. 3/4 for a waltz
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "MeterFields")]
+pub struct Meter {
+    beats_per_bar: u32,
+    /// The note value that gets the beat, 4 for a quarter note
+    beat_unit: u32,
+}
+
+/// A meter as it is written in a file, checked before it becomes a `Meter`
+#[derive(Deserialize)]
+struct MeterFields {
+    beats_per_bar: u32,
+    beat_unit: u32,
+}
+
+impl TryFrom<MeterFields> for Meter {
+    type Error = &'static str;
+
+    fn try_from(fields: MeterFields) -> Result<Self, Self::Error> {
+        Meter::new(fields.beats_per_bar, fields.beat_unit)
+            .ok_or("a meter has at least one beat per bar and a beat unit")
+    }
+}
+
+impl Meter {
+    /// The meter with the given number of beats in a bar, none if either number is 0
+    pub const fn new(beats_per_bar: u32, beat_unit: u32) -> Option<Self> {
+        if beats_per_bar == 0 || beat_unit == 0 {
+            return None;
+        }
+        Some(Self {
+            beats_per_bar,
+            beat_unit,
+        })
+    }
+
+    pub fn beats_per_bar(&self) -> u32 {
+        self.beats_per_bar
+    }
+
+    pub fn beat_unit(&self) -> u32 {
+        self.beat_unit
+    }
+
+    /// The beat on which the second half of the bar starts. Odd bars are split with the
+    /// longer half first, so 3/4 splits as 2 + 1 and 5/4 as 3 + 2.
+    pub fn second_half_start(&self) -> u32 {
+        self.beats_per_bar.div_ceil(2)
+    }
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        COMMON_TIME
+    }
+}
+
+impl Display for Meter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.beats_per_bar, self.beat_unit)
+    }
+}
+
+pub const COMMON_TIME: Meter = Meter::new(4, 4).unwrap();
+pub const WALTZ: Meter = Meter::new(3, 4).unwrap();
+
+/// Where a beat falls in the form
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BeatPosition {
+    /// Which time the form is played, starting at 0. The count off belongs to the first chorus.
+    pub chorus: u32,
+    /// Bar number counted from the start of the count off, starting at 0
+    pub bar: u32,
+    /// Beat within the bar, starting at 0
+    pub beat_in_bar: u32,
+    /// The meter of the bar the beat is in
+    pub meter: Meter,
+}
+
+impl BeatPosition {
+    pub fn is_downbeat(&self) -> bool {
+        self.beat_in_bar == 0
+    }
+}
+
+/// Formats as "bar.beat", both counted from 1
+impl Display for BeatPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.bar + 1, self.beat_in_bar + 1)
+    }
+}
@@ -1,7 +1,15 @@
+pub mod chart;
 pub mod chord;
+pub mod chord_name;
+pub mod chord_parser;
 pub mod form;
 pub mod interval;
+pub mod ireal;
 pub mod key;
+pub mod meter;
+pub mod musicxml;
 pub mod note;
+pub mod pitch;
 pub mod scale;
 pub mod songs;
+pub mod unroll;
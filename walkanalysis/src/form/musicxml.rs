@@ -0,0 +1,601 @@
+use std::{error::Error, fmt::Display, fs, path::Path};
+
+use roxmltree::Node;
+
+use crate::form::{
+    chord::Chord,
+    chord_parser::ChordParseError,
+    form::{Form, FormPiece},
+    key::{Quality, WrittenKey},
+    meter::Meter,
+    note::WrittenNote,
+    pitch::Pitch,
+};
+
+/// Tempo of scores that don't set one
+const DEFAULT_TEMPO: u32 = 120;
+
+/// Bars per line if the score doesn't say where its systems start
+const BARS_PER_LINE: usize = 4;
+
+/// A lead sheet read from a MusicXML file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MusicXmlScore {
+    pub title: Option<String>,
+    pub form: Form,
+    /// The written bass part, if the score has a part in bass clef. Holds the note that starts on
+    /// every beat of the form after the count off, to be played with `WrittenLine`.
+    pub bass_line: Option<Vec<Option<Pitch>>>,
+}
+
+/// Why a MusicXML file could not be read, measures are counted from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MusicXmlError {
+    Xml(String),
+    /// Only `score-partwise` files are supported, as written by most notation software
+    NotPartwise,
+    NoParts,
+    UnknownChordKind {
+        measure: usize,
+        kind: String,
+    },
+    InvalidChord {
+        measure: usize,
+        symbol: String,
+        error: ChordParseError,
+    },
+    /// The first measures of the score have no chord symbol to play over
+    NoChord {
+        measure: usize,
+    },
+    /// Divisions per quarter note that are not a positive number
+    InvalidDivisions {
+        measure: usize,
+        divisions: String,
+    },
+    /// A time signature without beats, or a composite one like `3+2/8`
+    InvalidTimeSignature {
+        measure: usize,
+        time: String,
+    },
+}
+
+impl Display for MusicXmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MusicXmlError::Xml(error) => write!(f, "Invalid XML: {}", error),
+            MusicXmlError::NotPartwise => write!(f, "Only partwise MusicXML scores are supported"),
+            MusicXmlError::NoParts => write!(f, "Score has no parts"),
+            MusicXmlError::UnknownChordKind { measure, kind } => {
+                write!(f, "Unknown chord kind `{}` in measure {}", kind, measure)
+            }
+            MusicXmlError::InvalidChord {
+                measure,
+                symbol,
+                error,
+            } => write!(
+                f,
+                "Invalid chord `{}` in measure {}: {}",
+                symbol, measure, error
+            ),
+            MusicXmlError::NoChord { measure } => {
+                write!(f, "Measure {} has no chord symbol", measure)
+            }
+            MusicXmlError::InvalidDivisions { measure, divisions } => {
+                write!(
+                    f,
+                    "Invalid divisions `{}` in measure {}",
+                    divisions, measure
+                )
+            }
+            MusicXmlError::InvalidTimeSignature { measure, time } => write!(
+                f,
+                "Unsupported time signature `{}` in measure {}",
+                time, measure
+            ),
+        }
+    }
+}
+
+impl Error for MusicXmlError {}
+
+impl MusicXmlScore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(&fs::read_to_string(path)?)?)
+    }
+
+    /// Reads the key, tempo, meter and chord symbols (`<harmony>` elements) of the first part
+    /// that has chord symbols, and the notes of the first part in bass clef
+    pub fn parse(xml: &str) -> Result<Self, MusicXmlError> {
+        // Files exported by notation software start with a DOCTYPE
+        let options = roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        let document = roxmltree::Document::parse_with_options(xml, options)
+            .map_err(|e| MusicXmlError::Xml(e.to_string()))?;
+        let score = document.root_element();
+        if !score.has_tag_name("score-partwise") {
+            return Err(MusicXmlError::NotPartwise);
+        }
+
+        let title = child_text(score, &["work", "work-title"])
+            .or_else(|| child_text(score, &["movement-title"]))
+            .map(String::from);
+
+        let parts: Vec<Node> = score
+            .children()
+            .filter(|n| n.has_tag_name("part"))
+            .collect();
+        let first_part = *parts.first().ok_or(MusicXmlError::NoParts)?;
+        let harmony_part = parts
+            .iter()
+            .copied()
+            .find(|part| part.descendants().any(|n| n.has_tag_name("harmony")))
+            .unwrap_or(first_part);
+        let bass_part = parts.iter().copied().find(|part| {
+            part.descendants()
+                .any(|n| n.has_tag_name("clef") && child_text(n, &["sign"]) == Some("F"))
+        });
+
+        let measures = read_part(harmony_part)?;
+        let form = build_form(&measures)?;
+        let bass_line = bass_part
+            .map(|part| read_part(part).map(|measures| build_line(&measures)))
+            .transpose()?;
+
+        Ok(Self {
+            title,
+            form,
+            bass_line,
+        })
+    }
+}
+
+/// Everything in a measure of a single part, positions are in divisions from the start of the measure
+struct Measure {
+    number: usize,
+    divisions: u32,
+    meter: Meter,
+    /// Set if the key is written in this measure
+    key: Option<WrittenKey>,
+    tempo: Option<u32>,
+    new_system: bool,
+    harmonies: Vec<(u32, Chord)>,
+    /// Notes that start in this measure, tied notes and rests have no pitch
+    notes: Vec<(u32, Option<Pitch>)>,
+}
+
+impl Measure {
+    /// The beat a position falls on, if it falls exactly on a beat
+    fn beat(&self, position: u32) -> Option<u32> {
+        let divisions_per_beat = self.divisions * 4;
+        let position = position * self.meter.beat_unit();
+        position
+            .is_multiple_of(divisions_per_beat)
+            .then_some(position / divisions_per_beat)
+    }
+
+    /// The beat a position falls in
+    fn beat_containing(&self, position: u32) -> u32 {
+        (position * self.meter.beat_unit() / (self.divisions * 4))
+            .min(self.meter.beats_per_bar() - 1)
+    }
+}
+
+fn read_part(part: Node) -> Result<Vec<Measure>, MusicXmlError> {
+    let mut measures = vec![];
+
+    let mut divisions = 1;
+    let mut meter = Meter::default();
+    let mut octave_change = 0;
+
+    for (number, measure) in (1..).zip(part.children().filter(|n| n.has_tag_name("measure"))) {
+        let mut key = None;
+        let mut tempo = None;
+        let mut new_system = false;
+        let mut harmonies = vec![];
+        let mut notes = vec![];
+        let mut position: u32 = 0;
+
+        for element in measure.children().filter(Node::is_element) {
+            match element.tag_name().name() {
+                "attributes" => {
+                    if let Some(d) = child_text(element, &["divisions"]) {
+                        divisions = d.parse().ok().filter(|&d| d > 0).ok_or_else(|| {
+                            MusicXmlError::InvalidDivisions {
+                                measure: number,
+                                divisions: d.into(),
+                            }
+                        })?;
+                    }
+                    if let Some(k) = element.children().find(|n| n.has_tag_name("key")) {
+                        key = read_key(k);
+                    }
+                    if let Some(time) = element.children().find(|n| n.has_tag_name("time")) {
+                        // Time without beats is a senza-misura measure, which keeps the meter
+                        if let (Some(beats), Some(beat_type)) = (
+                            child_text(time, &["beats"]),
+                            child_text(time, &["beat-type"]),
+                        ) {
+                            let invalid = || MusicXmlError::InvalidTimeSignature {
+                                measure: number,
+                                time: format!("{}/{}", beats, beat_type),
+                            };
+                            meter = beats
+                                .parse()
+                                .ok()
+                                .zip(beat_type.parse().ok())
+                                .and_then(|(beats, beat_type)| Meter::new(beats, beat_type))
+                                .ok_or_else(invalid)?;
+                        }
+                    }
+                    if let Some(change) = child_text(element, &["transpose", "octave-change"]) {
+                        octave_change = change.parse().unwrap_or(0);
+                    }
+                }
+                "direction" | "sound" => {
+                    tempo = tempo.or_else(|| read_tempo(element));
+                }
+                "print" => new_system |= element.attribute("new-system") == Some("yes"),
+                "harmony" => {
+                    let offset = child_text(element, &["offset"])
+                        .and_then(|o| o.parse::<i64>().ok())
+                        .unwrap_or(0);
+                    let at = (position as i64 + offset).max(0) as u32;
+                    if let Some(chord) = read_harmony(element, number)? {
+                        harmonies.push((at, chord));
+                    }
+                }
+                "note" => {
+                    let duration = child_text(element, &["duration"])
+                        .and_then(|d| d.parse().ok())
+                        .unwrap_or(0);
+                    let is_chord = element.children().any(|n| n.has_tag_name("chord"));
+                    let is_grace = element.children().any(|n| n.has_tag_name("grace"));
+                    let is_main_voice = child_text(element, &["voice"]).unwrap_or("1") == "1"
+                        && child_text(element, &["staff"]).unwrap_or("1") == "1";
+                    if is_chord || is_grace {
+                        continue;
+                    }
+
+                    if is_main_voice {
+                        let is_tied = element
+                            .children()
+                            .any(|n| n.has_tag_name("tie") && n.attribute("type") == Some("stop"));
+                        let pitch = element
+                            .children()
+                            .find(|n| n.has_tag_name("pitch"))
+                            .and_then(read_pitch)
+                            .map(|pitch| pitch.add_steps(12 * octave_change))
+                            .filter(|_| !is_tied);
+                        notes.push((position, pitch));
+                    }
+                    position += duration;
+                }
+                "backup" => {
+                    let duration: u32 = child_text(element, &["duration"])
+                        .and_then(|d| d.parse().ok())
+                        .unwrap_or(0);
+                    position = position.saturating_sub(duration);
+                }
+                "forward" => {
+                    position += child_text(element, &["duration"])
+                        .and_then(|d| d.parse::<u32>().ok())
+                        .unwrap_or(0);
+                }
+                _ => (),
+            }
+        }
+
+        measures.push(Measure {
+            number,
+            divisions,
+            meter,
+            key,
+            tempo,
+            new_system,
+            harmonies,
+            notes,
+        });
+    }
+
+    Ok(measures)
+}
+
+fn build_form(measures: &[Measure]) -> Result<Form, MusicXmlError> {
+    let first = measures.first();
+    let key = first
+        .and_then(|m| m.key)
+        .unwrap_or_else(|| read_fifths(0, false));
+    let meter = first.map(|m| m.meter).unwrap_or_default();
+    let tempo = measures
+        .iter()
+        .find_map(|m| m.tempo)
+        .unwrap_or(DEFAULT_TEMPO);
+    let has_systems = measures.iter().any(|m| m.new_system);
+
+    let mut music = vec![];
+    let mut current_key = key;
+    let mut current_meter = meter;
+    let mut last_chord = None;
+    let mut bars_on_line = 0;
+
+    for measure in measures {
+        if measure.meter != current_meter {
+            music.push(FormPiece::Meter(measure.meter));
+            current_meter = measure.meter;
+        }
+        if let Some(key) = measure.key.filter(|&key| key != current_key) {
+            music.push(FormPiece::Key(key.unwrite()));
+            current_key = key;
+        }
+
+        let line_is_full = if has_systems {
+            measure.new_system
+        } else {
+            bars_on_line == BARS_PER_LINE
+        };
+        if line_is_full && bars_on_line > 0 {
+            music.push(FormPiece::LineBreak);
+            bars_on_line = 0;
+        }
+
+        music.push(bar_from_harmonies(measure, &mut last_chord)?);
+        bars_on_line += 1;
+    }
+    if bars_on_line > 0 {
+        music.push(FormPiece::LineBreak);
+    }
+
+    Ok(Form::new(tempo, key, music).with_meter(meter))
+}
+
+/// A bar with the chords of the measure, a chord that started in an earlier measure is held
+/// until the first chord symbol of this one
+fn bar_from_harmonies(
+    measure: &Measure,
+    last_chord: &mut Option<Chord>,
+) -> Result<FormPiece, MusicXmlError> {
+    let mut chords: Vec<(u32, Chord)> = vec![];
+    for (position, chord) in &measure.harmonies {
+        let beat = measure.beat_containing(*position);
+        // Of two chords on the same beat, the last one is played
+        chords.retain(|(b, _)| *b != beat);
+        chords.push((beat, chord.clone()));
+    }
+    chords.sort_by_key(|(beat, _)| *beat);
+
+    if chords.first().map(|(beat, _)| *beat) != Some(0) {
+        let held = last_chord.clone().ok_or(MusicXmlError::NoChord {
+            measure: measure.number,
+        })?;
+        chords.insert(0, (0, held));
+    }
+    *last_chord = chords.last().map(|(_, chord)| chord.clone());
+
+    let beats_per_bar = measure.meter.beats_per_bar();
+    Ok(match chords.as_slice() {
+        [(_, chord)] => FormPiece::ChordBar(chord.clone()),
+        [(_, chord1), (beat, chord2)] if *beat == measure.meter.second_half_start() => {
+            FormPiece::HalfBar(chord1.clone(), chord2.clone())
+        }
+        _ => FormPiece::SplitBar(
+            chords
+                .iter()
+                .enumerate()
+                .map(|(i, (beat, chord))| {
+                    let end = chords.get(i + 1).map(|(b, _)| *b).unwrap_or(beats_per_bar);
+                    (chord.clone(), end - beat)
+                })
+                .collect(),
+        ),
+    })
+}
+
+/// The pitch that starts on every beat of the part
+fn build_line(measures: &[Measure]) -> Vec<Option<Pitch>> {
+    let mut line = vec![];
+
+    for measure in measures {
+        let bar_start = line.len();
+        line.resize(bar_start + measure.meter.beats_per_bar() as usize, None);
+        for &(position, pitch) in &measure.notes {
+            if let Some(beat) = measure
+                .beat(position)
+                .filter(|&beat| beat < measure.meter.beats_per_bar())
+            {
+                line[bar_start + beat as usize] = pitch;
+            }
+        }
+    }
+
+    line
+}
+
+fn child_text<'a>(node: Node<'a, 'a>, path: &[&str]) -> Option<&'a str> {
+    let mut node = node;
+    for name in path {
+        node = node.children().find(|n| n.has_tag_name(*name))?;
+    }
+    node.text().map(str::trim)
+}
+
+fn read_key(key: Node) -> Option<WrittenKey> {
+    let fifths = child_text(key, &["fifths"])?.parse().ok()?;
+    let minor = child_text(key, &["mode"]) == Some("minor");
+    Some(read_fifths(fifths, minor))
+}
+
+/// Roots of the major keys from seven flats up, followed by those only used for minor keys
+const CIRCLE_OF_FIFTHS: [&str; 18] = [
+    "Cb", "Gb", "Db", "Ab", "Eb", "Bb", "F", "C", "G", "D", "A", "E", "B", "F#", "C#", "G#", "D#",
+    "A#",
+];
+
+/// The key with the given number of sharps (positive) or flats (negative)
+fn read_fifths(fifths: i32, minor: bool) -> WrittenKey {
+    let index = (fifths.clamp(-7, 7) + 7) as usize;
+    let (root, quality) = if minor {
+        // The relative minor has three more sharps
+        (CIRCLE_OF_FIFTHS[index + 3], Quality::Minor)
+    } else {
+        (CIRCLE_OF_FIFTHS[index], Quality::Major)
+    };
+    WrittenKey::new(WrittenNote::parse_prefix(root).unwrap().0, quality)
+}
+
+/// The number of sharps (positive) or flats (negative) of a key, 0 for keys with more than 7
+pub(crate) fn fifths(key: WrittenKey) -> i32 {
+    let offset = match key.quality() {
+        Quality::Major => 7,
+        Quality::Minor => 10,
+    };
+    CIRCLE_OF_FIFTHS
+        .iter()
+        .position(|root| WrittenNote::parse_prefix(root).map(|(note, _)| note) == Some(key.root()))
+        .map(|index| index as i32 - offset)
+        .filter(|fifths| (-7..=7).contains(fifths))
+        .unwrap_or(0)
+}
+
+fn read_tempo(element: Node) -> Option<u32> {
+    element
+        .descendants()
+        .find_map(|n| n.attribute("tempo"))
+        .or_else(|| {
+            element
+                .descendants()
+                .find(|n| n.has_tag_name("per-minute"))
+                .and_then(|n| n.text())
+        })
+        .and_then(|tempo| tempo.trim().parse::<f32>().ok())
+        .map(|tempo| tempo.round() as u32)
+}
+
+fn read_pitch(pitch: Node) -> Option<Pitch> {
+    let step = match child_text(pitch, &["step"])? {
+        "C" => 0,
+        "D" => 2,
+        "E" => 4,
+        "F" => 5,
+        "G" => 7,
+        "A" => 9,
+        "B" => 11,
+        _ => return None,
+    };
+    let alter: f32 = child_text(pitch, &["alter"])
+        .and_then(|a| a.parse().ok())
+        .unwrap_or(0.);
+    let octave: i32 = child_text(pitch, &["octave"])?.parse().ok()?;
+
+    Some(Pitch::from_midi(
+        (octave + 1) * 12 + step + alter.round() as i32,
+    ))
+}
+
+/// Writes an alteration as "b", "#", "bb" or "##"
+fn accidental(alter: &str) -> &'static str {
+    match alter.parse::<f32>().map(|a| a.round() as i32) {
+        Ok(-2) => "bb",
+        Ok(-1) => "b",
+        Ok(1) => "#",
+        Ok(2) => "##",
+        _ => "",
+    }
+}
+
+/// Turns a `<harmony>` element into a chord symbol and parses it, `None` for N.C.
+fn read_harmony(harmony: Node, measure: usize) -> Result<Option<Chord>, MusicXmlError> {
+    let Some(step) = child_text(harmony, &["root", "root-step"]) else {
+        return Ok(None);
+    };
+    let root_alter = child_text(harmony, &["root", "root-alter"]).unwrap_or("0");
+
+    let kind_node = harmony.children().find(|n| n.has_tag_name("kind"));
+    let kind = kind_node.and_then(|n| n.text()).unwrap_or("major").trim();
+    let suffix = match kind {
+        "none" => return Ok(None),
+        "other" => kind_node
+            .and_then(|n| n.attribute("text"))
+            .unwrap_or("")
+            .to_string(),
+        kind => kind_suffix(kind)
+            .ok_or_else(|| MusicXmlError::UnknownChordKind {
+                measure,
+                kind: kind.into(),
+            })?
+            .to_string(),
+    };
+
+    let mut symbol = format!("{}{}{}", step, accidental(root_alter), suffix);
+
+    for degree in harmony.children().filter(|n| n.has_tag_name("degree")) {
+        let value = child_text(degree, &["degree-value"]).unwrap_or("");
+        let alter = accidental(child_text(degree, &["degree-alter"]).unwrap_or("0"));
+        match child_text(degree, &["degree-type"]).unwrap_or("add") {
+            "add" => symbol += &format!("add{}{}", alter, value),
+            "subtract" => symbol += &format!("no{}", value),
+            _ if alter.is_empty() => (),
+            _ => symbol += &format!("{}{}", alter, value),
+        }
+    }
+
+    if let Some(bass_step) = child_text(harmony, &["bass", "bass-step"]) {
+        let bass_alter = child_text(harmony, &["bass", "bass-alter"]).unwrap_or("0");
+        symbol += &format!("/{}{}", bass_step, accidental(bass_alter));
+    }
+
+    symbol
+        .parse()
+        .map(Some)
+        .map_err(|error| MusicXmlError::InvalidChord {
+            measure,
+            symbol,
+            error,
+        })
+}
+
+/// MusicXML chord kinds with the chord symbol they stand for, without the root
+const CHORD_KINDS: [(&str, &str); 25] = [
+    ("major", ""),
+    ("minor", "m"),
+    ("augmented", "aug"),
+    ("diminished", "dim"),
+    ("dominant", "7"),
+    ("major-seventh", "maj7"),
+    ("minor-seventh", "m7"),
+    ("diminished-seventh", "dim7"),
+    ("augmented-seventh", "aug7"),
+    ("half-diminished", "m7b5"),
+    ("major-minor", "m(maj7)"),
+    ("major-sixth", "6"),
+    ("minor-sixth", "m6"),
+    ("dominant-ninth", "9"),
+    ("major-ninth", "maj9"),
+    ("minor-ninth", "m9"),
+    ("dominant-11th", "11"),
+    ("major-11th", "maj11"),
+    ("minor-11th", "m11"),
+    ("dominant-13th", "13"),
+    ("major-13th", "maj13"),
+    ("minor-13th", "m13"),
+    ("suspended-second", "sus2"),
+    ("suspended-fourth", "sus4"),
+    ("power", "5"),
+];
+
+fn kind_suffix(kind: &str) -> Option<&'static str> {
+    CHORD_KINDS
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, suffix)| *suffix)
+}
+
+/// The MusicXML chord kind written as the given chord symbol suffix, such as "m7"
+pub(crate) fn suffix_kind(suffix: &str) -> Option<&'static str> {
+    CHORD_KINDS
+        .iter()
+        .find(|(_, s)| *s == suffix)
+        .map(|(kind, _)| *kind)
+}
@@ -2,7 +2,11 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
-use crate::form::{chord::Chord, interval::Interval};
+use crate::form::{
+    chord::{Chord, ChordTone},
+    interval::Interval,
+    pitch::Pitch,
+};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Note {
@@ -90,6 +94,16 @@ impl Note {
         WrittenNote { name, accidental }
     }
 
+    /// Writes this note using the given note name, e.g. E♭ as D♯ or F as E♯.
+    /// Returns None if that would take more than a double sharp or double flat.
+    pub fn spell_as(&self, name: NoteName) -> Option<WrittenNote> {
+        let natural = Note::new(name, Accidental::Natural);
+        // Distance from the natural note to this one, between -6 and 5
+        let difference = (self.index - natural.index + 6).rem_euclid(12) - 6;
+
+        Accidental::from_value(difference).map(|accidental| WrittenNote::new(name, accidental))
+    }
+
     pub fn add_steps(&self, steps: i32) -> Self {
         Self {
             index: (self.index + steps).rem_euclid(12),
@@ -101,87 +115,86 @@ impl Note {
     }
 
     pub fn min(&self) -> Chord {
-        let third = self.add_interval(Interval::MinorThird);
-        let fifth = self.add_interval(Interval::PerfectFifth);
-        let mut chord = Chord::new(vec![*self, third, fifth]);
-        chord.symbol = Some("m".into());
-        chord
+        Chord::new(*self)
+            .with_interval(ChordTone::Third, Interval::MinorThird)
+            .with_interval(ChordTone::Fifth, Interval::PerfectFifth)
     }
 
     pub fn maj(&self) -> Chord {
-        let third = self.add_interval(Interval::MajorThird);
-        let fifth = self.add_interval(Interval::PerfectFifth);
-        let mut chord = Chord::new(vec![*self, third, fifth]);
-        chord.symbol = Some("maj".into());
-        chord
+        Chord::new(*self)
+            .with_interval(ChordTone::Third, Interval::MajorThird)
+            .with_interval(ChordTone::Fifth, Interval::PerfectFifth)
     }
 
     pub fn maj7(&self) -> Chord {
-        let third = self.add_interval(Interval::MajorThird);
-        let fifth = self.add_interval(Interval::PerfectFifth);
-        let seventh = self.add_interval(Interval::MajorSeventh);
-        let mut chord = Chord::new(vec![*self, third, fifth, seventh]);
-        chord.symbol = Some("maj7".into());
-        chord
+        self.maj()
+            .with_interval(ChordTone::Seventh, Interval::MajorSeventh)
+    }
+
+    pub fn maj9(&self) -> Chord {
+        self.maj7()
+            .with_interval(ChordTone::Ninth, Interval::MajorSecond)
+    }
+
+    pub fn min6(&self) -> Chord {
+        self.min()
+            .with_interval(ChordTone::Sixth, Interval::MajorSixth)
     }
 
     pub fn min7(&self) -> Chord {
-        let third = self.add_interval(Interval::MinorThird);
-        let fifth = self.add_interval(Interval::PerfectFifth);
-        let seventh = self.add_interval(Interval::MinorSeventh);
-        let mut chord = Chord::new(vec![*self, third, fifth, seventh]);
-        chord.symbol = Some("min7".into());
-        chord
+        self.min()
+            .with_interval(ChordTone::Seventh, Interval::MinorSeventh)
+    }
+
+    pub fn min9(&self) -> Chord {
+        self.min7()
+            .with_interval(ChordTone::Ninth, Interval::MajorSecond)
     }
 
     pub fn dominant7(&self) -> Chord {
-        let third = self.add_interval(Interval::MajorThird);
-        let fifth = self.add_interval(Interval::PerfectFifth);
-        let seventh = self.add_interval(Interval::MinorSeventh);
-        let mut chord = Chord::new(vec![*self, third, fifth, seventh]);
-        chord.symbol = Some("7".into());
-        chord
+        self.maj()
+            .with_interval(ChordTone::Seventh, Interval::MinorSeventh)
+    }
+
+    pub fn dominant9(&self) -> Chord {
+        self.dominant7()
+            .with_interval(ChordTone::Ninth, Interval::MajorSecond)
+    }
+
+    pub fn dominant7_sharp5(&self) -> Chord {
+        self.dominant7()
+            .with_interval(ChordTone::Fifth, Interval::AugmentedFifth)
     }
 
     pub fn dim(&self) -> Chord {
-        let third = self.add_interval(Interval::MinorThird);
-        let fifth = self.add_interval(Interval::DiminishedFifth);
-        let mut chord = Chord::new(vec![*self, third, fifth]);
-        chord.symbol = Some("dim".into());
-        chord
+        Chord::new(*self)
+            .with_interval(ChordTone::Third, Interval::MinorThird)
+            .with_interval(ChordTone::Fifth, Interval::DiminishedFifth)
     }
 
     pub fn m7b5(&self) -> Chord {
-        let third = self.add_interval(Interval::MinorThird);
-        let fifth = self.add_interval(Interval::DiminishedFifth);
-        let seventh = self.add_interval(Interval::MinorSeventh);
-        let mut chord = Chord::new(vec![*self, third, fifth, seventh]);
-        chord.symbol = Some("m7b5".into());
-        chord
+        self.dim()
+            .with_interval(ChordTone::Seventh, Interval::MinorSeventh)
     }
 
     pub fn dim7(&self) -> Chord {
-        let third = self.add_interval(Interval::MinorThird);
-        let fifth = third.add_interval(Interval::MinorThird);
-        let seventh = fifth.add_interval(Interval::MinorThird);
-        let mut chord = Chord::new(vec![*self, third, fifth, seventh]);
-        chord.symbol = Some("dim7".into());
-        chord
+        self.dim()
+            .with_interval(ChordTone::Seventh, Interval::MajorSixth)
     }
 
     /// Returns the note closest to that frequency, and the error.
     /// Error = 0 means spot on the note, -1 is 50 cents flat, +1 si 50 cents sharp.
     pub fn from_frequency(frequency: f32) -> (Self, f32) {
-        if frequency <= 0. {
+        let Some((pitch, error)) = Pitch::from_frequency(frequency) else {
             todo!("Neatly handle the frequence 0Hz case");
-        }
-
-        let note_index_f = ((frequency / 55.0).log10() * 12.) / 2f32.log10();
+        };
 
-        let error = (note_index_f + 0.5).rem_euclid(1.0) - 0.5;
-        let note_index = note_index_f.round() as i32 % 12;
+        (pitch.note(), error)
+    }
 
-        (Self { index: note_index }, error)
+    /// This note in the given octave, e.g. `E.at_octave(1)` is the lowest string of a bass
+    pub fn at_octave(&self, octave: i32) -> Pitch {
+        Pitch::new(*self, octave)
     }
 }
 
@@ -208,6 +221,21 @@ impl NoteName {
             NoteName::G => NoteName::B,
         }
     }
+
+    /// The note name that is `letters` names above this one, e.g. C + 2 = E
+    pub fn add_letters(&self, letters: usize) -> NoteName {
+        const NAMES: [NoteName; 7] = [
+            NoteName::A,
+            NoteName::B,
+            NoteName::C,
+            NoteName::D,
+            NoteName::E,
+            NoteName::F,
+            NoteName::G,
+        ];
+        let index = NAMES.iter().position(|name| name == self).unwrap();
+        NAMES[(index + letters) % NAMES.len()]
+    }
 }
 
 impl std::fmt::Display for NoteName {
@@ -230,6 +258,8 @@ pub enum Accidental {
     Natural,
     Sharp,
     Flat,
+    DoubleSharp,
+    DoubleFlat,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -254,6 +284,19 @@ impl Accidental {
             Accidental::Natural => 0,
             Accidental::Sharp => 1,
             Accidental::Flat => -1,
+            Accidental::DoubleSharp => 2,
+            Accidental::DoubleFlat => -2,
+        }
+    }
+
+    pub const fn from_value(value: i32) -> Option<Accidental> {
+        match value {
+            0 => Some(Accidental::Natural),
+            1 => Some(Accidental::Sharp),
+            -1 => Some(Accidental::Flat),
+            2 => Some(Accidental::DoubleSharp),
+            -2 => Some(Accidental::DoubleFlat),
+            _ => None,
         }
     }
 }
@@ -264,6 +307,8 @@ impl std::fmt::Display for Accidental {
             Accidental::Natural => "",
             Accidental::Sharp => "♯",
             Accidental::Flat => "♭",
+            Accidental::DoubleSharp => "𝄪",
+            Accidental::DoubleFlat => "𝄫",
         };
         write!(f, "{}", s)
     }
@@ -278,6 +323,59 @@ impl WrittenNote {
     pub(crate) fn unwrite(&self) -> Note {
         Note::new(self.name, self.accidental)
     }
+
+    /// Spells the note `steps` semitones above this one using the note name `letters` names
+    /// higher, e.g. a minor third above C is E♭ and a diminished seventh above C is B𝄫.
+    /// Only if that would need more than a double accidental, the note name is chosen
+    /// to match this note's accidental instead.
+    pub fn above(&self, letters: usize, steps: i32) -> WrittenNote {
+        let note = self.unwrite().add_steps(steps);
+
+        note.spell_as(self.name.add_letters(letters))
+            .unwrap_or_else(|| match self.accidental.value() {
+                value if value < 0 => note.flat(),
+                _ => note.sharp(),
+            })
+    }
+
+    /// Reads a note name with an optional accidental ("E", "Bb", "F♯", "Bbb", "C𝄪") from the
+    /// start of `s`, returning the note and whatever follows it.
+    pub fn parse_prefix(s: &str) -> Option<(WrittenNote, &str)> {
+        let mut chars = s.chars();
+        let name = match chars.next()? {
+            'A' => NoteName::A,
+            'B' => NoteName::B,
+            'C' => NoteName::C,
+            'D' => NoteName::D,
+            'E' => NoteName::E,
+            'F' => NoteName::F,
+            'G' => NoteName::G,
+            _ => return None,
+        };
+
+        let rest = chars.as_str();
+        let (accidental, rest) = [
+            ("bb", Accidental::DoubleFlat),
+            ("♭♭", Accidental::DoubleFlat),
+            ("𝄫", Accidental::DoubleFlat),
+            ("##", Accidental::DoubleSharp),
+            ("♯♯", Accidental::DoubleSharp),
+            ("𝄪", Accidental::DoubleSharp),
+            ("b", Accidental::Flat),
+            ("♭", Accidental::Flat),
+            ("#", Accidental::Sharp),
+            ("♯", Accidental::Sharp),
+        ]
+        .into_iter()
+        .find_map(|(prefix, accidental)| Some((accidental, rest.strip_prefix(prefix)?)))
+        .unwrap_or((Accidental::Natural, rest));
+
+        Some((WrittenNote::new(name, accidental), rest))
+    }
+
+    pub const fn new(name: NoteName, accidental: Accidental) -> Self {
+        Self { name, accidental }
+    }
 }
 
 impl Display for WrittenNote {
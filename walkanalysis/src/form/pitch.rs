@@ -0,0 +1,116 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::form::note::{Accidental, Note, NoteName, WrittenNote};
+
+/// MIDI number of A4, which sounds at 440Hz
+const A4: i32 = 69;
+const A4_FREQUENCY: f32 = 440.;
+
+/// A note in a specific octave, stored as its MIDI number (E1 = 28, A2 = 45, C4 = 60).
+/// Where `Note` only knows a low E and a high E are both an E, `Pitch` knows which one was played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Pitch {
+    midi: i32,
+}
+
+impl Pitch {
+    pub const fn from_midi(midi: i32) -> Self {
+        Self { midi }
+    }
+
+    /// The note in the given octave, in scientific pitch notation: octaves start at C, C4 is middle C
+    pub fn new(note: Note, octave: i32) -> Self {
+        let steps_above_c =
+            (note.index() - Note::new(NoteName::C, Accidental::Natural).index()).rem_euclid(12);
+        Self {
+            midi: (octave + 1) * 12 + steps_above_c,
+        }
+    }
+
+    pub fn midi(&self) -> i32 {
+        self.midi
+    }
+
+    /// The pitch class, forgetting the octave
+    pub fn note(&self) -> Note {
+        Note::from(self.midi - A4)
+    }
+
+    pub fn octave(&self) -> i32 {
+        self.midi.div_euclid(12) - 1
+    }
+
+    pub fn add_steps(&self, steps: i32) -> Self {
+        Self {
+            midi: self.midi + steps,
+        }
+    }
+
+    /// Semitones from this pitch up to the other, negative if the other pitch is lower
+    pub fn steps_to(&self, other: Pitch) -> i32 {
+        other.midi - self.midi
+    }
+
+    pub fn frequency(&self) -> f32 {
+        A4_FREQUENCY * 2f32.powf((self.midi - A4) as f32 / 12.)
+    }
+
+    /// Returns the pitch closest to that frequency, and the error, or None if the frequency is not positive.
+    /// Error = 0 means spot on the pitch, -0.5 is 50 cents flat, +0.5 is 50 cents sharp.
+    pub fn from_frequency(frequency: f32) -> Option<(Self, f32)> {
+        if frequency <= 0. {
+            return None;
+        }
+
+        let midi_f = A4 as f32 + 12. * (frequency / A4_FREQUENCY).log2();
+
+        let error = (midi_f + 0.5).rem_euclid(1.0) - 0.5;
+        let midi = midi_f.round() as i32;
+
+        Some((Self { midi }, error))
+    }
+}
+
+impl Display for Pitch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.note().flat(), self.octave())
+    }
+}
+
+/// Why a pitch such as "E1" could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PitchParseError {
+    InvalidNote(String),
+    InvalidOctave(String),
+}
+
+impl Display for PitchParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PitchParseError::InvalidNote(token) => write!(f, "Invalid note `{}`", token),
+            PitchParseError::InvalidOctave(token) => write!(f, "Invalid octave `{}`", token),
+        }
+    }
+}
+
+impl Error for PitchParseError {}
+
+impl FromStr for Pitch {
+    type Err = PitchParseError;
+
+    /// Reads scientific pitch notation such as "E1", "Bb2" or "F♯3".
+    /// The octave belongs to the note name, so "Cb4" is the B just below "C4".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (written, octave) =
+            WrittenNote::parse_prefix(s).ok_or_else(|| PitchParseError::InvalidNote(s.into()))?;
+        let octave: i32 = octave
+            .parse()
+            .map_err(|_| PitchParseError::InvalidOctave(octave.into()))?;
+
+        let natural = Pitch::new(Note::new(written.name, Accidental::Natural), octave);
+        Ok(natural.add_steps(written.accidental.value()))
+    }
+}
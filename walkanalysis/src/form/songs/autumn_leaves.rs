@@ -9,7 +9,8 @@ pub fn autumn_leaves() -> Form {
         110,
         Key::new(G, Quality::Minor).flat(),
         vec![
-            // A section
+            FormPiece::Section("A".into()),
+            FormPiece::RepeatStart,
             bar(C.min7()),
             bar(F.dominant7()),
             bar(B_FLAT.maj7()),
@@ -19,19 +20,9 @@ pub fn autumn_leaves() -> Form {
             bar(D.dominant7()),
             bar(G.min()),
             bar(G.min()),
+            FormPiece::RepeatEnd(2),
             FormPiece::LineBreak,
-            // repeat of A section
-            bar(C.min7()),
-            bar(F.dominant7()),
-            bar(B_FLAT.maj7()),
-            bar(E_FLAT.maj7()),
-            FormPiece::LineBreak,
-            bar(A.m7b5()),
-            bar(D.dominant7()),
-            bar(G.min()),
-            bar(G.min()),
-            FormPiece::LineBreak,
-            // B section
+            FormPiece::Section("B".into()),
             bar(A.m7b5()),
             bar(D.dominant7()),
             bar(G.min()),
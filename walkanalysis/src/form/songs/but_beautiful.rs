@@ -1,5 +1,5 @@
 use crate::form::{
-    form::{bar, half_bar, Form, FormPiece},
+    form::{bar, half_bar, split_bar, Form, FormPiece},
     key::{Key, Quality},
     note::*,
 };
@@ -9,42 +9,42 @@ pub fn but_beautiful() -> Form {
         70,
         Key::new(G, Quality::Major).sharp(),
         vec![
-            bar(G.maj7()), // TODO: Gmaj9
+            bar(G.maj9()),
             bar(G_SHARP.dim7()),
-            bar(A.min7()), // TODO: Amin9
+            bar(A.min9()),
             bar(B_FLAT.dim7()),
             FormPiece::LineBreak,
             bar(G.maj()),
-            half_bar(D.min(), E.dominant7()), // TODO: Dmin6
-            bar(A.dominant7()),               // TODO: A9
-            bar(A.dominant7()),               // TODO: A9
+            half_bar(D.min6(), E.dominant7()),
+            bar(A.dominant9()),
+            bar(A.dominant9()),
             FormPiece::LineBreak,
             bar(D.dominant7()),
             half_bar(G.maj(), E.min7()),
-            half_bar(A.min7(), D.dominant7()), // TODO: rhythmic variation: 𝅗𝅥. ♩
+            split_bar(vec![(A.min7(), 3), (D.dominant7(), 1)]),
             bar(G.maj()),
             FormPiece::LineBreak,
-            bar(E.min7()),      // TODO: Em6 Em7
-            bar(A.dominant7()), // TODO: A9
+            half_bar(E.min6(), E.min7()),
+            bar(A.dominant9()),
             bar(A.min7()),
-            half_bar(A.min7(), D.dominant7()), // TODO: rhythmic variation: 𝅗𝅥. ♩
+            split_bar(vec![(A.min7(), 3), (D.dominant7(), 1)]),
             FormPiece::LineBreak,
-            bar(G.maj7()), // TODO: Gmaj9
+            bar(G.maj9()),
             bar(G_SHARP.dim7()),
-            bar(A.min7()), // TODO: Amin9
+            bar(A.min9()),
             bar(B_FLAT.dim7()),
             FormPiece::LineBreak,
             bar(G.maj()),
-            half_bar(D.min(), E.dominant7()), // TODO: Dmin6
-            bar(A.dominant7()),               // TODO: A9
-            bar(A.dominant7()),               // TODO: A9
+            half_bar(D.min6(), E.dominant7()),
+            bar(A.dominant9()),
+            bar(A.dominant9()),
             FormPiece::LineBreak,
             bar(D.dominant7()),
             half_bar(G.maj(), E.min7()),
-            half_bar(A.min7(), B.dominant7()), // TODO: B7#5
-            half_bar(E.min(), F.dominant7()),  // TODO: rhythmic variation: 𝅗𝅥. ♩
+            half_bar(A.min7(), B.dominant7_sharp5()),
+            split_bar(vec![(E.min(), 3), (F.dominant7(), 1)]),
             FormPiece::LineBreak,
-            half_bar(G.maj(), B_FLAT.dominant7()), // TODO: rhythmic variation: 𝅗𝅥. ♩
+            split_bar(vec![(G.maj(), 3), (B_FLAT.dominant7(), 1)]),
             half_bar(A.min7(), A_FLAT.dominant7()),
             bar(G.maj()),
             bar(D.dominant7()),
@@ -0,0 +1,130 @@
+use crate::form::form::{Form, FormPiece};
+
+impl Form {
+    /// The form as it is played: repeats, endings, jumps and choruses written out,
+    /// with all structural markers removed.
+    pub fn unrolled(&self) -> Form {
+        let music = self
+            .unrolled_indices()
+            .into_iter()
+            .map(|index| self.music()[index].clone())
+            .collect();
+
+        Form::new(self.tempo(), self.key(), music).with_meter(self.meter())
+    }
+
+    /// For every piece of the unrolled form, the index in `music()` of the piece it was
+    /// played from. Bars that are repeated show up multiple times.
+    pub fn unrolled_indices(&self) -> Vec<usize> {
+        let music = self.music();
+        let mut indices = vec![];
+
+        // The count off is only played before the first chorus
+        let start = match music.first() {
+            Some(FormPiece::CountOff) => {
+                indices.push(0);
+                1
+            }
+            _ => 0,
+        };
+
+        for _ in 0..self.choruses() {
+            unroll_chorus(music, start, &mut indices);
+        }
+
+        indices
+    }
+}
+
+/// Walks through the form once, following repeats and jumps
+fn unroll_chorus(music: &[FormPiece], start: usize, indices: &mut Vec<usize>) {
+    let find = |piece: FormPiece| music.iter().position(|p| *p == piece);
+
+    // Where a repeat sign sends us back to
+    let mut repeat_start = start;
+    // Which time we are playing the current repeated part, starting at 1
+    let mut pass = 1;
+    // Whether a D.C. or D.S. has been taken, after which repeats are ignored
+    let mut jumped = false;
+
+    let mut i = start;
+    while i < music.len() {
+        match &music[i] {
+            FormPiece::RepeatStart => {
+                repeat_start = i + 1;
+                pass = 1;
+            }
+            FormPiece::RepeatEnd(times) => {
+                if !jumped && pass < *times {
+                    pass += 1;
+                    i = repeat_start;
+                    continue;
+                }
+                repeat_start = i + 1;
+                pass = 1;
+            }
+            FormPiece::Ending(number) => {
+                let (end, closes_repeat) = ending_end(music, i);
+                // After a jump only the last ending is played
+                let skip = if jumped {
+                    closes_repeat
+                } else {
+                    *number != pass
+                };
+                if skip {
+                    // Skipping the repeat sign on the last pass leaves the repeat
+                    let last_pass = match music[end - 1] {
+                        FormPiece::RepeatEnd(times) => pass >= times,
+                        _ => false,
+                    };
+                    if closes_repeat && (jumped || last_pass) {
+                        repeat_start = end;
+                    }
+                    i = end;
+                    continue;
+                }
+                // The last ending is played after the repeat, a repeat that comes
+                // later starts over from its first pass
+                if !closes_repeat {
+                    repeat_start = i + 1;
+                    pass = 1;
+                }
+            }
+            FormPiece::ToCoda if jumped => {
+                if let Some(coda) = find(FormPiece::Coda) {
+                    i = coda + 1;
+                    continue;
+                }
+            }
+            FormPiece::Fine if jumped => return,
+            FormPiece::DaCapo if !jumped => {
+                jumped = true;
+                i = start;
+                continue;
+            }
+            FormPiece::DalSegno if !jumped => {
+                if let Some(segno) = find(FormPiece::Segno) {
+                    jumped = true;
+                    i = segno + 1;
+                    continue;
+                }
+            }
+            piece if !piece.is_marker() => indices.push(i),
+            _ => (),
+        }
+        i += 1;
+    }
+}
+
+/// The index just after the bars of the ending starting at `ending`,
+/// and whether the ending closes with a repeat sign
+fn ending_end(music: &[FormPiece], ending: usize) -> (usize, bool) {
+    for (i, piece) in music.iter().enumerate().skip(ending + 1) {
+        match piece {
+            FormPiece::Ending(_) => return (i, false),
+            FormPiece::RepeatEnd(_) => return (i + 1, true),
+            _ => (),
+        }
+    }
+    (music.len(), false)
+}
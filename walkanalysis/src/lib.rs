@@ -1,7 +1,9 @@
 pub mod analysis;
 pub mod exercise;
+pub mod export;
 pub mod form;
+pub mod midi;
+pub mod synth;
 pub mod transcribe;
 
-// TODO: midi module to generate midi outputs of the chords (and maybe also of an expected bassline?)
 // TODO: set up tests with standalone which pre-loads data of a playthrough of autumn leaves
@@ -0,0 +1,200 @@
+use crate::{
+    exercise::Exercise,
+    form::{
+        chord::Chord,
+        form::{Form, FormPiece},
+        key::{Quality, WrittenKey},
+        meter::Meter,
+        musicxml::fifths,
+        note::G,
+        pitch::Pitch,
+    },
+    transcribe::transcribe::{Ornament, PlayedNote},
+};
+
+use smf::{Smf, Track, KEY_SIGNATURE, SET_TEMPO, TIME_SIGNATURE};
+
+pub mod smf;
+
+pub const TICKS_PER_QUARTER: u16 = 480;
+
+const COMPING_CHANNEL: u8 = 0;
+const BASS_CHANNEL: u8 = 1;
+const DRUM_CHANNEL: u8 = 9;
+
+const ACOUSTIC_GRAND_PIANO: u8 = 0;
+const FINGERED_BASS: u8 = 33;
+const HI_WOOD_BLOCK: u8 = 76;
+const LOW_WOOD_BLOCK: u8 = 77;
+
+/// Renders the form as it is played to a MIDI file with a track of held chords, count off
+/// clicks and, if given, a bass line with a note per beat such as a `Transcription` has.
+/// Tracks are: tempo and meter, comping, count off and bass.
+pub fn to_midi(form: &Form, bass_line: Option<&[PlayedNote]>) -> Smf {
+    render(form, bass_line.map(|line| (line, &[][..])))
+}
+
+/// Renders the form like `to_midi`, with the line the exercise generates for it as the
+/// bass track: a reference that scores 100%, including the notes between the beats.
+pub fn exercise_to_midi(form: &Form, exercise: &dyn Exercise) -> Smf {
+    let line = exercise.generate(form);
+    let ornaments = exercise.generate_ornaments(form);
+    render(form, Some((&line, &ornaments)))
+}
+
+/// Renders the form with the notes on the beats and the ornaments between them as the bass
+fn render(form: &Form, bass_line: Option<(&[PlayedNote], &[Ornament])>) -> Smf {
+    let mut conductor = Track::new("Walkanalysis");
+    let mut comping = Track::new("Comping");
+    let mut count_off = Track::new("Count off");
+    let mut bass = Track::new("Bass");
+    comping.program_change(0, COMPING_CHANNEL, ACOUSTIC_GRAND_PIANO);
+    bass.program_change(0, BASS_CHANNEL, FINGERED_BASS);
+
+    conductor.meta(0, KEY_SIGNATURE, &key_signature(form.key()));
+
+    let mut tick = 0;
+    let mut previous_meter = None;
+    // The tick every beat starts on, with its length in ticks
+    let mut beats: Vec<(u32, u32)> = vec![];
+    // The chord that is sounding, with the tick it started on
+    let mut sounding: Option<(Chord, u32)> = None;
+
+    for (form_piece, meter) in form.unrolled().pieces_with_meter() {
+        if let FormPiece::Key(new_key) = form_piece {
+            let written = new_key.spell(new_key.flat().spell_preference());
+            conductor.meta(tick, KEY_SIGNATURE, &key_signature(written));
+        }
+
+        let ticks_per_beat = ticks_per_beat(meter);
+        for beat_in_bar in 0..form_piece.length_in_beats(meter) {
+            let beat_in_bar = beat_in_bar % meter.beats_per_bar();
+            if previous_meter != Some(meter) {
+                conductor.meta(tick, TIME_SIGNATURE, &time_signature(meter));
+                conductor.meta(tick, SET_TEMPO, &tempo(form.tempo(), meter));
+                previous_meter = Some(meter);
+            }
+
+            // Chords are played again at the start of every bar
+            let chord = form_piece.chord_at(beat_in_bar, meter);
+            let same_chord = beat_in_bar != 0 && sounding.as_ref().map(|(c, _)| c) == chord;
+            if !same_chord {
+                if let Some((chord, start)) = sounding.take() {
+                    comp(&mut comping, &chord, start, tick - start);
+                }
+                sounding = chord.map(|chord| (chord.clone(), tick));
+            }
+
+            if *form_piece == FormPiece::CountOff {
+                let (key, velocity) = if beat_in_bar == 0 {
+                    (HI_WOOD_BLOCK, 110)
+                } else {
+                    (LOW_WOOD_BLOCK, 90)
+                };
+                count_off.note(tick, ticks_per_beat / 2, DRUM_CHANNEL, key, velocity);
+            }
+
+            beats.push((tick, ticks_per_beat));
+            tick += ticks_per_beat;
+        }
+    }
+    if let Some((chord, start)) = sounding {
+        comp(&mut comping, &chord, start, tick - start);
+    }
+
+    if let Some((line, ornaments)) = bass_line {
+        // Every note with the tick it starts on and the length of the beat it is in
+        let on_beats = beats.iter().zip(line).map(|(&beat, &note)| (beat, note));
+        let between_beats = ornaments.iter().filter_map(|ornament| {
+            let (start, ticks_per_beat) = *beats.get(ornament.beat as usize)?;
+            let offset = ornament.subdivision.fraction() * ticks_per_beat as f32;
+            Some((
+                (start + offset.round() as u32, ticks_per_beat),
+                ornament.note,
+            ))
+        });
+        let mut notes: Vec<_> = on_beats
+            .chain(between_beats)
+            .filter_map(|(beat, note)| match note {
+                PlayedNote::Surely(pitch) => Some((beat, pitch)),
+                _ => None,
+            })
+            .collect();
+        notes.sort_by_key(|&((start, _), _)| start);
+
+        for (i, &((start, ticks_per_beat), pitch)) in notes.iter().enumerate() {
+            // Held for a beat at most, and slightly detached so a repeated note can be heard
+            let end = notes
+                .get(i + 1)
+                .map_or(start + ticks_per_beat, |&((next, _), _)| next)
+                .min(start + ticks_per_beat);
+            let length = (end - start).saturating_sub(ticks_per_beat / 8).max(1);
+            bass.note(start, length, BASS_CHANNEL, pitch.midi() as u8, 100);
+        }
+    }
+
+    let mut tracks = vec![conductor, comping, count_off];
+    if bass_line.is_some() {
+        tracks.push(bass);
+    }
+    Smf {
+        ticks_per_quarter: TICKS_PER_QUARTER,
+        tracks,
+    }
+}
+
+fn ticks_per_beat(meter: Meter) -> u32 {
+    TICKS_PER_QUARTER as u32 * 4 / meter.beat_unit()
+}
+
+fn comp(track: &mut Track, chord: &Chord, start: u32, length: u32) {
+    for pitch in voicing(chord) {
+        track.note(start, length, COMPING_CHANNEL, pitch.midi() as u8, 70);
+    }
+}
+
+/// The chord tones between G3 and F♯4. The root is left to the bass, unless the chord
+/// is a triad.
+pub fn voicing(chord: &Chord) -> Vec<Pitch> {
+    let lowest = G.at_octave(3);
+    let notes = chord.notes();
+    let mut pitches: Vec<Pitch> = notes
+        .iter()
+        .filter(|&&note| notes.len() <= 3 || note != chord.root())
+        .map(|&note| {
+            let pitch = note.at_octave(lowest.octave());
+            if pitch < lowest {
+                pitch.add_steps(12)
+            } else {
+                pitch
+            }
+        })
+        .collect();
+    pitches.sort();
+    pitches
+}
+
+/// Microseconds per quarter note, for a tempo in beats per minute
+fn tempo(beats_per_minute: u32, meter: Meter) -> [u8; 3] {
+    let microseconds = 60_000_000 * meter.beat_unit() / (4 * beats_per_minute.max(1));
+    let [_, bytes @ ..] = microseconds.to_be_bytes();
+    bytes
+}
+
+fn time_signature(meter: Meter) -> [u8; 4] {
+    // The beat unit as a power of two, then 24 MIDI clocks per metronome click
+    // and 8 32nd notes per quarter note
+    [
+        meter.beats_per_bar() as u8,
+        meter.beat_unit().trailing_zeros() as u8,
+        24,
+        8,
+    ]
+}
+
+fn key_signature(key: WrittenKey) -> [u8; 2] {
+    [
+        fifths(key) as i8 as u8,
+        (key.quality() == Quality::Minor) as u8,
+    ]
+}
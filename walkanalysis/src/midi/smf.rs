@@ -0,0 +1,330 @@
+use std::{error::Error, fmt::Display, fs::File, io::Write, path::Path};
+
+/// Meta event types used in the files we write
+pub const TRACK_NAME: u8 = 0x03;
+pub const END_OF_TRACK: u8 = 0x2F;
+pub const SET_TEMPO: u8 = 0x51;
+pub const TIME_SIGNATURE: u8 = 0x58;
+pub const KEY_SIGNATURE: u8 = 0x59;
+
+pub const NOTE_OFF: u8 = 0x80;
+pub const NOTE_ON: u8 = 0x90;
+const PROGRAM_CHANGE: u8 = 0xC0;
+const CHANNEL_PRESSURE: u8 = 0xD0;
+const SYSEX: u8 = 0xF0;
+const SYSEX_CONTINUATION: u8 = 0xF7;
+const META: u8 = 0xFF;
+
+/// A Standard MIDI File, written in format 1: every track is played at the same time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Smf {
+    pub ticks_per_quarter: u16,
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Track {
+    pub events: Vec<Event>,
+}
+
+/// A MIDI or meta message at a time in ticks from the start of the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub tick: u32,
+    /// The complete message including the status byte, meta events start with `0xFF`
+    pub message: Vec<u8>,
+}
+
+impl Track {
+    pub fn new(name: &str) -> Self {
+        let mut track = Track::default();
+        track.meta(0, TRACK_NAME, name.as_bytes());
+        track
+    }
+
+    pub fn meta(&mut self, tick: u32, kind: u8, data: &[u8]) {
+        let mut message = vec![META, kind];
+        message.extend(variable_length(data.len() as u32));
+        message.extend(data);
+        self.events.push(Event { tick, message });
+    }
+
+    pub fn program_change(&mut self, tick: u32, channel: u8, program: u8) {
+        self.events.push(Event {
+            tick,
+            message: vec![PROGRAM_CHANGE | channel, program],
+        });
+    }
+
+    /// A note on and the matching note off `length` ticks later
+    pub fn note(&mut self, tick: u32, length: u32, channel: u8, key: u8, velocity: u8) {
+        self.events.push(Event {
+            tick,
+            message: vec![NOTE_ON | channel, key, velocity],
+        });
+        self.events.push(Event {
+            tick: tick + length,
+            message: vec![NOTE_OFF | channel, key, 0],
+        });
+    }
+
+    /// Events in the order they are played. Note offs go before anything else on the
+    /// same tick, so a note that is played again right away is not cut short.
+    fn sorted_events(&self) -> Vec<&Event> {
+        let mut events: Vec<&Event> = self.events.iter().collect();
+        events.sort_by_key(|event| (event.tick, event.message[0] & 0xF0 != NOTE_OFF));
+        events
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut data = vec![];
+        let mut tick = 0;
+        for event in self.sorted_events() {
+            data.extend(variable_length(event.tick - tick));
+            data.extend(&event.message);
+            tick = event.tick;
+        }
+        data.extend([0, META, END_OF_TRACK, 0]);
+
+        let mut bytes = b"MTrk".to_vec();
+        bytes.extend((data.len() as u32).to_be_bytes());
+        bytes.extend(data);
+        bytes
+    }
+}
+
+impl Track {
+    fn parse(bytes: &[u8], number: usize) -> Result<Self, SmfError> {
+        let mut reader = Reader { bytes, position: 0 };
+        let mut events = vec![];
+        let mut tick = 0;
+        let mut running_status = None;
+
+        while reader.position < bytes.len() {
+            tick += reader.variable_length()?;
+
+            let mut status = reader.peek()?;
+            if status & 0x80 == 0 {
+                status = running_status.ok_or(SmfError::InvalidEvent {
+                    track: number,
+                    status,
+                })?;
+            } else {
+                reader.position += 1;
+            }
+
+            let mut message = vec![status];
+            match status {
+                META => {
+                    let kind = reader.take(1)?[0];
+                    let length = reader.variable_length()?;
+                    // Written again when the track is saved
+                    if kind == END_OF_TRACK {
+                        break;
+                    }
+                    message.push(kind);
+                    message.extend(variable_length(length));
+                    message.extend(reader.take(length as usize)?);
+                }
+                SYSEX | SYSEX_CONTINUATION => {
+                    let length = reader.variable_length()?;
+                    message.extend(variable_length(length));
+                    message.extend(reader.take(length as usize)?);
+                    running_status = None;
+                }
+                0x80..=0xEF => {
+                    let data_bytes = match status & 0xF0 {
+                        PROGRAM_CHANGE | CHANNEL_PRESSURE => 1,
+                        _ => 2,
+                    };
+                    message.extend(reader.take(data_bytes)?);
+                    running_status = Some(status);
+                }
+                _ => {
+                    return Err(SmfError::InvalidEvent {
+                        track: number,
+                        status,
+                    })
+                }
+            }
+
+            events.push(Event { tick, message });
+        }
+
+        Ok(Track { events })
+    }
+}
+
+impl Event {
+    /// The channel and key of a note on, a note on with velocity 0 is a note off
+    pub fn note_on(&self) -> Option<(u8, u8)> {
+        match self.message[..] {
+            [status, key, velocity] if status & 0xF0 == NOTE_ON && velocity > 0 => {
+                Some((status & 0x0F, key))
+            }
+            _ => None,
+        }
+    }
+
+    /// The channel and key of a note off
+    pub fn note_off(&self) -> Option<(u8, u8)> {
+        match self.message[..] {
+            [status, key, _] if status & 0xF0 == NOTE_OFF => Some((status & 0x0F, key)),
+            [status, key, 0] if status & 0xF0 == NOTE_ON => Some((status & 0x0F, key)),
+            _ => None,
+        }
+    }
+
+    /// Microseconds per quarter note, if this is a tempo change
+    pub fn tempo(&self) -> Option<u32> {
+        match self.message[..] {
+            [META, SET_TEMPO, 3, a, b, c] => Some(u32::from_be_bytes([0, a, b, c])),
+            _ => None,
+        }
+    }
+}
+
+impl Smf {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend(6u32.to_be_bytes());
+        bytes.extend(1u16.to_be_bytes());
+        bytes.extend((self.tracks.len() as u16).to_be_bytes());
+        bytes.extend(self.ticks_per_quarter.to_be_bytes());
+
+        for track in &self.tracks {
+            bytes.extend(track.to_bytes());
+        }
+        bytes
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::parse(&std::fs::read(path)?)?)
+    }
+
+    /// Reads a MIDI file of any format. Running status is written out, so every event
+    /// has its own status byte.
+    pub fn parse(bytes: &[u8]) -> Result<Self, SmfError> {
+        let mut reader = Reader { bytes, position: 0 };
+
+        if reader.take(4)? != b"MThd" {
+            return Err(SmfError::NotAMidiFile);
+        }
+        let header_length = reader.u32()? as usize;
+        let header = reader.take(header_length)?;
+        let &[_, _, _, _, division_high, division_low, ..] = header else {
+            return Err(SmfError::UnexpectedEnd);
+        };
+        let division = u16::from_be_bytes([division_high, division_low]);
+        if division & 0x8000 != 0 {
+            return Err(SmfError::UnsupportedTiming);
+        }
+
+        let mut tracks = vec![];
+        while reader.position < bytes.len() {
+            let kind = reader.take(4)?;
+            let length = reader.u32()? as usize;
+            let data = reader.take(length)?;
+            // Chunks that are not tracks must be skipped
+            if kind == b"MTrk" {
+                tracks.push(Track::parse(data, tracks.len())?);
+            }
+        }
+
+        Ok(Smf {
+            ticks_per_quarter: division,
+            tracks,
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        File::create(path)?.write_all(&self.to_bytes())
+    }
+}
+
+/// A number in the variable length format of MIDI files: seven bits per byte,
+/// most significant first, with the high bit set on all but the last byte
+fn variable_length(mut value: u32) -> Vec<u8> {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.insert(0, (value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes
+}
+
+/// Why a MIDI file could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmfError {
+    NotAMidiFile,
+    UnexpectedEnd,
+    /// Timing in SMPTE frames instead of ticks per quarter note
+    UnsupportedTiming,
+    InvalidEvent {
+        track: usize,
+        status: u8,
+    },
+}
+
+impl Display for SmfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmfError::NotAMidiFile => write!(f, "Not a MIDI file"),
+            SmfError::UnexpectedEnd => write!(f, "The MIDI file ends unexpectedly"),
+            SmfError::UnsupportedTiming => {
+                write!(f, "MIDI files timed in SMPTE frames are not supported")
+            }
+            SmfError::InvalidEvent { track, status } => {
+                write!(
+                    f,
+                    "Invalid event with status {:#04X} in track {}",
+                    status, track
+                )
+            }
+        }
+    }
+}
+
+impl Error for SmfError {}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Result<u8, SmfError> {
+        self.bytes
+            .get(self.position)
+            .copied()
+            .ok_or(SmfError::UnexpectedEnd)
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], SmfError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + length)
+            .ok_or(SmfError::UnexpectedEnd)?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, SmfError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn variable_length(&mut self) -> Result<u32, SmfError> {
+        let mut value = 0;
+        // At most four bytes
+        for _ in 0..4 {
+            let byte = self.take(1)?[0];
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SmfError::UnexpectedEnd)
+    }
+}
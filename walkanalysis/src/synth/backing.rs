@@ -0,0 +1,95 @@
+use crate::{
+    exercise::form_beats,
+    form::form::Form,
+    midi::voicing,
+    synth::{bass::BassSynth, drums, mix, samples_per_beat},
+};
+
+/// Renders a band to play along with: a ride cymbal with the hi-hat on the backbeat,
+/// clicks during the count off and the chords held on a piano
+#[derive(Debug, Clone)]
+pub struct BackingTrack {
+    pub sample_rate: u32,
+    /// Where the eighth note after the beat falls, as part of the beat:
+    /// 0.5 is straight, 2/3 is triplet swing
+    pub swing: f32,
+    pub drums_volume: f32,
+    pub piano_volume: f32,
+    pub click_volume: f32,
+}
+
+impl Default for BackingTrack {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44_100,
+            swing: 2. / 3.,
+            drums_volume: 0.3,
+            piano_volume: 0.25,
+            click_volume: 0.4,
+        }
+    }
+}
+
+impl BackingTrack {
+    /// The whole form as it is played, including the count off, at the given tempo
+    pub fn render(&self, form: &Form, tempo: f32) -> Vec<f32> {
+        let beat_length = samples_per_beat(tempo, self.sample_rate);
+        let beats = form_beats(form);
+        let mut samples = vec![0.; beats.len() * beat_length];
+
+        let ride = drums::ride(self.sample_rate, 1);
+        let hi_hat = drums::hi_hat(self.sample_rate, 2);
+        let high_click = drums::click(self.sample_rate, true);
+        let low_click = drums::click(self.sample_rate, false);
+        // A brighter string that rings longer passes for a piano
+        let piano = BassSynth {
+            sample_rate: self.sample_rate,
+            harmonics: vec![1.0, 0.4, 0.25, 0.15, 0.1, 0.05],
+            decay: 1.5,
+            ..Default::default()
+        };
+
+        for (beat, form_beat) in beats.iter().enumerate() {
+            let start = beat * beat_length;
+            let position = form_beat.position;
+
+            let Some(chord) = &form_beat.chord else {
+                let click = if position.is_downbeat() {
+                    &high_click
+                } else {
+                    &low_click
+                };
+                mix(&mut samples, start, click, self.click_volume);
+                continue;
+            };
+
+            // Ding, ding-a, ding, ding-a: the skip note leads into every other beat
+            mix(&mut samples, start, &ride, self.drums_volume);
+            if position.beat_in_bar % 2 == 1 {
+                let skip = start + (self.swing * beat_length as f32) as usize;
+                mix(&mut samples, skip, &ride, 0.7 * self.drums_volume);
+                mix(&mut samples, start, &hi_hat, self.drums_volume);
+            }
+
+            if form_beat.beat_in_chord == 0 {
+                let length = beats[beat + 1..]
+                    .iter()
+                    .take_while(|next| next.beat_in_chord != 0)
+                    .count()
+                    + 1;
+                let end = (start + length * beat_length).min(samples.len());
+
+                let mut chord_samples = vec![0.; end - start];
+                for pitch in voicing(chord) {
+                    piano.pluck(pitch, &mut chord_samples);
+                }
+                mix(&mut samples, start, &chord_samples, self.piano_volume);
+            }
+        }
+
+        for sample in samples.iter_mut() {
+            *sample = sample.clamp(-1., 1.);
+        }
+        samples
+    }
+}
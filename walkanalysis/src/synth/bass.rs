@@ -0,0 +1,93 @@
+use std::f32::consts::TAU;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{form::pitch::Pitch, transcribe::transcribe::PlayedNote};
+
+use super::samples_per_beat;
+
+/// Seconds over which a note fades in and out, so notes don't click
+const ATTACK: f32 = 0.005;
+const RELEASE: f32 = 0.02;
+
+/// An additive model of a plucked bass string: a sum of harmonics that each decay
+/// exponentially, higher harmonics faster than lower ones
+#[derive(Debug, Clone)]
+pub struct BassSynth {
+    pub sample_rate: u32,
+    /// Amplitude of every harmonic relative to the fundamental, starting with the fundamental
+    pub harmonics: Vec<f32>,
+    /// Seconds for the fundamental to decay to a third of its amplitude
+    pub decay: f32,
+    /// Amplitude of the white noise added to everything
+    pub noise: f32,
+    /// How far every note is out of tune, in cents
+    pub detune: f32,
+    /// Seed for the noise, so renders are the same every time
+    pub seed: u64,
+}
+
+impl Default for BassSynth {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44_100,
+            harmonics: vec![1.0, 0.8, 0.5, 0.35, 0.2, 0.12, 0.08, 0.05],
+            decay: 0.8,
+            noise: 0.,
+            detune: 0.,
+            seed: 0,
+        }
+    }
+}
+
+impl BassSynth {
+    /// Renders a note at the start of every beat, like a transcription has them.
+    /// Every note is plucked again, also if the previous beat had the same note.
+    pub fn render(&self, notes: &[PlayedNote], tempo: f32) -> Vec<f32> {
+        let beat_length = samples_per_beat(tempo, self.sample_rate);
+        let mut samples = vec![0.; notes.len() * beat_length];
+
+        for (beat, note) in notes.iter().enumerate() {
+            if let PlayedNote::Surely(pitch) = note {
+                let start = beat * beat_length;
+                self.pluck(*pitch, &mut samples[start..start + beat_length]);
+            }
+        }
+
+        if self.noise > 0. {
+            let mut rng = StdRng::seed_from_u64(self.seed);
+            for sample in samples.iter_mut() {
+                *sample += rng.random_range(-self.noise..self.noise);
+            }
+        }
+
+        samples
+    }
+
+    /// Adds a note that lasts as long as the given samples
+    pub fn pluck(&self, pitch: Pitch, samples: &mut [f32]) {
+        let frequency = pitch.frequency() * 2f32.powf(self.detune / 1200.);
+        let nyquist = self.sample_rate as f32 / 2.;
+        let total: f32 = self.harmonics.iter().sum();
+        let length = samples.len() as f32 / self.sample_rate as f32;
+
+        for (i, sample) in samples.iter_mut().enumerate() {
+            let time = i as f32 / self.sample_rate as f32;
+            let envelope = (time / ATTACK).min(1.) * ((length - time) / RELEASE).min(1.);
+
+            let mut value = 0.;
+            for (harmonic, amplitude) in (1..).zip(&self.harmonics) {
+                let harmonic_frequency = frequency * harmonic as f32;
+                if harmonic_frequency >= nyquist {
+                    break;
+                }
+                let decay = self.decay / (harmonic as f32).sqrt();
+                value +=
+                    amplitude * (-time / decay).exp() * (TAU * harmonic_frequency * time).sin();
+            }
+
+            // Leave room for noise and a bit of headroom
+            *sample += 0.5 * envelope * value / total;
+        }
+    }
+}
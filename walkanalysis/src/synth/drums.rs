@@ -0,0 +1,62 @@
+use std::f32::consts::TAU;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Inharmonic partials of a ride cymbal in Hz, as (frequency, amplitude)
+const RIDE_PARTIALS: [(f32, f32); 6] = [
+    (3_310., 1.0),
+    (4_140., 0.8),
+    (5_070., 0.7),
+    (6_280., 0.5),
+    (7_530., 0.4),
+    (9_210., 0.3),
+];
+
+/// The ping of a stick on a ride cymbal, ringing out for a while
+pub fn ride(sample_rate: u32, seed: u64) -> Vec<f32> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let total: f32 = RIDE_PARTIALS.iter().map(|(_, amplitude)| amplitude).sum();
+    decaying(sample_rate, 0.6, 0.35, |time| {
+        let ping: f32 = RIDE_PARTIALS
+            .iter()
+            .map(|(frequency, amplitude)| amplitude * (TAU * frequency * time).sin())
+            .sum();
+        0.7 * ping / total + 0.3 * rng.random_range(-1.0..1.0)
+    })
+}
+
+/// A hi-hat closed with the foot: a short burst of high noise
+pub fn hi_hat(sample_rate: u32, seed: u64) -> Vec<f32> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut previous = 0.;
+    decaying(sample_rate, 0.08, 0.02, |_| {
+        // The difference between neighbouring samples of noise leaves only the high frequencies
+        let noise = rng.random_range(-1.0..1.0);
+        let high = noise - previous;
+        previous = noise;
+        0.5 * high
+    })
+}
+
+/// A metronome click, higher on the first beat of a bar
+pub fn click(sample_rate: u32, downbeat: bool) -> Vec<f32> {
+    let frequency = if downbeat { 1_500. } else { 1_000. };
+    decaying(sample_rate, 0.05, 0.01, |time| {
+        (TAU * frequency * time).sin()
+    })
+}
+
+/// A sound of `length` seconds that decays exponentially with time constant `decay`
+fn decaying(
+    sample_rate: u32,
+    length: f32,
+    decay: f32,
+    mut sound: impl FnMut(f32) -> f32,
+) -> Vec<f32> {
+    (0..(length * sample_rate as f32) as usize)
+        .map(|i| {
+            let time = i as f32 / sample_rate as f32;
+            (-time / decay).exp() * sound(time)
+        })
+        .collect()
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+
+pub mod backing;
+pub mod bass;
+pub mod drums;
+
+/// Writes mono samples between -1 and 1 as a 32 bit WAV file, which
+/// `Transcription::transcribe_from_wav` reads back as the same samples
+pub fn save_wav<P: AsRef<Path>>(
+    path: P,
+    samples: &[f32],
+    sample_rate: u32,
+) -> Result<(), hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for sample in samples {
+        writer.write_sample((sample.clamp(-1., 1.) * i32::MAX as f32) as i32)?;
+    }
+    writer.finalize()
+}
+
+/// Number of samples in a beat at the tempo
+pub fn samples_per_beat(tempo: f32, sample_rate: u32) -> usize {
+    (sample_rate as f64 / (tempo as f64 / 60.)).round() as usize
+}
+
+/// Adds the sound to the samples from the given sample on, as far as it fits
+pub fn mix(samples: &mut [f32], start: usize, sound: &[f32], gain: f32) {
+    for (sample, value) in samples.iter_mut().skip(start).zip(sound) {
+        *sample += gain * value;
+    }
+}
@@ -0,0 +1,236 @@
+use std::{collections::BTreeMap, error::Error, path::Path};
+
+use crate::{
+    form::pitch::Pitch,
+    midi::smf::Smf,
+    transcribe::transcribe::{Ornament, PlayedNote, Subdivision, Transcription},
+};
+
+/// MIDI files without a tempo are played at 120 beats per minute
+const DEFAULT_MICROSECONDS_PER_QUARTER: u32 = 500_000;
+const DRUM_CHANNEL: u8 = 9;
+/// Notes that start less than this part of a beat apart are played together
+const TOGETHER: f32 = 1. / 12.;
+
+/// A note from a MIDI file that was taken as the note played on a beat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiNote {
+    pub beat: u32,
+    pub pitch: Pitch,
+    /// Beats between the beat and the start of the note: negative if the note was early
+    pub offset: f32,
+    pub velocity: u8,
+}
+
+/// A note starting or stopping, at a time in beats from the start of the take
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MidiEvent {
+    NoteOn {
+        position: f32,
+        pitch: Pitch,
+        velocity: u8,
+    },
+    NoteOff {
+        position: f32,
+        pitch: Pitch,
+    },
+}
+
+impl Transcription {
+    pub fn transcribe_from_midi(
+        path: &Path,
+        tempo: f32,
+        channel: Option<u8>,
+    ) -> Result<(Self, Vec<MidiNote>), Box<dyn Error>> {
+        Ok(Transcription::transcribe_midi(
+            &Smf::open(path)?,
+            tempo,
+            channel,
+        ))
+    }
+
+    /// Reads the notes in the file at the given tempo, using the tempo changes in the file
+    /// to know when they are played. Only notes on the given channel are read, or notes
+    /// on any channel but drums.
+    pub fn transcribe_midi(smf: &Smf, tempo: f32, channel: Option<u8>) -> (Self, Vec<MidiNote>) {
+        let tempo_map = TempoMap::new(smf);
+        let seconds_per_beat = 60. / tempo as f64;
+        let in_beats = |tick| (tempo_map.seconds(tick) / seconds_per_beat) as f32;
+        let wanted = |note_channel| match channel {
+            Some(channel) => note_channel == channel,
+            None => note_channel != DRUM_CHANNEL,
+        };
+
+        let mut events: Vec<_> = smf.tracks.iter().flat_map(|track| &track.events).collect();
+        events.sort_by_key(|event| event.tick);
+
+        let events = events.into_iter().filter_map(|event| {
+            if let Some((note_channel, key)) = event.note_on() {
+                wanted(note_channel).then(|| MidiEvent::NoteOn {
+                    position: in_beats(event.tick),
+                    pitch: Pitch::from_midi(key as i32),
+                    velocity: event.message[2],
+                })
+            } else if let Some((note_channel, key)) = event.note_off() {
+                wanted(note_channel).then(|| MidiEvent::NoteOff {
+                    position: in_beats(event.tick),
+                    pitch: Pitch::from_midi(key as i32),
+                })
+            } else {
+                None
+            }
+        });
+
+        Transcription::transcribe_midi_events(events)
+    }
+
+    /// Quantizes every note that starts to the nearest beat. If notes start on the same
+    /// beat, the one closest to the beat is played on it, or the lowest one if they are
+    /// played together. Beats where no note starts get the note that is held over them,
+    /// as a transcription of the audio hears it, or are silent. Notes without a note off
+    /// are not held. The other notes are notes between the beats, if they are near a subdivision.
+    pub fn transcribe_midi_events(
+        events: impl IntoIterator<Item = MidiEvent>,
+    ) -> (Self, Vec<MidiNote>) {
+        let mut last_beat: f32 = 0.;
+        let mut notes: BTreeMap<u32, MidiNote> = BTreeMap::new();
+        let mut all_notes = vec![];
+        // Notes that are still sounding, with where they start
+        let mut sounding: Vec<(f32, Pitch)> = vec![];
+        // Where every released note starts and stops
+        let mut held: Vec<(f32, f32, Pitch)> = vec![];
+
+        for event in events {
+            let (position, pitch, velocity) = match event {
+                MidiEvent::NoteOff { position, pitch } => {
+                    last_beat = last_beat.max(position);
+                    if let Some(index) = sounding.iter().position(|&(_, p)| p == pitch) {
+                        let (start, _) = sounding.remove(index);
+                        held.push((start, position, pitch));
+                    }
+                    continue;
+                }
+                MidiEvent::NoteOn {
+                    position,
+                    pitch,
+                    velocity,
+                } => (position, pitch, velocity),
+            };
+            last_beat = last_beat.max(position);
+            sounding.push((position, pitch));
+
+            let beat = position.round().max(0.) as u32;
+            let note = MidiNote {
+                beat,
+                pitch,
+                offset: position - beat as f32,
+                velocity,
+            };
+
+            all_notes.push(note);
+
+            let wins = notes.get(&beat).is_none_or(|other| {
+                if (note.offset - other.offset).abs() < TOGETHER {
+                    note.pitch < other.pitch
+                } else {
+                    note.offset.abs() < other.offset.abs()
+                }
+            });
+            if wins {
+                notes.insert(beat, note);
+            }
+        }
+
+        // The lowest note at a subdivision, in the order they were played
+        let mut between_beats: BTreeMap<(u32, Subdivision), Pitch> = BTreeMap::new();
+        for note in all_notes {
+            if notes.get(&note.beat) == Some(&note) {
+                continue;
+            }
+            let position = note.beat as f32 + note.offset;
+            let beat = position.floor().max(0.);
+            let Some(subdivision) = Subdivision::nearest(position - beat) else {
+                continue;
+            };
+
+            let lowest = between_beats
+                .entry((beat as u32, subdivision))
+                .or_insert(note.pitch);
+            *lowest = (*lowest).min(note.pitch);
+        }
+        let ornaments = between_beats
+            .into_iter()
+            .map(|((beat, subdivision), pitch)| Ornament {
+                beat,
+                subdivision,
+                note: PlayedNote::Surely(pitch),
+            })
+            .collect();
+
+        let length = match notes.last_key_value() {
+            Some((&beat, _)) => u32::max(beat + 1, last_beat.ceil() as u32),
+            None => 0,
+        };
+        // The last note to start before the beat that still sounds a bit after it
+        let held_over = |beat: u32| {
+            let beat = beat as f32;
+            held.iter()
+                .filter(|&&(start, end, _)| start < beat && end > beat + TOGETHER)
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|&(_, _, pitch)| pitch)
+        };
+        let played = (0..length)
+            .map(|beat| match notes.get(&beat) {
+                Some(note) => PlayedNote::Surely(note.pitch),
+                None => held_over(beat).map_or(PlayedNote::Silence, PlayedNote::Surely),
+            })
+            .collect();
+
+        (
+            Transcription::new(played).with_ornaments(ornaments),
+            notes.into_values().collect(),
+        )
+    }
+}
+
+/// When every tempo change in the file happens
+struct TempoMap {
+    ticks_per_quarter: f64,
+    /// Tick of every tempo change, the time in seconds at that tick and the new tempo
+    changes: Vec<(u32, f64, u32)>,
+}
+
+impl TempoMap {
+    fn new(smf: &Smf) -> Self {
+        let mut tempos: Vec<(u32, u32)> = smf
+            .tracks
+            .iter()
+            .flat_map(|track| &track.events)
+            .filter_map(|event| Some((event.tick, event.tempo()?)))
+            .collect();
+        tempos.sort_by_key(|(tick, _)| *tick);
+
+        let mut map = TempoMap {
+            ticks_per_quarter: smf.ticks_per_quarter.max(1) as f64,
+            changes: vec![(0, 0., DEFAULT_MICROSECONDS_PER_QUARTER)],
+        };
+        for (tick, tempo) in tempos {
+            let seconds = map.seconds(tick);
+            map.changes.push((tick, seconds, tempo));
+        }
+        map
+    }
+
+    fn seconds(&self, tick: u32) -> f64 {
+        let (start, seconds, tempo) = self
+            .changes
+            .iter()
+            .rev()
+            .find(|(start, _, _)| *start <= tick)
+            .copied()
+            .unwrap_or((0, 0., DEFAULT_MICROSECONDS_PER_QUARTER));
+
+        let quarters = (tick - start) as f64 / self.ticks_per_quarter;
+        seconds + quarters * tempo as f64 / 1_000_000.
+    }
+}
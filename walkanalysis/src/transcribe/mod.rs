@@ -1 +1,4 @@
+pub mod midi;
+pub mod onset;
+pub mod pitch;
 pub mod transcribe;
@@ -0,0 +1,199 @@
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::{Deserialize, Serialize};
+
+use crate::transcribe::transcribe::Subdivision;
+
+/// Samples in every frame of the spectrogram, and samples between frames
+const FRAME_LENGTH: usize = 4096;
+const HOP_LENGTH: usize = 512;
+/// Only changes in the bass register and its first harmonics count as notes
+const HIGHEST_FREQUENCY: f32 = 2000.;
+/// Seconds around an onset in which the novelty must be highest, so a note starts once
+const PEAK_DISTANCE: f32 = 0.05;
+/// Seconds around an onset over which the novelty is averaged, a note must stand out from it
+const AVERAGE_DISTANCE: f32 = 0.2;
+/// Onsets further from a beat than this part of a beat are not played on a beat,
+/// such as ghost notes and skips
+pub const MAX_OFFSET: f32 = 0.35;
+
+/// Where a note starts in the samples
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Onset {
+    pub sample: usize,
+    /// The spectral flux at the onset, louder and sharper attacks are stronger
+    pub strength: f32,
+}
+
+/// An onset that was taken as the note played on a beat
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BeatOnset {
+    pub beat: u32,
+    pub onset: Onset,
+    /// Beats between the beat and the onset: negative if the note was early
+    pub offset: f32,
+}
+
+/// An onset that was not taken as the note played on a beat, but as a note between beats
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SubdivisionOnset {
+    /// The beat the onset comes after
+    pub beat: u32,
+    pub subdivision: Subdivision,
+    pub onset: Onset,
+}
+
+/// How much the spectrum grows from frame to frame, summed over the frequencies that
+/// grow. Frames are centered on every `HOP_LENGTH`th sample.
+pub fn spectral_flux(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME_LENGTH);
+    let hann: Vec<f32> = (0..FRAME_LENGTH)
+        .map(|i| {
+            let phase = std::f32::consts::TAU * i as f32 / FRAME_LENGTH as f32;
+            0.5 - 0.5 * phase.cos()
+        })
+        .collect();
+    let bins = ((HIGHEST_FREQUENCY * FRAME_LENGTH as f32 / sample_rate as f32) as usize)
+        .clamp(1, FRAME_LENGTH / 2);
+
+    let mut previous = vec![0.; bins];
+    let mut flux = Vec::with_capacity(samples.len().div_ceil(HOP_LENGTH));
+    for frame in 0..samples.len().div_ceil(HOP_LENGTH) {
+        // Before the first and after the last sample is silence
+        let first = (frame * HOP_LENGTH) as isize - (FRAME_LENGTH / 2) as isize;
+        let mut buffer: Vec<Complex<f32>> = hann
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| {
+                let sample = usize::try_from(first + i as isize)
+                    .ok()
+                    .and_then(|i| samples.get(i))
+                    .copied()
+                    .unwrap_or(0.);
+                Complex::new(sample * weight, 0.)
+            })
+            .collect();
+        fft.process(&mut buffer);
+
+        // Leave out DC
+        let magnitudes: Vec<f32> = buffer[1..=bins].iter().map(|bin| bin.norm()).collect();
+        flux.push(
+            magnitudes
+                .iter()
+                .zip(&previous)
+                .map(|(magnitude, previous)| (magnitude - previous).max(0.))
+                .sum(),
+        );
+        previous = magnitudes;
+    }
+    flux
+}
+
+/// Finds where notes start: peaks in the spectral flux that are the highest around them,
+/// and stand out from the average around them by `threshold` times the highest peak
+pub fn detect_onsets(samples: &[f32], sample_rate: u32, threshold: f32) -> Vec<Onset> {
+    let flux = spectral_flux(samples, sample_rate);
+    let frames = |seconds: f32| (seconds * sample_rate as f32 / HOP_LENGTH as f32) as usize;
+    let (peak_distance, average_distance) = (frames(PEAK_DISTANCE), frames(AVERAGE_DISTANCE));
+    let highest = flux.iter().copied().fold(0., f32::max);
+
+    let around = |frame: usize, distance: usize| {
+        frame.saturating_sub(distance)..(frame + distance + 1).min(flux.len())
+    };
+
+    let mut onsets = vec![];
+    for (frame, &strength) in flux.iter().enumerate() {
+        // On a plateau, the first frame is the peak
+        let range = around(frame, peak_distance);
+        let is_peak = flux[range.clone()]
+            .iter()
+            .zip(range)
+            .all(|(&other, other_frame)| match other_frame.cmp(&frame) {
+                std::cmp::Ordering::Less => other < strength,
+                _ => other <= strength,
+            });
+        if !is_peak {
+            continue;
+        }
+
+        let average_range = around(frame, average_distance);
+        let average = flux[average_range.clone()].iter().sum::<f32>() / average_range.len() as f32;
+        if strength > average + threshold * highest {
+            // The flux grows most in the frame centered on the start of the note,
+            // a parabola through the neighbouring frames finds it between frames
+            let neighbour = |frame: Option<usize>| {
+                frame
+                    .and_then(|frame| flux.get(frame))
+                    .copied()
+                    .unwrap_or(0.)
+            };
+            let (before, after) = (neighbour(frame.checked_sub(1)), neighbour(Some(frame + 1)));
+            let curvature = before - 2. * strength + after;
+            let between = if curvature < 0. {
+                0.5 * (before - after) / curvature
+            } else {
+                0.
+            };
+            onsets.push(Onset {
+                sample: ((frame as f32 + between) * HOP_LENGTH as f32).max(0.) as usize,
+                strength,
+            });
+        }
+    }
+    onsets
+}
+
+/// Takes the onset nearest to every beat as the note played on it. Other notes that start
+/// around a beat, such as a skip into it, are played between the beats.
+pub fn on_beats(onsets: &[Onset], samples_per_beat: usize, beats: usize) -> Vec<Option<BeatOnset>> {
+    let mut on_beats: Vec<Option<BeatOnset>> = vec![None; beats];
+
+    for &onset in onsets {
+        let position = onset.sample as f32 / samples_per_beat as f32;
+        let beat = position.round() as usize;
+        let offset = position - beat as f32;
+        if beat >= beats || offset.abs() > MAX_OFFSET {
+            continue;
+        }
+
+        let on_beat = &mut on_beats[beat];
+        if on_beat.is_none_or(|other| offset.abs() < other.offset.abs()) {
+            *on_beat = Some(BeatOnset {
+                beat: beat as u32,
+                onset,
+                offset,
+            });
+        }
+    }
+
+    on_beats
+}
+
+/// The onsets that were not taken as the note played on a beat, at the subdivision of
+/// the beat they are nearest to. Onsets that are near no subdivision are left out.
+pub fn between_beats(
+    onsets: &[Onset],
+    on_beats: &[Option<BeatOnset>],
+    samples_per_beat: usize,
+) -> Vec<SubdivisionOnset> {
+    onsets
+        .iter()
+        .filter_map(|&onset| {
+            let position = onset.sample as f32 / samples_per_beat as f32;
+            let on_a_beat = on_beats
+                .get(position.round() as usize)
+                .copied()
+                .flatten()
+                .is_some_and(|on_beat| on_beat.onset == onset);
+            let beat = position.floor();
+            if on_a_beat || beat as usize >= on_beats.len() {
+                return None;
+            }
+
+            Subdivision::nearest(position - beat).map(|subdivision| SubdivisionOnset {
+                beat: beat as u32,
+                subdivision,
+                onset,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,467 @@
+use std::f32::consts::TAU;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+const POSSIBLE_ROOT_RELATIVE_HEIGHT_TO_DOMINANT: f32 = 0.13;
+
+/// Below the low B string of a five string bass, and above the highest note on the G string
+const LOWEST_FREQUENCY: f32 = 28.;
+const HIGHEST_FREQUENCY: f32 = 500.;
+
+/// At most this many frequencies are given as candidates
+pub const MAX_CANDIDATES: usize = 3;
+
+/// Works out the frequency of the note that is sounding in a piece of a recording
+pub trait PitchDetector {
+    /// The fundamental frequency in Hz, or None if no note could be heard
+    fn detect(&self, samples: &[f32], sample_rate: u32) -> Option<f32>;
+
+    /// Frequencies the note might have had with how likely each of them is, the
+    /// detected frequency first. The likelihoods add up to 1.
+    fn candidates(&self, samples: &[f32], sample_rate: u32) -> Vec<(f32, f32)> {
+        self.detect(samples, sample_rate)
+            .map(|frequency| vec![(frequency, 1.)])
+            .unwrap_or_default()
+    }
+}
+
+/// Takes the lowest peak in the spectrum that is at least some part of the highest peak.
+/// Needs a few periods of the note in the samples to tell the bins of low notes apart,
+/// and hears the octave if the fundamental is weak.
+pub struct FftPeaks;
+
+/// YIN by de Cheveigné and Kawahara: the first period for which the samples differ
+/// little from themselves shifted by that period
+pub struct Yin {
+    /// How much the samples may differ from their shifted selves, as part of the
+    /// average difference over shorter periods
+    pub threshold: f32,
+}
+
+/// McLeod's pitch method: the first peak of the normalized autocorrelation that is nearly
+/// as high as the highest peak
+pub struct Mcleod {
+    /// How high the peak must be, as part of the highest peak
+    pub cutoff: f32,
+    /// Below this correlation the samples are not periodic enough to be a note
+    pub clarity: f32,
+}
+
+/// Scores every possible fundamental by how loud its overtones are together: the product
+/// of the spectrum at the fundamental and its first harmonics. An octave or fifth above
+/// misses the fundamental and the harmonics in between, so it scores lower even if
+/// the fundamental is weak.
+pub struct HarmonicProductSpectrum {
+    /// How many harmonics are multiplied, including the fundamental
+    pub harmonics: usize,
+    /// How loud the harmonics must be on average, as part of the loudest peak,
+    /// to be a note and not noise
+    pub clarity: f32,
+}
+
+impl HarmonicProductSpectrum {
+    pub const DEFAULT: HarmonicProductSpectrum = HarmonicProductSpectrum {
+        harmonics: 4,
+        clarity: 0.2,
+    };
+}
+
+impl Yin {
+    pub const DEFAULT: Yin = Yin { threshold: 0.15 };
+}
+
+impl Mcleod {
+    pub const DEFAULT: Mcleod = Mcleod {
+        cutoff: 0.9,
+        clarity: 0.5,
+    };
+}
+
+/// The spectrum of the samples, without the DC part
+pub fn spectrum(samples: &[f32]) -> Vec<Complex<f32>> {
+    let mut buffer: Vec<Complex<f32>> = samples.iter().map(|&x| Complex::new(x, 0.0)).collect();
+    FftPlanner::<f32>::new()
+        .plan_fft_forward(buffer.len())
+        .process(&mut buffer);
+
+    if let Some(dc_component) = buffer.get_mut(0) {
+        *dc_component = Complex::new(0., 0.);
+    }
+    buffer
+}
+
+impl PitchDetector for FftPeaks {
+    fn detect(&self, samples: &[f32], sample_rate: u32) -> Option<f32> {
+        let buffer = spectrum(samples);
+
+        let half = buffer.len() / 2;
+        let max_mag = buffer
+            .iter()
+            .take(half)
+            .map(|b| b.norm_sqr())
+            .fold(0., f32::max);
+
+        // Dominant frequency is usually not the root, take every peakt that's over some percentage of the height of dominant,
+        // the lowest frequency there is the root
+        let possible_roots = buffer
+            .iter()
+            .take(half)
+            .enumerate()
+            .filter(|(_i, bin)| {
+                bin.norm_sqr() > POSSIBLE_ROOT_RELATIVE_HEIGHT_TO_DOMINANT * max_mag
+            })
+            .collect::<Vec<_>>();
+
+        let bin_as_freq = |bin| (bin as f32) * sample_rate as f32 / buffer.len() as f32;
+
+        possible_roots.first().and_then(|(bin, _power)| {
+            if *bin == 0 {
+                println!("DC showed up as possible root, ignoring.");
+                return None; // DC, useless, and filtered out earlier
+            }
+
+            let center_bin = *bin;
+            let left_bin = center_bin - 1;
+            let right_bin = center_bin + 1;
+
+            let center_power = buffer.get(center_bin)?.norm_sqr();
+            let left_power = buffer.get(left_bin)?.norm_sqr();
+            let right_power = buffer.get(right_bin)?.norm_sqr();
+
+            let (other_power, other_bin, sign) = if left_power > right_power {
+                (left_power, left_bin, -1.)
+            } else {
+                (right_power, right_bin, 1.)
+            };
+
+            let center_freq = bin_as_freq(center_bin);
+            let other_freq = bin_as_freq(other_bin);
+
+            let freq_diff = (center_freq - other_freq).abs();
+
+            let freq_offset = (1.0 - (center_power / (center_power + other_power))) * freq_diff;
+
+            Some(center_freq + freq_offset * sign)
+        })
+    }
+}
+
+impl PitchDetector for HarmonicProductSpectrum {
+    fn detect(&self, samples: &[f32], sample_rate: u32) -> Option<f32> {
+        let candidates = self.candidates(samples, sample_rate);
+        candidates.first().map(|&(frequency, _)| frequency)
+    }
+
+    fn candidates(&self, samples: &[f32], sample_rate: u32) -> Vec<(f32, f32)> {
+        // Padded with silence, so the harmonics of low notes fall close to a bin
+        let length = (4 * samples.len()).next_power_of_two();
+        let hann = |i: usize| 0.5 - 0.5 * (TAU * i as f32 / samples.len() as f32).cos();
+        let mut buffer: Vec<Complex<f32>> = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| Complex::new(x * hann(i), 0.))
+            .chain(std::iter::repeat(Complex::new(0., 0.)))
+            .take(length)
+            .collect();
+        FftPlanner::<f32>::new()
+            .plan_fft_forward(length)
+            .process(&mut buffer);
+        let magnitudes: Vec<f32> = buffer[..length / 2].iter().map(|bin| bin.norm()).collect();
+
+        let loudest = magnitudes.iter().copied().fold(0., f32::max);
+        if loudest <= 0. {
+            return vec![];
+        }
+        // Anything this soft is as good as missing, and keeps the logarithm finite
+        let floor = loudest * 1e-3;
+
+        let bin = |frequency: f32| (frequency * length as f32 / sample_rate as f32) as usize;
+        let lowest = bin(LOWEST_FREQUENCY).max(1);
+        let highest = bin(HIGHEST_FREQUENCY).min((magnitudes.len() - 1) / self.harmonics);
+        if highest <= lowest {
+            return vec![];
+        }
+
+        // Summing logarithms multiplies the magnitudes without running out of range
+        let scores: Vec<f32> = (0..=highest)
+            .map(|fundamental| {
+                if fundamental < lowest {
+                    return f32::MIN;
+                }
+                (1..=self.harmonics)
+                    .map(|harmonic| magnitudes[fundamental * harmonic].max(floor).ln())
+                    .sum()
+            })
+            .collect();
+        let average = |fundamental: usize| (scores[fundamental] / self.harmonics as f32).exp();
+        let frequency =
+            |fundamental| interpolate(&scores, fundamental) * sample_rate as f32 / length as f32;
+
+        let Some(best) = (lowest..=highest).max_by(|&a, &b| scores[a].total_cmp(&scores[b])) else {
+            return vec![];
+        };
+        if average(best) < self.clarity * loudest {
+            return vec![];
+        }
+
+        let others = (lowest..=highest)
+            .filter(|&fundamental| {
+                fundamental != best
+                    && scores[fundamental] >= scores[fundamental - 1]
+                    && scores
+                        .get(fundamental + 1)
+                        .is_none_or(|&next| scores[fundamental] >= next)
+                    && average(fundamental) >= self.clarity * loudest
+            })
+            .map(|fundamental| (frequency(fundamental), average(fundamental)))
+            .collect();
+        rank((frequency(best), average(best)), others)
+    }
+}
+
+impl PitchDetector for Yin {
+    fn detect(&self, samples: &[f32], sample_rate: u32) -> Option<f32> {
+        let candidates = self.candidates(samples, sample_rate);
+        candidates.first().map(|&(frequency, _)| frequency)
+    }
+
+    fn candidates(&self, samples: &[f32], sample_rate: u32) -> Vec<(f32, f32)> {
+        let Some((shortest, correlation)) = Correlation::new(samples, sample_rate) else {
+            return vec![];
+        };
+
+        // The difference of the samples with themselves shifted by every period, divided
+        // by the average difference over all shorter periods
+        let mut normalized = vec![1.; correlation.lags()];
+        let mut sum = 0.;
+        for (period, normalized) in normalized.iter_mut().enumerate().skip(1) {
+            let difference = correlation.energy(period) - 2. * correlation.lag(period);
+            sum += difference;
+            *normalized = if sum > 0. {
+                difference * period as f32 / sum
+            } else {
+                1.
+            };
+        }
+
+        let Some(mut period) =
+            (shortest..normalized.len()).find(|&p| normalized[p] < self.threshold)
+        else {
+            return vec![];
+        };
+        while normalized
+            .get(period + 1)
+            .is_some_and(|&next| next < normalized[period])
+        {
+            period += 1;
+        }
+
+        // Other dips are periods the samples nearly repeat at as well
+        let candidate = |period| {
+            (
+                sample_rate as f32 / interpolate(&normalized, period),
+                1. - normalized[period],
+            )
+        };
+        let others = (shortest.max(1)..normalized.len() - 1)
+            .filter(|&other| {
+                other != period
+                    && normalized[other] < 0.5
+                    && normalized[other] <= normalized[other - 1]
+                    && normalized[other] <= normalized[other + 1]
+            })
+            .map(candidate)
+            .collect();
+        rank(candidate(period), others)
+    }
+}
+
+impl PitchDetector for Mcleod {
+    fn detect(&self, samples: &[f32], sample_rate: u32) -> Option<f32> {
+        let candidates = self.candidates(samples, sample_rate);
+        candidates.first().map(|&(frequency, _)| frequency)
+    }
+
+    fn candidates(&self, samples: &[f32], sample_rate: u32) -> Vec<(f32, f32)> {
+        let Some((shortest, correlation)) = Correlation::new(samples, sample_rate) else {
+            return vec![];
+        };
+
+        let nsdf: Vec<f32> = (0..correlation.lags())
+            .map(|period| {
+                let energy = correlation.energy(period);
+                if energy > 0. {
+                    2. * correlation.lag(period) / energy
+                } else {
+                    0.
+                }
+            })
+            .collect();
+
+        // The highest point between every time the function goes up through zero
+        // and back down again
+        let mut peaks = vec![];
+        let mut peak: Option<usize> = None;
+        for period in 1..nsdf.len() {
+            if nsdf[period - 1] <= 0. && nsdf[period] > 0. {
+                peak = Some(period);
+            } else if nsdf[period - 1] > 0. && nsdf[period] <= 0. {
+                peaks.extend(peak.take());
+            }
+            if let Some(highest) = peak.as_mut() {
+                if nsdf[period] > nsdf[*highest] {
+                    *highest = period;
+                }
+            }
+        }
+        peaks.extend(peak);
+        peaks.retain(|&period| period >= shortest);
+
+        let highest = peaks.iter().map(|&period| nsdf[period]).fold(0., f32::max);
+        if highest < self.clarity {
+            return vec![];
+        }
+        let Some(&period) = peaks
+            .iter()
+            .find(|&&period| nsdf[period] >= self.cutoff * highest)
+        else {
+            return vec![];
+        };
+
+        let candidate = |period| {
+            (
+                sample_rate as f32 / interpolate(&nsdf, period),
+                nsdf[period],
+            )
+        };
+        let others = peaks
+            .into_iter()
+            .filter(|&other| other != period && nsdf[other] >= self.clarity)
+            .map(candidate)
+            .collect();
+        rank(candidate(period), others)
+    }
+}
+
+/// The detected frequency and the strongest other frequencies, leaving out those within
+/// a semitone of a stronger one. Their strengths are scaled to add up to 1.
+fn rank(detected: (f32, f32), mut others: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    others.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut ranked = vec![detected];
+    for other in others {
+        if ranked.len() == MAX_CANDIDATES {
+            break;
+        }
+        let semitones = |frequency: f32| 12. * (other.0 / frequency).log2().abs();
+        if ranked
+            .iter()
+            .all(|&(frequency, _)| semitones(frequency) >= 1.)
+        {
+            ranked.push(other);
+        }
+    }
+
+    let total: f32 = ranked.iter().map(|&(_, strength)| strength).sum();
+    if total > 0. {
+        for (_, strength) in ranked.iter_mut() {
+            *strength /= total;
+        }
+    }
+    ranked
+}
+
+/// The vertex of the parabola through a point and its neighbours
+fn interpolate(values: &[f32], at: usize) -> f32 {
+    let (Some(&before), Some(&after)) = (
+        at.checked_sub(1).and_then(|i| values.get(i)),
+        values.get(at + 1),
+    ) else {
+        return at as f32;
+    };
+    let curvature = before - 2. * values[at] + after;
+    if curvature.abs() < f32::EPSILON {
+        return at as f32;
+    }
+    at as f32 + 0.5 * (before - after) / curvature
+}
+
+/// The samples multiplied with themselves shifted by every period a note can have,
+/// over a window that is the same for every period
+struct Correlation {
+    lags: Vec<f32>,
+    /// Sum of squares of all samples before every sample
+    squares: Vec<f32>,
+    window: usize,
+}
+
+impl Correlation {
+    /// Also gives the shortest period a note can have
+    fn new(samples: &[f32], sample_rate: u32) -> Option<(usize, Self)> {
+        let shortest = (sample_rate as f32 / HIGHEST_FREQUENCY) as usize;
+        let longest = ((sample_rate as f32 / LOWEST_FREQUENCY) as usize).min(samples.len() / 2);
+        if longest <= shortest + 1 {
+            return None;
+        }
+        let window = samples.len() - longest;
+
+        // Correlating through the spectrum is much faster than shifting the samples
+        let length = (samples.len() + window).next_power_of_two();
+        let mut planner = FftPlanner::<f32>::new();
+        let mut windowed: Vec<Complex<f32>> = samples[..window]
+            .iter()
+            .map(|&x| Complex::new(x, 0.))
+            .chain(std::iter::repeat(Complex::new(0., 0.)))
+            .take(length)
+            .collect();
+        let mut all: Vec<Complex<f32>> = samples
+            .iter()
+            .map(|&x| Complex::new(x, 0.))
+            .chain(std::iter::repeat(Complex::new(0., 0.)))
+            .take(length)
+            .collect();
+        let forward = planner.plan_fft_forward(length);
+        forward.process(&mut windowed);
+        forward.process(&mut all);
+
+        let mut product: Vec<Complex<f32>> = windowed
+            .iter()
+            .zip(&all)
+            .map(|(windowed, all)| windowed.conj() * all)
+            .collect();
+        planner.plan_fft_inverse(length).process(&mut product);
+        let lags = product[..longest]
+            .iter()
+            .map(|lag| lag.re / length as f32)
+            .collect();
+
+        let mut squares = Vec::with_capacity(samples.len() + 1);
+        squares.push(0.);
+        for sample in samples {
+            squares.push(squares.last().unwrap() + sample * sample);
+        }
+
+        Some((
+            shortest,
+            Correlation {
+                lags,
+                squares,
+                window,
+            },
+        ))
+    }
+
+    fn lags(&self) -> usize {
+        self.lags.len()
+    }
+
+    fn lag(&self, period: usize) -> f32 {
+        self.lags[period]
+    }
+
+    /// The energy of the window and of the window shifted by the period
+    fn energy(&self, period: usize) -> f32 {
+        let shifted = self.squares[period + self.window] - self.squares[period];
+        self.squares[self.window] + shifted
+    }
+}
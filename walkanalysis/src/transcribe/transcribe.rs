@@ -1,31 +1,136 @@
 use std::{
     error::Error,
+    fmt::Display,
     fs::File,
     io::Write,
     path::{Path, PathBuf},
 };
 
-use rustfft::{num_complex::Complex, FftPlanner};
 use serde::{Deserialize, Serialize};
 
-use crate::form::note::Note;
-
-const POSSIBLE_ROOT_RELATIVE_HEIGHT_TO_DOMINANT: f32 = 0.13;
+use crate::{
+    form::{note::Note, pitch::Pitch},
+    transcribe::{
+        onset::{self, BeatOnset, SubdivisionOnset},
+        pitch::{self, HarmonicProductSpectrum, PitchDetector},
+    },
+};
 
-// Look at these portions of the beat to determine the note
-const START_OFFSET: f64 = 0.05;
+// Look at these portions of a beat after the note started to determine the note,
+// the attack of the note is left out
+const START_OFFSET: f64 = 0.10;
 const END_OFFSET: f64 = 0.60;
+/// Notes further than this many semitones from a pitch are not taken as that pitch
+const MAX_ERROR: f32 = 0.25;
 
 /// Given a wav file and a tempo, works out the notes that were played,
 /// leaving holes where it doesn't know
 #[derive(Debug, Clone)]
 pub struct Transcription {
     pub notes: Vec<PlayedNote>,
+    /// How sure the transcription is of every beat. Beats without confidence, such as
+    /// those read from a MIDI file, are sure.
+    pub confidence: Vec<Confidence>,
+    /// Notes played between the beats, in the order they were played
+    pub ornaments: Vec<Ornament>,
+}
+
+/// Where a note between two beats starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Subdivision {
+    /// A third of the way into the beat
+    FirstTriplet,
+    /// Half way into the beat, a straight eighth note
+    Eighth,
+    /// Two thirds of the way into the beat. A swung eighth note falls here, such as
+    /// the skip leading into the next beat.
+    SecondTriplet,
+}
+
+impl Subdivision {
+    pub const ALL: [Subdivision; 3] = [
+        Subdivision::FirstTriplet,
+        Subdivision::Eighth,
+        Subdivision::SecondTriplet,
+    ];
+
+    /// The part of the beat before the note starts
+    pub fn fraction(self) -> f32 {
+        match self {
+            Subdivision::FirstTriplet => 1. / 3.,
+            Subdivision::Eighth => 0.5,
+            Subdivision::SecondTriplet => 2. / 3.,
+        }
+    }
+
+    /// The subdivision nearest to a part of the beat, if it is less than a twelfth of
+    /// a beat away: half way between the first triplet and the eighth note
+    pub fn nearest(fraction: f32) -> Option<Self> {
+        let distance = |subdivision: &Subdivision| (subdivision.fraction() - fraction).abs();
+        Self::ALL
+            .into_iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .filter(|subdivision| distance(subdivision) <= 1. / 12.)
+    }
+}
+
+impl Display for Subdivision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Subdivision::FirstTriplet => write!(f, "first triplet"),
+            Subdivision::Eighth => write!(f, "eighth note"),
+            Subdivision::SecondTriplet => write!(f, "second triplet"),
+        }
+    }
+}
+
+/// A note played between two beats, such as a pickup, a skip or a ghost note
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ornament {
+    /// The beat the note is played after
+    pub beat: u32,
+    pub subdivision: Subdivision,
+    /// Unknown for ghost notes, that have no clear pitch
+    pub note: PlayedNote,
+}
+
+impl Ornament {
+    /// Beats from the start of the take to the note
+    pub fn position(&self) -> f32 {
+        self.beat as f32 + self.subdivision.fraction()
+    }
+}
+
+/// A pitch that might have been played on a beat
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub pitch: Pitch,
+    /// From 0 to 1, the likelihoods of all candidates of a beat add up to at most 1
+    pub likelihood: f32,
+}
+
+/// How sure the transcription is of the note on a beat, with the pitches that were heard
+/// most likely first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Confidence {
+    /// From 0 to 1: 1 for silence, the likelihood of the transcribed pitch for a note
+    /// and 0 for unknown notes
+    pub score: f32,
+    pub candidates: Vec<Candidate>,
+}
+
+impl Confidence {
+    /// Below this score the transcribed note is a guess
+    pub const UNCERTAIN: f32 = 0.5;
+
+    pub fn is_uncertain(&self) -> bool {
+        self.score < Self::UNCERTAIN
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PlayedNote {
-    Surely(Note),
+    Surely(Pitch),
     Unknown,
     Silence,
 }
@@ -35,8 +140,13 @@ pub struct BeatData {
     number: usize,
     dominant_frequency: f32,
     root_frequency: Option<f32>,
+    /// Frequencies the detector might have heard, with their likelihoods
+    candidates: Vec<(f32, f32)>,
     maximum_amplitude: f32,
     note: Option<Note>,
+    pitch: Option<Pitch>,
+    /// Where the note on this beat started, if an onset was found near the beat
+    onset: Option<BeatOnset>,
     human_readable_note: String,
     fft: Vec<f32>,
     samples: Vec<f32>,
@@ -49,6 +159,11 @@ pub struct TranscriptionData {
 }
 
 impl TranscriptionData {
+    /// The onset of the note on every beat, holding how early or late it was played
+    pub fn onsets(&self) -> Vec<Option<BeatOnset>> {
+        self.beat_data.iter().map(|beat| beat.onset).collect()
+    }
+
     pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
         // Save all the beat data for debugging purposes
         let mut file = File::create(PathBuf::from(path))?;
@@ -60,6 +175,11 @@ impl TranscriptionData {
 
 pub struct TranscriptionSettings {
     pub silence_threshold: f32,
+    /// How much a note start must stand out, as part of the strongest note start
+    pub onset_threshold: f32,
+    /// The harmonic product spectrum is the default: bass notes often have a weak
+    /// fundamental, and then `FftPeaks` hears the octave or the fifth above
+    pub pitch_detector: &'static dyn PitchDetector,
 }
 
 pub struct AudioSettings {
@@ -68,9 +188,25 @@ pub struct AudioSettings {
 
 pub const DEFAULT_SETTINGS: TranscriptionSettings = TranscriptionSettings {
     silence_threshold: 200. / i32::MAX as f32,
+    onset_threshold: 0.1,
+    pitch_detector: &HarmonicProductSpectrum::DEFAULT,
 };
 
 impl Transcription {
+    /// A transcription that is sure of every note, without notes between the beats
+    pub fn new(notes: Vec<PlayedNote>) -> Self {
+        Self {
+            notes,
+            confidence: vec![],
+            ornaments: vec![],
+        }
+    }
+
+    pub fn with_ornaments(mut self, ornaments: Vec<Ornament>) -> Self {
+        self.ornaments = ornaments;
+        self
+    }
+
     pub fn transcribe_from_wav(
         path: &Path,
         tempo: f32,
@@ -107,36 +243,47 @@ impl Transcription {
         let beats_per_second = tempo as f64 / 60.;
         let samples_per_beat = (samples_per_second / beats_per_second).round() as usize;
 
-        let samples_split_per_beat = samples.chunks_exact(samples_per_beat);
+        let beats = samples.len() / samples_per_beat;
 
         let start_position = (START_OFFSET * samples_per_beat as f64).round() as usize;
         let end_position = (END_OFFSET * samples_per_beat as f64).round() as usize;
         let window_len = end_position - start_position;
-        let mut planner = FftPlanner::<f32>::new();
-        let fft = planner.plan_fft_forward(window_len);
+
+        let onsets = onset::detect_onsets(
+            samples,
+            audio_settings.sample_rate,
+            transcription_settings.onset_threshold,
+        );
+        let beat_onsets = onset::on_beats(&onsets, samples_per_beat, beats);
 
         let mut transcription_data = TranscriptionData {
             beat_data: Vec::new(),
             sample_rate: audio_settings.sample_rate,
         };
 
-        for (beat_number, beat) in samples_split_per_beat.enumerate() {
-            let relevant_samples = &beat[start_position..end_position];
-            let upsampled = relevant_samples;
-            let sample_rate = audio_settings.sample_rate;
-
-            let mut buffer: Vec<Complex<f32>> = upsampled
+        for (beat_number, &beat_onset) in beat_onsets.iter().enumerate() {
+            // Notes are looked at from where they started, held notes and notes
+            // without a clear start from the beat
+            let on_the_beat = beat_number * samples_per_beat + start_position;
+            let start = beat_onset
+                .map(|beat_onset| beat_onset.onset.sample + start_position)
+                .filter(|start| start + window_len <= samples.len())
+                .unwrap_or(on_the_beat);
+            // Stop before the next note starts, this might be a note between beats
+            let end = onsets
                 .iter()
-                .map(|&x| Complex::new(x as f32, 0.0))
-                .collect();
+                .map(|onset| onset.sample)
+                .find(|&sample| sample > start)
+                .map_or(start + window_len, |next| next.min(start + window_len))
+                .max(start + window_len / 2);
 
-            fft.process(&mut buffer);
-
-            let dc_component = buffer.get_mut(0).unwrap();
-            *dc_component = Complex::new(0., 0.); // Get rid of DC part
+            let relevant_samples = &samples[start..end];
+            let sample_rate = audio_settings.sample_rate;
 
+            // Kept to see what the detector had to work with
+            let buffer = pitch::spectrum(relevant_samples);
             let half = buffer.len() / 2;
-            let (max_idx, max_mag) = buffer
+            let (max_idx, _max_mag) = buffer
                 .iter()
                 .take(half)
                 .map(|b| b.norm_sqr())
@@ -146,60 +293,20 @@ impl Transcription {
 
             let dominant_freq_hz = max_idx as f32 * sample_rate as f32 / buffer.len() as f32;
 
-            // Dominant frequency is usually not the root, take every peakt that's over some percentage of the height of dominant,
-            // the lowest frequency there is the root
-            let possible_roots = buffer
-                .iter()
-                .take(half)
-                .enumerate()
-                .filter(|(_i, bin)| {
-                    bin.norm_sqr() > POSSIBLE_ROOT_RELATIVE_HEIGHT_TO_DOMINANT * max_mag
-                })
-                .collect::<Vec<_>>();
-
-            let bin_as_freq = |bin| (bin as f32) * sample_rate as f32 / buffer.len() as f32;
-
-            let root_freq_hz: Option<f32> = possible_roots.first().and_then(|(bin, _power)| {
-                if *bin == 0 {
-                    println!("DC showed up as possible root, ignoring.");
-                    return None; // DC, useless, and filtered out earlier
-                }
-
-                let center_bin = *bin;
-                let left_bin = center_bin - 1;
-                let right_bin = center_bin + 1;
-
-                let center_power = buffer.get(center_bin)?.norm_sqr();
-                let left_power = buffer.get(left_bin)?.norm_sqr();
-                let right_power = buffer.get(right_bin)?.norm_sqr();
-
-                let (other_power, other_bin, sign) = if left_power > right_power {
-                    (left_power, left_bin, -1.)
-                } else {
-                    (right_power, right_bin, 1.)
-                };
-
-                let center_freq = bin_as_freq(center_bin);
-                let other_freq = bin_as_freq(other_bin);
-
-                let freq_diff = (center_freq - other_freq).abs();
-
-                let freq_offset = (1.0 - (center_power / (center_power + other_power))) * freq_diff;
-
-                Some(center_freq + freq_offset * sign)
-            });
+            let candidates = transcription_settings
+                .pitch_detector
+                .candidates(relevant_samples, sample_rate);
+            let root_freq_hz = candidates.first().map(|&(frequency, _)| frequency);
 
-            let note = root_freq_hz.and_then(|freq| {
-                let (note, error) = Note::from_frequency(freq);
-                if error.abs() > 0.25 { TODO: set this very low and fix the UI bug of silence not appearing
+            let pitch = root_freq_hz.and_then(|freq| {
+                let (pitch, error) = Pitch::from_frequency(freq)?;
+                if error.abs() > MAX_ERROR {
+                    // TODO: set this very low and fix the UI bug of silence not appearing
                     // Note is too sharp or flat
-                    println!(
-                        "{beat_number} Found {} with large error: {error}",
-                        note.flat()
-                    );
+                    println!("{beat_number} Found {} with large error: {error}", pitch);
                     None
                 } else {
-                    Some(note)
+                    Some(pitch)
                 }
             });
 
@@ -209,31 +316,121 @@ impl Transcription {
                 fft: buffer.iter().map(|c| c.norm_sqr()).collect(),
                 dominant_frequency: dominant_freq_hz,
                 root_frequency: root_freq_hz,
+                candidates,
                 maximum_amplitude: relevant_samples.iter().copied().reduce(f32::max).unwrap(),
-                human_readable_note: note
-                    .map(|n| format!("{}", n.flat()))
-                    .unwrap_or("".to_string()),
-                note,
+                human_readable_note: pitch.map(|p| format!("{}", p)).unwrap_or("".to_string()),
+                note: pitch.map(|p| p.note()),
+                pitch,
+                onset: beat_onset,
             });
         }
 
         let mut result = vec![];
+        let mut confidence = vec![];
 
         for beat in transcription_data.beat_data.iter() {
-            let Some(note) = beat.note else {
+            let candidates = candidates(&beat.candidates);
+
+            // A silent beat has no pitch either, so check for silence first
+            if beat.maximum_amplitude < transcription_settings.silence_threshold {
+                result.push(PlayedNote::Silence);
+                confidence.push(Confidence {
+                    score: 1.,
+                    candidates: vec![],
+                });
+                continue;
+            }
+
+            let Some(pitch) = beat.pitch else {
                 result.push(PlayedNote::Unknown);
+                confidence.push(Confidence {
+                    score: 0.,
+                    candidates,
+                });
                 continue;
             };
+            result.push(PlayedNote::Surely(pitch));
+            confidence.push(Confidence {
+                score: candidates
+                    .iter()
+                    .find(|candidate| candidate.pitch == pitch)
+                    .map_or(0., |candidate| candidate.likelihood),
+                candidates,
+            });
+        }
 
-            if beat.maximum_amplitude < transcription_settings.silence_threshold {
-                result.push(PlayedNote::Silence);
+        // Notes between the beats are looked at until the next note starts, like notes on the beat
+        let mut ornaments = vec![];
+        for SubdivisionOnset {
+            beat,
+            subdivision,
+            onset,
+        } in onset::between_beats(&onsets, &beat_onsets, samples_per_beat)
+        {
+            let start = onset.sample + start_position;
+            let end = onsets
+                .iter()
+                .map(|onset| onset.sample)
+                .find(|&sample| sample > start)
+                .map_or(start + window_len, |next| next.min(start + window_len))
+                .min(samples.len());
+            if start >= end {
+                continue;
+            }
+
+            let relevant_samples = &samples[start..end];
+            let maximum_amplitude = relevant_samples.iter().copied().reduce(f32::max).unwrap();
+            if maximum_amplitude < transcription_settings.silence_threshold {
                 continue;
             }
-            // TODO: when doing the from_frequency computation we can add a deviation from perfect
-            // so we have a metric to how sure we are it's this note
-            result.push(PlayedNote::Surely(note));
+
+            // Too short to hear a pitch in is a ghost note as well
+            let pitch = (end - start >= window_len / 4)
+                .then(|| {
+                    transcription_settings
+                        .pitch_detector
+                        .detect(relevant_samples, audio_settings.sample_rate)
+                })
+                .flatten()
+                .and_then(Pitch::from_frequency)
+                .filter(|(_, error)| error.abs() <= MAX_ERROR);
+
+            ornaments.push(Ornament {
+                beat,
+                subdivision,
+                note: pitch.map_or(PlayedNote::Unknown, |(pitch, _)| PlayedNote::Surely(pitch)),
+            });
         }
 
-        (Transcription { notes: result }, transcription_data)
+        (
+            Transcription {
+                notes: result,
+                confidence,
+                ornaments,
+            },
+            transcription_data,
+        )
+    }
+}
+
+/// The pitches of the frequencies a detector heard, most likely first. A frequency
+/// between two pitches is less likely to be either of them.
+fn candidates(frequencies: &[(f32, f32)]) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = vec![];
+    for &(frequency, weight) in frequencies {
+        let Some((pitch, error)) = Pitch::from_frequency(frequency) else {
+            continue;
+        };
+        let likelihood = weight * (1. - error.abs() / (2. * MAX_ERROR)).max(0.);
+
+        match candidates
+            .iter_mut()
+            .find(|candidate| candidate.pitch == pitch)
+        {
+            Some(candidate) => candidate.likelihood = candidate.likelihood.max(likelihood),
+            None => candidates.push(Candidate { pitch, likelihood }),
+        }
     }
+    candidates.sort_by(|a, b| b.likelihood.total_cmp(&a.likelihood));
+    candidates
 }
@@ -1,9 +1,14 @@
 use walkanalysis::{
-    analysis::analysis::{Analysis, NoteAnalysis},
+    analysis::{
+        analysis::{Analysis, NoteAnalysis},
+        mistake::MistakeKind,
+    },
+    exercise::{arpeggios_up::ArpeggiosUp, chord_tones::ChordTones, Exercise},
     form::{
         chord::ChordTone,
-        form::{bar, Form},
+        form::{bar, half_bar, split_bar, Form},
         key::{self, Key, Quality},
+        meter::WALTZ,
         note::*,
     },
     transcribe::transcribe::{PlayedNote, Transcription},
@@ -11,9 +16,9 @@ use walkanalysis::{
 
 fn assert_role(expected_degree: key::Degree, expected_role: ChordTone, na: NoteAnalysis) {
     let NoteAnalysis::Note {
-        note: _,
         degree_in_key,
         role_in_chord,
+        ..
     } = na
     else {
         panic!("Not noteanalysis")
@@ -27,22 +32,20 @@ fn assert_role(expected_degree: key::Degree, expected_role: ChordTone, na: NoteA
 fn test_analysis() {
     let form = Form::new(110, Key::new(G, Quality::Minor).flat(), vec![bar(C.min7())]);
 
-    let transcription = Transcription {
-        notes: vec![
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Surely(C),
-            PlayedNote::Surely(E_FLAT),
-            PlayedNote::Surely(G),
-            PlayedNote::Surely(G_FLAT),
-        ],
-    };
+    let transcription = Transcription::new(vec![
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Surely(C.at_octave(2)),
+        PlayedNote::Surely(E_FLAT.at_octave(2)),
+        PlayedNote::Surely(G.at_octave(2)),
+        PlayedNote::Surely(G_FLAT.at_octave(2)),
+    ]);
 
     let analysis = Analysis::analyze(transcription, &form);
 
@@ -72,26 +75,24 @@ fn test_analysis() {
 
 #[test]
 fn test_gmin() {
-    let transcription = Transcription {
-        notes: vec![
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Silence,
-            PlayedNote::Surely(G),
-            PlayedNote::Surely(D_FLAT),
-            PlayedNote::Surely(B_FLAT),
-            PlayedNote::Surely(G),
-            PlayedNote::Surely(G),
-            PlayedNote::Surely(B_FLAT),
-            PlayedNote::Surely(D_FLAT),
-            PlayedNote::Surely(F),
-        ],
-    };
+    let transcription = Transcription::new(vec![
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Silence,
+        PlayedNote::Surely(G.at_octave(2)),
+        PlayedNote::Surely(D_FLAT.at_octave(2)),
+        PlayedNote::Surely(B_FLAT.at_octave(2)),
+        PlayedNote::Surely(G.at_octave(2)),
+        PlayedNote::Surely(G.at_octave(2)),
+        PlayedNote::Surely(B_FLAT.at_octave(2)),
+        PlayedNote::Surely(D_FLAT.at_octave(2)),
+        PlayedNote::Surely(F.at_octave(2)),
+    ]);
     let form = Form::new(
         110,
         Key::new(G, Quality::Minor).flat(),
@@ -102,3 +103,124 @@ fn test_gmin() {
 
     dbg!(analysis);
 }
+
+#[test]
+fn test_slash_chord_bass_on_one() {
+    let form = Form::new(
+        110,
+        Key::new(C, Quality::Major).sharp(),
+        vec![bar(C.maj().with_bass(E)), bar(C.maj().with_bass(E))],
+    );
+
+    let mut notes = vec![PlayedNote::Silence; 8];
+    notes.extend([
+        PlayedNote::Surely(E.at_octave(2)),
+        PlayedNote::Surely(G.at_octave(2)),
+        PlayedNote::Surely(C.at_octave(2)),
+        PlayedNote::Surely(E.at_octave(2)),
+        PlayedNote::Surely(C.at_octave(2)),
+        PlayedNote::Surely(G.at_octave(2)),
+        PlayedNote::Surely(C.at_octave(2)),
+        PlayedNote::Surely(E.at_octave(2)),
+    ]);
+
+    let analysis = Analysis::analyze(Transcription::new(notes), &form);
+    let correction = ChordTones {}.correct(&analysis);
+
+    assert_eq!(correction.mistakes.len(), 1);
+    let mistake = correction.mistakes.get(&12).unwrap();
+    let MistakeKind::WrongNote { played, expected } = mistake.mistake else {
+        panic!("Expected a wrong note, found {:?}", mistake.mistake)
+    };
+    assert_eq!(played, C);
+    assert_eq!(expected, E);
+}
+
+#[test]
+fn test_waltz_arpeggios() {
+    let form = Form::new(
+        140,
+        Key::new(C, Quality::Major).sharp(),
+        vec![bar(C.maj()), half_bar(D.min7(), G.dominant7())],
+    )
+    .with_meter(WALTZ);
+    assert_eq!(form.length_in_beats(), 12);
+
+    let mut notes = vec![PlayedNote::Silence; 6];
+    notes.extend([
+        PlayedNote::Surely(C.at_octave(2)),
+        PlayedNote::Surely(E.at_octave(2)),
+        PlayedNote::Surely(G.at_octave(2)),
+        PlayedNote::Surely(D.at_octave(2)),
+        PlayedNote::Surely(E.at_octave(2)),
+        PlayedNote::Surely(D.at_octave(3)),
+    ]);
+
+    let analysis = Analysis::analyze(Transcription::new(notes), &form);
+    let correction = ArpeggiosUp {}.correct(&analysis);
+
+    // Only the E on the second beat of the second bar is wrong, the D on beat 3 is the fifth of G7
+    assert_eq!(correction.mistakes.len(), 1);
+    let mistake = correction.mistakes.get(&10).unwrap();
+    let MistakeKind::WrongNote { played, expected } = mistake.mistake else {
+        panic!("Expected a wrong note, found {:?}", mistake.mistake)
+    };
+    assert_eq!(played, E);
+    assert_eq!(expected, F);
+    assert!(mistake.to_string().starts_with("[4.2 | 10]"));
+}
+
+#[test]
+fn test_scores_per_chorus() {
+    let form = Form::new(
+        120,
+        Key::new(C, Quality::Major).sharp(),
+        vec![bar(C.maj()), bar(G.dominant7())],
+    )
+    .with_choruses(2);
+    // The count off is only played before the first chorus
+    assert_eq!(form.length_in_beats(), 8 + 2 * 8);
+
+    let mut notes = vec![PlayedNote::Silence; 8];
+    for third in [A, E] {
+        notes.extend(
+            [C, third, G, C, G, B, D, F]
+                .iter()
+                .map(|note| PlayedNote::Surely(note.at_octave(2))),
+        );
+    }
+
+    let analysis = Analysis::analyze(Transcription::new(notes), &form);
+    assert_eq!(analysis.beat_positions[&7].chorus, 0);
+    assert_eq!(analysis.beat_positions[&15].chorus, 0);
+    assert_eq!(analysis.beat_positions[&16].chorus, 1);
+    assert_eq!(analysis.beat_positions[&16].bar, 4);
+
+    let correction = ArpeggiosUp {}.correct(&analysis);
+    assert_eq!(correction.mistakes.len(), 1);
+    assert_eq!(correction.mistakes[&9].position.chorus, 0);
+    assert_eq!(correction.chorus_scores(), vec![1.0 - 1.0 / 16.0, 1.0]);
+}
+
+#[test]
+fn test_split_bar_without_chords() {
+    let form = Form::new(
+        110,
+        Key::new(C, Quality::Major).sharp(),
+        vec![split_bar(vec![]), bar(C.maj7())],
+    );
+
+    let mut notes = vec![PlayedNote::Silence; 8];
+    notes.extend([C, E, G, B, C, E, G, B].map(|note| PlayedNote::Surely(note.at_octave(2))));
+    let analysis = Analysis::analyze(Transcription::new(notes), &form);
+    ChordTones {}.correct(&analysis);
+
+    assert!(matches!(
+        analysis.beat_analysis[&8].1,
+        NoteAnalysis::NoteDuringSilence { .. }
+    ));
+    assert!(matches!(
+        analysis.beat_analysis[&12].1,
+        NoteAnalysis::Note { .. }
+    ));
+}
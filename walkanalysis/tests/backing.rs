@@ -0,0 +1,95 @@
+use std::f32::consts::TAU;
+
+use walkanalysis::{
+    form::{
+        form::{bar, Form},
+        key::{Key, Quality},
+        note::{C, D, F},
+        pitch::Pitch,
+    },
+    synth::{backing::BackingTrack, samples_per_beat},
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+
+fn form() -> Form {
+    Form::new(120, Key::new(C, Quality::Major).flat(), vec![bar(C.maj7())])
+}
+
+fn peak(samples: &[f32]) -> f32 {
+    samples
+        .iter()
+        .fold(0., |peak, sample| peak.max(sample.abs()))
+}
+
+/// Strength of the frequency in the samples
+fn magnitude(samples: &[f32], frequency: f32) -> f32 {
+    let (re, im) = samples
+        .iter()
+        .enumerate()
+        .fold((0., 0.), |(re, im), (i, sample)| {
+            let phase = TAU * frequency * i as f32 / SAMPLE_RATE as f32;
+            (re + sample * phase.cos(), im + sample * phase.sin())
+        });
+    (re * re + im * im).sqrt() / samples.len() as f32
+}
+
+#[test]
+fn test_count_off_clicks() {
+    let backing = BackingTrack::default();
+    let samples = backing.render(&form(), 120.);
+    let beat = samples_per_beat(120., SAMPLE_RATE);
+    assert_eq!(samples.len(), 12 * beat);
+
+    // A short click at the start of every beat of the count off, nothing else
+    for count in 0..8 {
+        let start = count * beat;
+        assert!(peak(&samples[start..start + 2_000]) > 0.1);
+        assert!(peak(&samples[start + beat / 4..start + beat]) < 1e-3);
+    }
+    // The first beat of the bar clicks higher
+    let downbeat = &samples[0..2_000];
+    assert!(magnitude(downbeat, 1_500.) > magnitude(downbeat, 1_000.));
+    let upbeat = &samples[beat..beat + 2_000];
+    assert!(magnitude(upbeat, 1_000.) > magnitude(upbeat, 1_500.));
+}
+
+#[test]
+fn test_piano_voicing() {
+    let backing = BackingTrack {
+        drums_volume: 0.,
+        click_volume: 0.,
+        ..Default::default()
+    };
+    let samples = backing.render(&form(), 120.);
+    let beat = samples_per_beat(120., SAMPLE_RATE);
+    let bar = &samples[8 * beat..10 * beat];
+
+    // Cmaj7 is voiced G3 B3 E4
+    let strength = |note: &str| magnitude(bar, note.parse::<Pitch>().unwrap().frequency());
+    for chord_tone in ["G3", "B3", "E4"] {
+        for other in ["F3", "C4", "D4", "F4"] {
+            assert!(strength(chord_tone) > 10. * strength(other));
+        }
+    }
+}
+
+#[test]
+fn test_swing_ride() {
+    let backing = BackingTrack {
+        piano_volume: 0.,
+        click_volume: 0.,
+        ..Default::default()
+    };
+    let form = Form::new(120, Key::new(F, Quality::Major).flat(), vec![bar(D.min7())]);
+    let samples = backing.render(&form, 120.);
+    let beat = samples_per_beat(120., SAMPLE_RATE);
+
+    // The skip note is two thirds into the second beat, there is none after the first
+    let loudness = |from: f32| {
+        let start = 8 * beat + (from * beat as f32) as usize;
+        peak(&samples[start..start + 200])
+    };
+    assert!(loudness(1. + 2. / 3.) > 1.5 * loudness(1. + 2. / 3. - 0.01));
+    assert!(loudness(2. / 3.) < loudness(2. / 3. - 0.01));
+}
@@ -0,0 +1,112 @@
+use test_case::test_case;
+use walkanalysis::form::{
+    chart::ChartError,
+    form::{bar, half_bar, split_bar, Form, FormPiece},
+    key::{Key, Quality},
+    meter::WALTZ,
+    note::{A, B_FLAT, C, D, E_FLAT, F, G},
+    songs::{autumn_leaves::autumn_leaves, but_beautiful::but_beautiful, test::test},
+};
+
+#[test]
+fn test_parse_chart() {
+    let chart = "key: Gm
+tempo: 140
+meter: 3/4
+
+# The first eight bars
+[A]
+|: Cm7 | F7 | Bbmaj7 | Ebmaj7 |
+| 1. Am7b5 D7 :| 2. Gm7*2 C7 | F |";
+
+    let form: Form = chart.parse().unwrap();
+    let expected = Form::new(
+        140,
+        Key::new(G, Quality::Minor).flat(),
+        vec![
+            FormPiece::Section("A".into()),
+            FormPiece::LineBreak,
+            FormPiece::RepeatStart,
+            bar(C.min7()),
+            bar(F.dominant7()),
+            bar(B_FLAT.maj7()),
+            bar(E_FLAT.maj7()),
+            FormPiece::LineBreak,
+            FormPiece::Ending(1),
+            half_bar(A.m7b5(), D.dominant7()),
+            FormPiece::RepeatEnd(2),
+            FormPiece::Ending(2),
+            split_bar(vec![(G.min7(), 2), (C.dominant7(), 1)]),
+            bar(F.maj()),
+        ],
+    )
+    .with_meter(WALTZ);
+
+    assert_eq!(form, expected);
+}
+
+#[test]
+fn test_bar_lines_without_spaces() {
+    let spaced: Form = "key: C\ntempo: 120\n| Dm7 | G7 :|x3 Cmaj7 |"
+        .parse()
+        .unwrap();
+    let compact: Form = "key: C\ntempo: 120\n|Dm7|G7:|x3 Cmaj7|".parse().unwrap();
+    assert_eq!(spaced, compact);
+    assert_eq!(spaced.music()[3], FormPiece::RepeatEnd(3));
+}
+
+#[test_case(autumn_leaves(); "autumn leaves form")]
+#[test_case(but_beautiful(); "but beautiful form")]
+#[test_case(test().with_choruses(3); "test form in three choruses")]
+fn test_chart_round_trip(form: Form) {
+    let chart = form.to_chart();
+    let parsed: Form = chart.parse().unwrap();
+    assert_eq!(parsed, form, "{}", chart);
+}
+
+#[test]
+fn test_print_chart() {
+    let form = Form::new(
+        120,
+        Key::new(F, Quality::Major).flat(),
+        vec![
+            FormPiece::Section("A".into()),
+            FormPiece::RepeatStart,
+            bar(G.min7()),
+            half_bar(C.dominant7(), B_FLAT.maj7()),
+            FormPiece::RepeatEnd(2),
+            FormPiece::LineBreak,
+            bar(D.min7()),
+        ],
+    );
+
+    assert_eq!(
+        form.to_chart(),
+        "key: F\ntempo: 120\n[A] |: Gm7 | C7 B♭maj7 :|\n| Dm7 |"
+    );
+}
+
+#[test_case("tempo: 120\n| C |", 1, 1, "Missing `key:` header")]
+#[test_case("key: C\ntempo: fast\n| C |", 2, 8, "Invalid tempo `fast`")]
+#[test_case("key: C\ntempo: 120\n| C | Hm7 |", 3, 7, "Invalid root note `Hm7`")]
+#[test_case(
+    "key: C\ntempo: 120\n\n| C | Dm7*x |",
+    4,
+    7,
+    "Invalid number of beats `x`"
+)]
+#[test_case("key: C\ntempo: 120\n| C [A |", 3, 5, "Unclosed section label `[A |`")]
+fn test_chart_errors(chart: &str, line: usize, column: usize, message: &str) {
+    let expected = ChartError {
+        line,
+        column,
+        message: message.into(),
+    };
+    assert_eq!(chart.parse::<Form>(), Err(expected));
+}
+
+#[test]
+fn test_open_chart() {
+    let form = Form::open_chart("tests/data/forms/autumn_leaves.chart").unwrap();
+    assert_eq!(form, autumn_leaves());
+}
@@ -1,4 +1,10 @@
-use walkanalysis::form::{chord::ChordTone, note::*};
+use test_case::test_case;
+use walkanalysis::form::{
+    chord::{Chord, ChordTone},
+    chord_name::SymbolStyle,
+    chord_parser::ChordParseError,
+    note::*,
+};
 
 #[test]
 fn test_chords() {
@@ -17,6 +23,75 @@ fn test_spelling() {
     println!("{}", B_FLAT.dim7().auto_spell());
 }
 
+#[test_case(C.dim7(), "C E♭ G♭ B𝄫 " ; "c diminished seventh")]
+#[test_case(G_SHARP.dim7(), "G♯ B D F " ; "g sharp diminished seventh")]
+#[test_case(D_FLAT.min(), "C♯ E G♯ " ; "d flat minor")]
+#[test_case(B_FLAT.dim7(), "A♯ C♯ E G " ; "b flat diminished seventh")]
+#[test_case(C_SHARP.dominant7_sharp5(), "D♭ F A C♭ " ; "c sharp seven sharp five")]
+#[test_case(F_SHARP.maj7(), "G♭ B♭ D♭ F " ; "f sharp major seventh")]
+#[test_case(E_FLAT.maj7().with_bass(G), "E♭ G B♭ D /G " ; "slash bass as chord tone")]
+fn test_auto_spell(chord: Chord, expected: &str) {
+    let written = chord.auto_spell();
+    assert!(written.is_spelled_correctly());
+    assert_eq!(written.to_string(), expected);
+}
+
+#[test]
+fn test_spelling_never_panics() {
+    let chords: Vec<fn(&Note) -> Chord> = vec![
+        Note::min,
+        Note::maj,
+        Note::maj7,
+        Note::maj9,
+        Note::min6,
+        Note::min7,
+        Note::min9,
+        Note::dominant7,
+        Note::dominant9,
+        Note::dominant7_sharp5,
+        Note::dim,
+        Note::m7b5,
+        Note::dim7,
+    ];
+
+    for root in 0..12 {
+        for chord in chords.iter() {
+            let chord = chord(&Note::from(root));
+            for written in [chord.sharp(), chord.flat(), chord.auto_spell()] {
+                assert!(written.is_spelled_correctly(), "{}", written);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_spell_from_root() {
+    let c_flat = WrittenNote::new(NoteName::C, Accidental::Flat);
+    assert_eq!(B.maj7().spell_from_root(c_flat).to_string(), "C♭ E♭ G♭ B♭ ");
+
+    let f_flat = WrittenNote::new(NoteName::F, Accidental::Flat);
+    let written = E.maj().spell_from_root(f_flat);
+    assert!(written.is_spelled_correctly());
+    assert_eq!(written.to_string(), "F♭ A♭ C♭ ");
+
+    let e_sharp = WrittenNote::new(NoteName::E, Accidental::Sharp);
+    assert_eq!(
+        F.dominant7().spell_from_root(e_sharp).to_string(),
+        "E♯ G𝄪 B♯ D♯ "
+    );
+}
+
+#[test_case("Bbb", NoteName::B, Accidental::DoubleFlat)]
+#[test_case("F##", NoteName::F, Accidental::DoubleSharp)]
+#[test_case("C𝄪", NoteName::C, Accidental::DoubleSharp)]
+#[test_case("E𝄫", NoteName::E, Accidental::DoubleFlat)]
+fn test_parse_double_accidentals(s: &str, name: NoteName, accidental: Accidental) {
+    assert_eq!(
+        WrittenNote::parse_prefix(s),
+        Some((WrittenNote::new(name, accidental), ""))
+    );
+}
+
 #[test]
 fn test_symbols() {
     println!("{}", A.m7b5().flat_symbol());
@@ -33,3 +108,167 @@ fn test_roles() {
     println!("{}", gmin.note(ChordTone::Root).unwrap().flat());
     println!("{:?}", gmin.role(G));
 }
+
+#[test]
+fn test_extended_roles() {
+    let a9 = A.dominant9();
+    assert_eq!(a9.role(B), ChordTone::Ninth);
+    assert_eq!(a9.role(G), ChordTone::Seventh);
+    assert_eq!(a9.note(ChordTone::Ninth), Some(B));
+    assert!(a9.has_seventh());
+
+    let gmaj9 = G.maj9();
+    assert_eq!(gmaj9.role(A), ChordTone::Ninth);
+    assert_eq!(gmaj9.note(ChordTone::Seventh), Some(F_SHARP));
+
+    let b7_sharp5 = B.dominant7_sharp5();
+    assert_eq!(b7_sharp5.role(G), ChordTone::Fifth);
+    assert_eq!(b7_sharp5.role(F_SHARP), ChordTone::NoChordTone);
+
+    let d_min6 = D.min6();
+    assert_eq!(d_min6.role(B), ChordTone::Sixth);
+    assert!(!d_min6.has_seventh());
+    assert!(!G.min().has_seventh());
+}
+
+#[test_case("Bbmaj7", B_FLAT.maj7())]
+#[test_case("BbΔ7", B_FLAT.maj7() ; "Bb delta 7")]
+#[test_case("F#m7b5", F_SHARP.m7b5())]
+#[test_case("F#ø", F_SHARP.m7b5())]
+#[test_case("Cm7", C.min7())]
+#[test_case("C-7", C.min7())]
+#[test_case("Gm", G.min())]
+#[test_case("D7", D.dominant7())]
+#[test_case("G#dim7", G_SHARP.dim7())]
+#[test_case("Ab°7", A_FLAT.dim7())]
+#[test_case("A9", A.dominant9())]
+#[test_case("Gmaj9", G.maj9())]
+#[test_case("Am9", A.min9())]
+#[test_case("Dm6", D.min6())]
+#[test_case("B7#5", B.dominant7_sharp5())]
+#[test_case("B7(+5)", B.dominant7_sharp5() ; "B7 plus 5")]
+fn test_parse_symbol(symbol: &str, expected: Chord) {
+    let chord: Chord = symbol.parse().unwrap();
+    assert_eq!(chord.tones(), expected.tones());
+}
+
+#[test]
+fn test_parse_extensions() {
+    let g7alt: Chord = "G7alt".parse().unwrap();
+    assert_eq!(g7alt.note(ChordTone::FlatNinth), Some(A_FLAT));
+    assert_eq!(g7alt.note(ChordTone::SharpNinth), Some(A_SHARP));
+    assert_eq!(g7alt.note(ChordTone::FlatThirteenth), Some(E_FLAT));
+    assert_eq!(g7alt.note(ChordTone::Fifth), None);
+
+    let c69: Chord = "C6/9".parse().unwrap();
+    assert_eq!(c69.note(ChordTone::Sixth), Some(A));
+    assert_eq!(c69.note(ChordTone::Ninth), Some(D));
+    assert_eq!(c69.slash_bass(), None);
+
+    let c7sus: Chord = "C7sus4".parse().unwrap();
+    assert_eq!(c7sus.note(ChordTone::Third), None);
+    assert_eq!(c7sus.note(ChordTone::Fourth), Some(F));
+
+    let c_mmaj7: Chord = "Cm(maj7)".parse().unwrap();
+    assert_eq!(c_mmaj7.note(ChordTone::Third), Some(E_FLAT));
+    assert_eq!(c_mmaj7.note(ChordTone::Seventh), Some(B));
+
+    let c13: Chord = "C13#11".parse().unwrap();
+    assert_eq!(c13.note(ChordTone::Thirteenth), Some(A));
+    assert_eq!(c13.note(ChordTone::SharpEleventh), Some(F_SHARP));
+
+    let cadd9: Chord = "Cmadd9".parse().unwrap();
+    assert_eq!(cadd9.note(ChordTone::Ninth), Some(D));
+    assert!(!cadd9.has_seventh());
+}
+
+#[test]
+fn test_parse_slash_chords() {
+    let c_over_e: Chord = "C/E".parse().unwrap();
+    assert_eq!(c_over_e.tones(), C.maj().tones());
+    assert_eq!(c_over_e.slash_bass(), Some(E));
+
+    let dm7_over_g: Chord = "Dm7/G".parse().unwrap();
+    assert_eq!(dm7_over_g.slash_bass(), Some(G));
+}
+
+#[test_case("", ChordParseError::Empty)]
+#[test_case("H7", ChordParseError::InvalidRoot("H7".into()))]
+#[test_case("Cm7xyz", ChordParseError::UnknownToken("xyz".into()))]
+#[test_case("C7(b9,q)", ChordParseError::UnknownToken("q".into()))]
+#[test_case("C/X", ChordParseError::InvalidBass("X".into()))]
+fn test_parse_errors(symbol: &str, expected: ChordParseError) {
+    assert_eq!(symbol.parse::<Chord>(), Err(expected));
+}
+
+#[test]
+fn test_slash_chord_roles() {
+    let c_over_e = C.maj().with_bass(E);
+    assert_eq!(c_over_e.bass(), E);
+    assert_eq!(c_over_e.role(E), ChordTone::Third);
+    assert_eq!(c_over_e.note(ChordTone::Bass), Some(E));
+    assert_eq!(c_over_e.flat_symbol(), "C/E");
+
+    let c_over_d = C.maj().with_bass(D);
+    assert_eq!(c_over_d.role(D), ChordTone::Bass);
+    assert_eq!(C.maj().bass(), C);
+    assert_eq!(C.maj().with_bass(C).slash_bass(), None);
+}
+
+#[test_case(C.maj(), "", "" ; "major")]
+#[test_case(C.min(), "m", "−" ; "minor")]
+#[test_case(C.maj7(), "maj7", "Δ" ; "major seventh")]
+#[test_case(C.min7(), "m7", "−7" ; "minor seventh")]
+#[test_case(C.dominant7(), "7", "7" ; "dominant")]
+#[test_case(C.m7b5(), "m7b5", "ø" ; "half diminished")]
+#[test_case(C.dim(), "dim", "°" ; "diminished")]
+#[test_case(C.dim7(), "dim7", "°7" ; "diminished seventh")]
+#[test_case(C.maj9(), "maj9", "Δ9" ; "major ninth")]
+#[test_case(C.min9(), "m9", "−9" ; "minor ninth")]
+#[test_case(C.dominant9(), "9", "9" ; "dominant ninth")]
+#[test_case(C.min6(), "m6", "−6" ; "minor sixth")]
+#[test_case(C.dominant7_sharp5(), "7#5", "7♯5" ; "dominant sharp five")]
+#[test_case(C.maj().with(ChordTone::Fifth, G_SHARP), "aug", "+" ; "augmented")]
+#[test_case(C.min().with(ChordTone::Seventh, B), "m(maj7)", "−Δ" ; "minor major seventh")]
+#[test_case(C.maj().without(ChordTone::Third).with(ChordTone::Fourth, F), "sus4", "sus4" ; "sus")]
+fn test_quality_symbols(chord: Chord, plain: &str, jazz: &str) {
+    assert_eq!(chord.quality_symbol(SymbolStyle::Plain), plain);
+    assert_eq!(chord.quality_symbol(SymbolStyle::Jazz), jazz);
+}
+
+#[test_case("G7alt")]
+#[test_case("C7sus4")]
+#[test_case("C6/9")]
+#[test_case("Ebm6/9")]
+#[test_case("F13#11")]
+#[test_case("A7b9#9")]
+#[test_case("Bbmaj7#11")]
+#[test_case("Cadd9")]
+#[test_case("C5")]
+#[test_case("C7no3")]
+#[test_case("C6no3")]
+#[test_case("Dm7/G")]
+fn test_symbol_round_trip(symbol: &str) {
+    let chord: Chord = symbol.parse().unwrap();
+    for style in [SymbolStyle::Plain, SymbolStyle::Jazz] {
+        let styled: Chord = chord.styled_symbol(Spelling::Flat, style).parse().unwrap();
+        assert_eq!(styled, chord);
+    }
+}
+
+#[test]
+fn test_song_symbols_round_trip() {
+    use walkanalysis::form::songs::{autumn_leaves::autumn_leaves, but_beautiful::but_beautiful};
+
+    for form in [autumn_leaves(), but_beautiful()] {
+        for piece in form.music() {
+            for chord in piece.chords() {
+                for style in [SymbolStyle::Plain, SymbolStyle::Jazz] {
+                    let symbol = chord.styled_symbol(Spelling::Flat, style);
+                    let parsed: Chord = symbol.parse().unwrap();
+                    assert_eq!(&parsed, chord, "{}", symbol);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use walkanalysis::{
+    analysis::{analysis::Analysis, mistake::MistakeKind},
+    exercise::{chord_tones::ChordTones, written_line::WrittenLine, Exercise},
+    form::{
+        form::{bar, Form},
+        key::{Key, Quality},
+        note::*,
+        pitch::Pitch,
+        songs::autumn_leaves::autumn_leaves,
+    },
+    synth::{bass::BassSynth, mix, samples_per_beat},
+    transcribe::transcribe::{
+        AudioSettings, Candidate, Confidence, PlayedNote, Transcription, DEFAULT_SETTINGS,
+    },
+};
+
+const TEMPO: f32 = 110.;
+
+fn transcribe(samples: &[f32], synth: &BassSynth) -> Transcription {
+    let audio_settings = AudioSettings {
+        sample_rate: synth.sample_rate,
+    };
+    Transcription::transcribe(samples, TEMPO, DEFAULT_SETTINGS, audio_settings).0
+}
+
+/// The score of every beat a note was transcribed on
+fn note_scores(transcription: &Transcription) -> Vec<f32> {
+    transcription
+        .notes
+        .iter()
+        .zip(&transcription.confidence)
+        .filter(|(note, _)| matches!(note, PlayedNote::Surely(_)))
+        .map(|(_, confidence)| confidence.score)
+        .collect()
+}
+
+#[test]
+fn test_clean_notes_are_sure() {
+    let synth = BassSynth::default();
+    let notes = ChordTones {}.generate(&autumn_leaves());
+    let transcription = transcribe(&synth.render(&notes, TEMPO), &synth);
+
+    assert_eq!(transcription.notes, notes);
+    assert_eq!(transcription.confidence.len(), notes.len());
+    for (beat, confidence) in transcription.confidence.iter().enumerate() {
+        assert!(
+            !confidence.is_uncertain(),
+            "beat {}: {:?}",
+            beat,
+            confidence
+        );
+        if let PlayedNote::Surely(pitch) = notes[beat] {
+            assert_eq!(confidence.candidates[0].pitch, pitch);
+        }
+    }
+}
+
+#[test]
+fn test_out_of_tune_notes_are_less_sure() {
+    let notes = ChordTones {}.generate(&autumn_leaves());
+    let in_tune = BassSynth::default();
+    let out_of_tune = BassSynth {
+        detune: 20.,
+        ..Default::default()
+    };
+
+    let sure = transcribe(&in_tune.render(&notes, TEMPO), &in_tune);
+    let unsure = transcribe(&out_of_tune.render(&notes, TEMPO), &out_of_tune);
+
+    assert_eq!(unsure.notes, notes);
+    for (sure, unsure) in note_scores(&sure).iter().zip(note_scores(&unsure)) {
+        assert!(unsure < *sure, "{} is not below {}", unsure, sure);
+    }
+}
+
+#[test]
+fn test_two_notes_at_once() {
+    let synth = BassSynth::default();
+    let beat = samples_per_beat(TEMPO, synth.sample_rate);
+    let (low, high) = (Pitch::from_midi(33), Pitch::from_midi(38));
+
+    let mut samples = vec![0.; 4 * beat];
+    for number in 0..4 {
+        let mut sound = vec![0.; beat];
+        synth.pluck(low, &mut sound);
+        synth.pluck(high, &mut sound);
+        mix(&mut samples, number * beat, &sound, 1.);
+    }
+
+    let transcription = transcribe(&samples, &synth);
+    for confidence in &transcription.confidence {
+        assert!(confidence.is_uncertain(), "{:?}", confidence);
+        let pitches: Vec<Pitch> = confidence.candidates.iter().map(|c| c.pitch).collect();
+        assert!(pitches.contains(&low) && pitches.contains(&high));
+    }
+}
+
+/// A bar of C major with the count off, where a D was heard on the third beat and
+/// the transcription was not sure it wasn't an E
+fn unsure_d(likelihood_of_e: f32) -> Analysis {
+    let form = Form::new(110, Key::new(C, Quality::Major).sharp(), vec![bar(C.maj())]);
+
+    let mut notes = vec![PlayedNote::Silence; 8];
+    notes.extend([C, E, D, G].map(|note| PlayedNote::Surely(note.at_octave(2))));
+    let mut transcription = Transcription::new(notes);
+    transcription.confidence = vec![
+        Confidence {
+            score: 1.,
+            candidates: vec![],
+        };
+        12
+    ];
+    transcription.confidence[10] = Confidence {
+        score: 1. - likelihood_of_e,
+        candidates: vec![
+            Candidate {
+                pitch: D.at_octave(2),
+                likelihood: 1. - likelihood_of_e,
+            },
+            Candidate {
+                pitch: E.at_octave(2),
+                likelihood: likelihood_of_e,
+            },
+        ],
+    };
+
+    Analysis::analyze(transcription, &form)
+}
+
+#[test]
+fn test_benefit_of_the_doubt() {
+    let analysis = unsure_d(0.4);
+    assert!(ChordTones {}.correct(&analysis).mistakes.is_empty());
+
+    let mut written = WrittenLine {
+        notes: [C, E, E, G].map(|note| Some(note.at_octave(2))).to_vec(),
+    };
+    assert!(written.correct(&analysis).mistakes.is_empty());
+}
+
+#[test]
+fn test_no_benefit_of_the_doubt_for_unlikely_notes() {
+    let analysis = unsure_d(0.1);
+    let correction = ChordTones {}.correct(&analysis);
+
+    assert_eq!(correction.mistakes.len(), 1);
+    assert!(matches!(
+        correction.mistakes[&10].mistake,
+        MistakeKind::ExpectedChordTone { .. }
+    ));
+}
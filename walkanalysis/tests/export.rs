@@ -0,0 +1,94 @@
+use walkanalysis::{
+    analysis::{analysis::Analysis, correction::Correction},
+    exercise::{chord_tones::ChordTones, written_line::WrittenLine, Exercise},
+    export::{lilypond::to_lilypond, musicxml::to_musicxml},
+    form::{
+        form::{bar, Form, FormPiece},
+        key::{Key, Quality},
+        meter::Meter,
+        musicxml::MusicXmlScore,
+        note::{C, D, F},
+    },
+    transcribe::transcribe::{PlayedNote, Transcription},
+};
+
+/// The lead sheet played with a D instead of the A in the last bar
+fn corrected_take() -> (MusicXmlScore, Analysis, Correction) {
+    let score = MusicXmlScore::open("tests/data/musicxml/lead_sheet.musicxml").unwrap();
+    let line = score.bass_line.clone().unwrap();
+
+    let mut notes = vec![PlayedNote::Silence; 8];
+    notes.extend(line.iter().map(|pitch| match pitch {
+        Some(pitch) => PlayedNote::Surely(*pitch),
+        None => PlayedNote::Silence,
+    }));
+    notes[8 + 12] = PlayedNote::Surely(D.at_octave(2));
+
+    let analysis = Analysis::analyze(Transcription::new(notes), &score.form);
+    let correction = WrittenLine { notes: line }.correct(&analysis);
+    (score, analysis, correction)
+}
+
+#[test]
+fn test_musicxml_export() {
+    let (score, analysis, correction) = corrected_take();
+    let xml = to_musicxml("Lead Sheet", &score.form, &analysis, &correction);
+
+    assert_eq!(xml.matches("<measure ").count(), 4);
+    assert_eq!(xml.matches("<note color=\"#D32F2F\">").count(), 1);
+    assert!(xml.contains("<lyric><text>A?</text></lyric>"));
+
+    // Reading the export back gives the same chords and, with the mistake, the played line
+    let exported = MusicXmlScore::parse(&xml).unwrap();
+    assert_eq!(exported.form, score.form);
+    let played: Vec<_> = analysis
+        .form_analysis
+        .iter()
+        .skip(1)
+        .flat_map(|(_, notes)| notes)
+        .map(|note| note.pitch())
+        .collect();
+    assert_eq!(exported.bass_line.unwrap(), played);
+}
+
+#[test]
+fn test_musicxml_export_meter_change() {
+    let form = Form::new(
+        120,
+        Key::new(C, Quality::Major).sharp(),
+        vec![
+            bar(C.maj7()),
+            FormPiece::Meter(Meter::new(6, 8).unwrap()),
+            bar(F.maj7()),
+            FormPiece::Meter(Meter::new(4, 4).unwrap()),
+            bar(C.maj7()),
+            FormPiece::LineBreak,
+        ],
+    );
+    let analysis = Analysis::analyze(Transcription::new(vec![PlayedNote::Silence; 22]), &form);
+    let correction = ChordTones {}.correct(&analysis);
+    let xml = to_musicxml("Meter Change", &form, &analysis, &correction);
+
+    // Eighth note beats need two divisions per quarter note, from the bar they start in
+    let divisions: Vec<_> = xml
+        .match_indices("<divisions>")
+        .map(|(i, _)| &xml[i..i + 13])
+        .collect();
+    assert_eq!(
+        divisions,
+        ["<divisions>1<", "<divisions>2<", "<divisions>1<"]
+    );
+    assert_eq!(MusicXmlScore::parse(&xml).unwrap().form, form);
+}
+
+#[test]
+fn test_lilypond_export() {
+    let (score, analysis, correction) = corrected_take();
+    let ly = to_lilypond("Lead Sheet", &score.form, &analysis, &correction);
+
+    assert!(ly.contains("\\clef \"bass_8\""));
+    assert!(ly.contains("\\key bf \\major \\time 4/4 \\tempo 4 = 132"));
+    assert!(ly.contains("c4^\\markup { \"Cm7\" } ef4 g4 bf,4 |"));
+    assert!(ly.contains("\\tweak color #(rgb-color 0.827 0.184 0.184) d,4^\\markup { \"Am7b5\" }_\\markup { \\with-color #(rgb-color 0.827 0.184 0.184) \"A?\" }"));
+    assert_eq!(ly.matches(" |\n").count(), 4);
+}
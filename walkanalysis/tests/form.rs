@@ -1,7 +1,10 @@
 use std::{fs::File, io::Write};
 
 use walkanalysis::form::{
-    form::Form,
+    form::{bar, split_bar, Form, FormPiece},
+    key::{Key, Quality},
+    meter::{BeatPosition, Meter, COMMON_TIME, WALTZ},
+    note::{A, C, D, E, F, G},
     songs::{autumn_leaves::autumn_leaves, test::test},
 };
 
@@ -19,8 +22,240 @@ fn test_deserialize_form() {
     assert_eq!(autumn_leaves(), data);
 }
 
+#[test]
+fn test_deserialize_no_choruses() {
+    let json = serde_json::to_string(&autumn_leaves().with_choruses(2)).unwrap();
+    assert!(serde_json::from_str::<Form>(&json).is_ok());
+
+    let json = json.replace("\"choruses\":2", "\"choruses\":0");
+    assert!(serde_json::from_str::<Form>(&json).is_err());
+}
+
+#[test]
+fn test_meter_without_beats() {
+    assert_eq!(Meter::new(0, 4), None);
+    assert_eq!(Meter::new(4, 0), None);
+
+    let form = autumn_leaves().with_meter(WALTZ);
+    let json = serde_json::to_string(&form).unwrap();
+    assert_eq!(serde_json::from_str::<Form>(&json).unwrap(), form);
+
+    for (valid, invalid) in [
+        ("\"beats_per_bar\":3", "\"beats_per_bar\":0"),
+        ("\"beat_unit\":4", "\"beat_unit\":0"),
+    ] {
+        let json = json.replacen(valid, invalid, 1);
+        assert!(serde_json::from_str::<Form>(&json).is_err(), "{}", json);
+    }
+}
+
 #[test]
 fn test_form_lengths() {
     assert_eq!(autumn_leaves().length_in_beats(), 136);
     assert_eq!(test().length_in_beats(), 16);
 }
+
+#[test]
+fn test_meter_changes() {
+    let form = Form::new(
+        120,
+        Key::new(C, Quality::Major).sharp(),
+        vec![
+            bar(C.maj7()),
+            FormPiece::Meter(Meter::new(5, 4).unwrap()),
+            bar(F.maj7()),
+            FormPiece::Meter(COMMON_TIME),
+            bar(G.dominant7()),
+        ],
+    )
+    .with_meter(WALTZ);
+
+    // Count off in 3/4, then 3 + 5 + 4 beats
+    assert_eq!(form.length_in_beats(), 6 + 3 + 5 + 4);
+
+    let positions = form.beat_positions();
+    assert_eq!(
+        positions[6],
+        BeatPosition {
+            chorus: 0,
+            bar: 2,
+            beat_in_bar: 0,
+            meter: WALTZ
+        }
+    );
+    assert_eq!(positions[13].to_string(), "4.5");
+    assert_eq!(positions[14].to_string(), "5.1");
+    assert_eq!(positions[14].meter, COMMON_TIME);
+}
+
+#[test]
+fn test_split_bar() {
+    let piece = split_bar(vec![(A.min7(), 3), (D.dominant7(), 1)]);
+    assert_eq!(piece.length_in_beats(COMMON_TIME), 4);
+
+    let chords: Vec<_> = (0..4)
+        .map(|beat| piece.chord_at(beat, COMMON_TIME).unwrap().clone())
+        .collect();
+    assert_eq!(chords, vec![A.min7(), A.min7(), A.min7(), D.dominant7()]);
+
+    // A bar that is not filled holds the last chord
+    let piece = split_bar(vec![(C.maj7(), 1), (A.min7(), 1)]);
+    assert_eq!(piece.chord_at(3, COMMON_TIME), Some(&A.min7()));
+    assert_eq!(piece.chords(), vec![&C.maj7(), &A.min7()]);
+}
+
+#[test]
+fn test_deserialize_invalid_split_bar() {
+    let form = Form::new(
+        120,
+        Key::new(C, Quality::Major).sharp(),
+        vec![split_bar(vec![(A.min7(), 3), (D.dominant7(), 1)])],
+    );
+    let json = serde_json::to_string(&form).unwrap();
+    assert_eq!(serde_json::from_str::<Form>(&json).unwrap(), form);
+
+    // A chord held for no beats, and chords that don't fit in the bar
+    for beats in ["},0]", "},2]"] {
+        let json = json.replacen("},1]", beats, 1);
+        assert!(serde_json::from_str::<Form>(&json).is_err(), "{}", json);
+    }
+}
+
+fn unrolled_chords(form: &Form) -> Vec<String> {
+    form.unrolled()
+        .music()
+        .iter()
+        .flat_map(|piece| piece.chords())
+        .map(|chord| chord.flat_symbol())
+        .collect()
+}
+
+fn c_major(music: Vec<FormPiece>) -> Form {
+    Form::new(120, Key::new(C, Quality::Major).sharp(), music)
+}
+
+#[test]
+fn test_unroll_repeat_with_endings() {
+    let form = c_major(vec![
+        FormPiece::RepeatStart,
+        bar(C.maj7()),
+        FormPiece::Ending(1),
+        bar(G.dominant7()),
+        FormPiece::RepeatEnd(2),
+        FormPiece::Ending(2),
+        bar(F.maj7()),
+    ]);
+
+    assert_eq!(
+        unrolled_chords(&form),
+        vec!["Cmaj7", "G7", "Cmaj7", "Fmaj7"]
+    );
+    assert_eq!(form.unrolled_indices(), vec![0, 2, 4, 2, 7]);
+}
+
+#[test]
+fn test_unroll_three_endings() {
+    let form = c_major(vec![
+        FormPiece::RepeatStart,
+        bar(C.maj7()),
+        FormPiece::Ending(1),
+        bar(D.min7()),
+        FormPiece::RepeatEnd(3),
+        FormPiece::Ending(2),
+        bar(E.min7()),
+        FormPiece::RepeatEnd(3),
+        FormPiece::Ending(3),
+        bar(F.maj7()),
+    ]);
+
+    assert_eq!(
+        unrolled_chords(&form),
+        vec!["Cmaj7", "Dm7", "Cmaj7", "Em7", "Cmaj7", "Fmaj7"]
+    );
+}
+
+#[test]
+fn test_unroll_consecutive_repeats() {
+    let form = c_major(vec![
+        FormPiece::RepeatStart,
+        bar(C.maj7()),
+        FormPiece::Ending(1),
+        bar(G.dominant7()),
+        FormPiece::RepeatEnd(2),
+        FormPiece::Ending(2),
+        bar(F.maj7()),
+        FormPiece::RepeatStart,
+        bar(D.min7()),
+        FormPiece::RepeatEnd(2),
+        bar(E.min7()),
+        FormPiece::RepeatEnd(2),
+    ]);
+
+    // The repeat after the endings is taken, and the one after that goes back to it
+    assert_eq!(
+        unrolled_chords(&form),
+        vec!["Cmaj7", "G7", "Cmaj7", "Fmaj7", "Dm7", "Dm7", "Em7", "Em7"]
+    );
+}
+
+#[test]
+fn test_unroll_dal_segno_al_coda() {
+    let form = c_major(vec![
+        bar(C.maj7()),
+        FormPiece::Segno,
+        bar(D.min7()),
+        FormPiece::ToCoda,
+        bar(G.dominant7()),
+        FormPiece::DalSegno,
+        FormPiece::Coda,
+        bar(F.maj7()),
+    ]);
+
+    assert_eq!(
+        unrolled_chords(&form),
+        vec!["Cmaj7", "Dm7", "G7", "Dm7", "Fmaj7"]
+    );
+}
+
+#[test]
+fn test_unroll_da_capo_al_fine() {
+    let form = c_major(vec![
+        FormPiece::RepeatStart,
+        bar(C.maj7()),
+        FormPiece::RepeatEnd(3),
+        FormPiece::Fine,
+        bar(G.dominant7()),
+        FormPiece::DaCapo,
+    ]);
+
+    // Repeats are not taken after the D.C.
+    assert_eq!(
+        unrolled_chords(&form),
+        vec!["Cmaj7", "Cmaj7", "Cmaj7", "G7", "Cmaj7"]
+    );
+}
+
+#[test]
+fn test_unroll_choruses() {
+    let form = c_major(vec![bar(C.maj7()), bar(G.dominant7())]).with_choruses(3);
+
+    assert_eq!(form.unrolled_indices(), vec![0, 1, 2, 1, 2, 1, 2]);
+    assert_eq!(form.length_in_beats(), 8 + 3 * 8);
+    assert_eq!(
+        form.unrolled()
+            .music()
+            .iter()
+            .filter(|piece| **piece == FormPiece::CountOff)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_autumn_leaves_repeat() {
+    let form = autumn_leaves();
+    let unrolled = form.unrolled();
+    assert!(unrolled.music().iter().all(|piece| !piece.is_marker()));
+    assert_eq!(unrolled.music()[1..10], unrolled.music()[10..19]);
+    assert_eq!(unrolled.length_in_beats(), form.length_in_beats());
+}
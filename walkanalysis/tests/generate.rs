@@ -0,0 +1,159 @@
+use test_case::test_case;
+use walkanalysis::{
+    analysis::analysis::Analysis,
+    exercise::{
+        arpeggios_up::ArpeggiosUp,
+        chord_tones::ChordTones,
+        skips::Skips,
+        two_beat::{two_beat, two_beat_fifths, two_beat_thirds},
+        written_line::WrittenLine,
+        Exercise,
+    },
+    form::{
+        form::{bar, half_bar, split_bar, Form},
+        key::{Key, Quality},
+        meter::WALTZ,
+        note::*,
+        songs::{autumn_leaves::autumn_leaves, but_beautiful::but_beautiful, test::longer_test},
+    },
+    midi::exercise_to_midi,
+    transcribe::transcribe::{PlayedNote, Transcription},
+};
+
+fn exercises() -> Vec<Box<dyn Exercise>> {
+    vec![
+        Box::new(ArpeggiosUp {}),
+        Box::new(ChordTones {}),
+        Box::new(two_beat()),
+        Box::new(two_beat_thirds()),
+        Box::new(two_beat_fifths()),
+        Box::new(Skips {}),
+    ]
+}
+
+fn waltz() -> Form {
+    Form::new(
+        140,
+        Key::new(F, Quality::Major).flat(),
+        vec![
+            bar(G.min7()),
+            split_bar(vec![(C.dominant7(), 2), (C.dominant9(), 1)]),
+            half_bar(F.maj7(), D.dominant7()),
+        ],
+    )
+    .with_meter(WALTZ)
+}
+
+/// Chords without a third or a fifth
+fn suspended() -> Form {
+    Form::new(
+        120,
+        Key::new(C, Quality::Major).flat(),
+        vec![
+            bar("Csus4".parse().unwrap()),
+            half_bar("G7sus4".parse().unwrap(), "Dsus2".parse().unwrap()),
+            bar("C5".parse().unwrap()),
+        ],
+    )
+}
+
+#[test_case(autumn_leaves() ; "autumn leaves form")]
+#[test_case(autumn_leaves().with_choruses(2) ; "two choruses")]
+#[test_case(but_beautiful() ; "but beautiful form")]
+#[test_case(longer_test() ; "longer test form")]
+#[test_case(waltz() ; "waltz form")]
+#[test_case(suspended() ; "suspended chords")]
+fn test_generated_lines_are_correct(form: Form) {
+    for mut exercise in exercises() {
+        let notes = exercise.generate(&form);
+        assert_eq!(notes.len() as u32, form.length_in_beats());
+
+        let transcription =
+            Transcription::new(notes).with_ornaments(exercise.generate_ornaments(&form));
+        let analysis = Analysis::analyze(transcription, &form);
+        let correction = exercise.correct(&analysis);
+        assert!(
+            correction.mistakes.is_empty(),
+            "{}\n{}",
+            exercise.explain(),
+            correction
+        );
+    }
+}
+
+#[test]
+fn test_arpeggios_up() {
+    let form = Form::new(
+        120,
+        Key::new(B_FLAT, Quality::Major).flat(),
+        vec![bar(C.min7()), bar(F.dominant7().with_bass(A))],
+    );
+    let pitch = |p: &str| PlayedNote::Surely(p.parse().unwrap());
+
+    let mut expected = vec![PlayedNote::Silence; 8];
+    expected.extend([
+        pitch("C2"),
+        pitch("Eb2"),
+        pitch("G2"),
+        pitch("Bb2"),
+        // The bass of the slash chord, then up from there
+        pitch("A1"),
+        pitch("A2"),
+        pitch("C3"),
+        pitch("Eb3"),
+    ]);
+    assert_eq!(ArpeggiosUp {}.generate(&form), expected);
+}
+
+#[test]
+fn test_arpeggios_up_without_third_or_fifth() {
+    let form = Form::new(
+        120,
+        Key::new(C, Quality::Major).flat(),
+        vec![bar("C7sus4".parse().unwrap()), bar("C5".parse().unwrap())],
+    );
+    let mut exercise = ArpeggiosUp {};
+
+    let notes = exercise.generate(&form);
+    let analysis = Analysis::analyze(Transcription::new(notes), &form);
+    assert!(exercise.correct(&analysis).mistakes.is_empty());
+}
+
+#[test]
+fn test_two_beat_holds_notes() {
+    let form = Form::new(120, Key::new(C, Quality::Major).flat(), vec![bar(C.maj7())]);
+    let pitch = |p: &str| PlayedNote::Surely(p.parse().unwrap());
+
+    let line = two_beat_fifths().generate(&form);
+    assert_eq!(
+        line[8..],
+        [pitch("C2"), pitch("C2"), pitch("G2"), pitch("G2")]
+    );
+}
+
+#[test]
+fn test_written_line_repeats_every_chorus() {
+    let form = Form::new(
+        120,
+        Key::new(C, Quality::Major).flat(),
+        vec![half_bar(C.maj7(), A.min7())],
+    )
+    .with_choruses(2);
+    let c2 = C.at_octave(2);
+    let a1 = A.at_octave(1);
+    let mut exercise = WrittenLine {
+        notes: vec![Some(c2), None, Some(a1), None],
+    };
+
+    let notes = exercise.generate(&form);
+    let held = [c2, c2, a1, a1].map(PlayedNote::Surely);
+    assert_eq!(notes[8..12], held);
+    assert_eq!(notes[12..16], held);
+
+    // Also what a MIDI file of the reference line plays
+    let midi = exercise_to_midi(&form, &exercise);
+    assert_eq!(midi.tracks.len(), 4);
+
+    let analysis = Analysis::analyze(Transcription::new(notes), &form);
+    assert!(exercise.correct(&analysis).mistakes.is_empty());
+}
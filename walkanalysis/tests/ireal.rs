@@ -0,0 +1,174 @@
+use walkanalysis::form::{
+    chord::Chord,
+    form::{bar, half_bar, split_bar, Form, FormPiece},
+    ireal::{IRealError, IRealPlaylist},
+    key::{Key, Quality},
+    meter::WALTZ,
+    note::{A, B_FLAT, C, D, E_FLAT, F, G},
+    songs::autumn_leaves::autumn_leaves,
+};
+
+/// Scrambles music the way iReal Pro does, which is the same operation as unscrambling it
+fn scramble(music: &str) -> String {
+    let mut rest: Vec<char> = music.chars().collect();
+    let mut result = String::from("1r34LbKcu7");
+
+    while rest.len() > 51 {
+        let mut block: Vec<char> = rest.drain(..50).collect();
+        for i in (0..5).chain(10..24) {
+            block.swap(i, 49 - i);
+        }
+        result.extend(block);
+    }
+    result.extend(rest);
+    result
+}
+
+fn irealb_song(title: &str, key: &str, music: &str, tempo: u32) -> String {
+    format!(
+        "{}=Kosma Joseph==Medium Swing={}=={}=Jazz-Medium Swing={}=0",
+        title,
+        key,
+        scramble(music),
+        tempo
+    )
+}
+
+fn irealb_url(songs: &[String]) -> String {
+    format!("irealb://{}", urlencoding::encode(&songs.join("===")))
+}
+
+#[test]
+fn test_autumn_leaves() {
+    let music = "*A{T44C-7 |F7 |Bb^7 |Eb^7 |Ah7 |D7 |G- |G- }\
+                 *B[Ah7 |D7 |G- |G- |C-7 |F7 |Bb^7 |Bb^7 |\
+                 Ah7 |D7 |G-7 Gb7 |F-7 E7 |Eb-7 |Ah7 D7 |G-7 Kcl Z";
+    let url = irealb_url(&[irealb_song("Autumn Leaves", "G-", music, 110)]);
+
+    let playlist = IRealPlaylist::from_url(&url).unwrap();
+    assert_eq!(playlist.name, None);
+    assert_eq!(playlist.songs.len(), 1);
+
+    let song = &playlist.songs[0];
+    assert_eq!(song.title, "Autumn Leaves");
+    assert_eq!(song.composer, "Kosma Joseph");
+    assert_eq!(song.style, "Medium Swing");
+    assert_eq!(song.form, autumn_leaves());
+}
+
+#[test]
+fn test_playlist() {
+    let songs = [
+        irealb_song("One", "C", "[T44C^7LZD-7 G7 Z", 0),
+        irealb_song("Two", "Eb", "[T34Eb^7 |F-7 Bb7 Z", 180),
+    ];
+    let url = irealb_url(&[songs[0].clone(), songs[1].clone(), "Standards".into()]);
+
+    let playlist = IRealPlaylist::from_url(&url).unwrap();
+    assert_eq!(playlist.name, Some("Standards".into()));
+
+    let titles: Vec<_> = playlist
+        .songs
+        .iter()
+        .map(|song| song.title.as_str())
+        .collect();
+    assert_eq!(titles, ["One", "Two"]);
+
+    let two = &playlist.songs[1].form;
+    assert_eq!(two.tempo(), 180);
+    assert_eq!(two.meter(), WALTZ);
+    assert_eq!(two.key(), Key::new(E_FLAT, Quality::Major).flat());
+    // Songs without a tempo get a default one
+    assert_eq!(playlist.songs[0].form.tempo(), 120);
+}
+
+#[test]
+fn test_endings_jumps_and_rhythm() {
+    let music = "S{T44C^7 D-7 G7 |<3x>A-7 p |N1D7 }N2D-7 G7 Q|<Fine>C^6 Z\
+                 <D.S. al Coda>F^7 |QC^7 Z";
+    let url = format!(
+        "irealbook://{}",
+        urlencoding::encode(&format!("Jumps=Composer=Swing=C=n={}", music))
+    );
+    let form = IRealPlaylist::from_url(&url).unwrap().songs[0].form.clone();
+
+    let expected = Form::new(
+        120,
+        Key::new(C, Quality::Major).sharp(),
+        vec![
+            FormPiece::Segno,
+            FormPiece::RepeatStart,
+            split_bar(vec![(C.maj7(), 2), (D.min7(), 1), (G.dominant7(), 1)]),
+            half_bar(A.min7(), A.min7()),
+            FormPiece::Ending(1),
+            bar(D.dominant7()),
+            FormPiece::RepeatEnd(3),
+            FormPiece::Ending(2),
+            half_bar(D.min7(), G.dominant7()),
+            FormPiece::ToCoda,
+            FormPiece::LineBreak,
+            bar("C6".parse::<Chord>().unwrap()),
+            FormPiece::Fine,
+            bar(F.maj7()),
+            FormPiece::DalSegno,
+            FormPiece::Coda,
+            bar(C.maj7()),
+            FormPiece::LineBreak,
+        ],
+    );
+    assert_eq!(form, expected);
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        IRealPlaylist::from_url("https://example.com"),
+        Err(IRealError::NotAnIRealLink)
+    );
+
+    let url = irealb_url(&[irealb_song("Wrong", "H", "[C^7 Z", 120)]);
+    assert_eq!(
+        IRealPlaylist::from_url(&url),
+        Err(IRealError::InvalidKey("H".into()))
+    );
+
+    let url = irealb_url(&[irealb_song("No beats", "C", "[T40C^7 Z", 120)]);
+    assert_eq!(
+        IRealPlaylist::from_url(&url),
+        Err(IRealError::InvalidMeter("40".into()))
+    );
+
+    let url = irealb_url(&[irealb_song("No unit", "C", "[T04C^7 Z", 120)]);
+    assert_eq!(
+        IRealPlaylist::from_url(&url),
+        Err(IRealError::InvalidMeter("04".into()))
+    );
+
+    let url = irealb_url(&[irealb_song("Break", "C", "[C^7 |n Z", 120)]);
+    assert_eq!(IRealPlaylist::from_url(&url), Err(IRealError::NoChord));
+
+    let url = irealb_url(&["Short=Composer".into()]);
+    assert_eq!(
+        IRealPlaylist::from_url(&url),
+        Err(IRealError::MissingField("music"))
+    );
+}
+
+#[test]
+fn test_long_music_is_unscrambled() {
+    // Long enough to be scrambled in several blocks
+    let bars = ["Bb^7", "G7", "C-7", "F7"].repeat(8).join(" |");
+    let music = format!("[T44{} Z", bars);
+    let url = irealb_url(&[irealb_song("Long", "Bb", &music, 120)]);
+
+    let form = &IRealPlaylist::from_url(&url).unwrap().songs[0].form;
+    let chords: Vec<_> = form
+        .music()
+        .iter()
+        .flat_map(|piece| piece.chords())
+        .cloned()
+        .collect();
+    assert_eq!(chords.len(), 32);
+    assert_eq!(chords[0], B_FLAT.maj7());
+    assert_eq!(chords[31], F.dominant7());
+}
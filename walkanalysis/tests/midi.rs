@@ -0,0 +1,158 @@
+use walkanalysis::{
+    analysis::analysis::Analysis,
+    exercise::{skips::Skips, Exercise},
+    form::{
+        form::{bar, half_bar, Form},
+        key::{Key, Quality},
+        meter::Meter,
+        note::{C, D, G},
+        pitch::Pitch,
+    },
+    midi::{exercise_to_midi, smf::Track, to_midi, voicing},
+    transcribe::transcribe::{PlayedNote, Transcription},
+};
+
+fn form() -> Form {
+    Form::new(
+        120,
+        Key::new(C, Quality::Major).flat(),
+        vec![bar(C.maj7()), half_bar(D.min7(), G.dominant7())],
+    )
+}
+
+/// Every note on in the track as its tick and key
+fn note_ons(track: &Track) -> Vec<(u32, u8)> {
+    track
+        .events
+        .iter()
+        .filter(|event| event.message[0] & 0xF0 == 0x90)
+        .map(|event| (event.tick, event.message[1]))
+        .collect()
+}
+
+#[test]
+fn test_voicing() {
+    let pitch = |p: &str| p.parse::<Pitch>().unwrap();
+    assert_eq!(
+        voicing(&C.maj7()),
+        vec![pitch("G3"), pitch("B3"), pitch("E4")]
+    );
+    assert_eq!(
+        voicing(&G.dominant7()),
+        vec![pitch("B3"), pitch("D4"), pitch("F4")]
+    );
+    // Triads keep their root
+    assert_eq!(
+        voicing(&D.min()),
+        vec![pitch("A3"), pitch("D4"), pitch("F4")]
+    );
+}
+
+#[test]
+fn test_midi_file() {
+    let smf = to_midi(&form(), None);
+    let bytes = smf.to_bytes();
+
+    assert_eq!(&bytes[0..4], b"MThd");
+    // Format 1, three tracks and 480 ticks per quarter note
+    assert_eq!(&bytes[8..14], &[0, 1, 0, 3, 0x01, 0xE0]);
+    assert_eq!(bytes.windows(4).filter(|w| w == b"MTrk").count(), 3);
+    // 500000 microseconds per quarter note is 120 beats per minute
+    assert!(bytes
+        .windows(6)
+        .any(|w| w == [0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20]));
+}
+
+#[test]
+fn test_comping_and_count_off() {
+    let smf = to_midi(&form(), None);
+    let [_, comping, count_off] = &smf.tracks[..] else {
+        panic!("Expected three tracks")
+    };
+
+    let clicks = note_ons(count_off);
+    assert_eq!(clicks.len(), 8);
+    assert_eq!(clicks[0], (0, 76));
+    assert_eq!(clicks[1], (480, 77));
+    assert_eq!(clicks[4], (4 * 480, 76));
+
+    let chords = note_ons(comping);
+    let starts: Vec<u32> = chords.iter().map(|(tick, _)| tick / 480).collect();
+    assert_eq!(starts, vec![8, 8, 8, 12, 12, 12, 14, 14, 14]);
+    // Dm7 without its root
+    assert_eq!(chords[3], (12 * 480, 57));
+}
+
+#[test]
+fn test_bass_track() {
+    let pitch = |p: &str| PlayedNote::Surely(p.parse().unwrap());
+    let mut line = vec![PlayedNote::Silence; 8];
+    line.extend([
+        pitch("C2"),
+        pitch("E2"),
+        pitch("G2"),
+        pitch("E2"),
+        pitch("D2"),
+        PlayedNote::Unknown,
+        pitch("G2"),
+        PlayedNote::Silence,
+    ]);
+
+    let smf = to_midi(&form(), Some(&line));
+    assert_eq!(smf.tracks.len(), 4);
+    let notes = note_ons(&smf.tracks[3]);
+    assert_eq!(
+        notes,
+        vec![
+            (8 * 480, 36),
+            (9 * 480, 40),
+            (10 * 480, 43),
+            (11 * 480, 40),
+            (12 * 480, 38),
+            (14 * 480, 43),
+        ]
+    );
+}
+
+#[test]
+fn test_meter_of_the_beat() {
+    // In 6/8 a beat is an eighth note
+    let smf = to_midi(&form().with_meter(Meter::new(6, 8).unwrap()), None);
+    let clicks = note_ons(&smf.tracks[2]);
+    assert_eq!(clicks.len(), 12);
+    assert_eq!(clicks[1].0, 240);
+    assert_eq!(clicks[6], (6 * 240, 76));
+
+    // 120 eighths per minute is a quarter note per second
+    let bytes = smf.to_bytes();
+    assert!(bytes
+        .windows(6)
+        .any(|w| w == [0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40]));
+    assert!(bytes
+        .windows(7)
+        .any(|w| w == [0xFF, 0x58, 0x04, 6, 3, 24, 8]));
+}
+
+#[test]
+fn test_exercise_to_midi() {
+    let mut exercise = Skips {};
+    let smf = exercise_to_midi(&form(), &exercise);
+    assert_eq!(smf.tracks.len(), 4);
+
+    // A note on every beat of the form and a skip into every chord change, which repeats
+    // the note before it two thirds into the beat
+    let notes = note_ons(&smf.tracks[3]);
+    assert_eq!(notes.len(), 8 + 2);
+    assert_eq!(notes[4], (11 * 480 + 320, notes[3].1));
+    assert_eq!(notes[7], (13 * 480 + 320, notes[6].1));
+
+    // Reading the bass back gives the reference line with its skips, which scores 100%
+    let (transcription, _) = Transcription::transcribe_midi(&smf, 120., Some(1));
+    assert_eq!(transcription.notes, exercise.generate(&form()));
+    assert_eq!(
+        transcription.ornaments,
+        exercise.generate_ornaments(&form())
+    );
+    let analysis = Analysis::analyze(transcription, &form());
+    assert!(exercise.correct(&analysis).mistakes.is_empty());
+}
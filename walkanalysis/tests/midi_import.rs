@@ -0,0 +1,241 @@
+use walkanalysis::{
+    analysis::analysis::Analysis,
+    exercise::{chord_tones::ChordTones, two_beat::two_beat_thirds, Exercise},
+    form::{
+        form::{bar, half_bar, Form},
+        key::{Key, Quality},
+        note::{C, D, F, G},
+    },
+    midi::{
+        smf::{Smf, SmfError},
+        to_midi,
+    },
+    transcribe::{
+        midi::MidiEvent,
+        transcribe::{PlayedNote, Subdivision, Transcription},
+    },
+};
+
+fn pitch(p: &str) -> PlayedNote {
+    PlayedNote::Surely(p.parse().unwrap())
+}
+
+/// A file with a tempo change halfway, running status and notes that are a bit early or late
+fn take() -> Vec<u8> {
+    #[rustfmt::skip]
+    let track = [
+        // 60 beats per minute
+        0x00, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40,
+        0x00, 0x90, 28, 100,
+        // Six ticks early for the second beat, the note off is a note on without velocity
+        0x5A, 28, 0, 0x00, 33, 100,
+        // Eight ticks late for the third beat, with a higher note at the same time
+        0x6E, 33, 0, 0x00, 38, 100, 0x00, 45, 100,
+        // Twice as fast from the fourth beat on
+        0x58, 38, 0, 0x00, 45, 0, 0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20,
+        0x81, 0x40, 0x90, 43, 80,
+        0x60, 0x80, 43, 0,
+        0x00, 0xFF, 0x2F, 0x00,
+    ];
+
+    let mut bytes = b"MThd".to_vec();
+    bytes.extend([0, 0, 0, 6, 0, 0, 0, 1, 0, 96]);
+    bytes.extend(b"MTrk");
+    bytes.extend((track.len() as u32).to_be_bytes());
+    bytes.extend(track);
+    bytes
+}
+
+#[test]
+fn test_quantize_midi_take() {
+    let smf = Smf::parse(&take()).unwrap();
+    let (transcription, notes) = Transcription::transcribe_midi(&smf, 60., None);
+
+    assert_eq!(
+        transcription.notes,
+        vec![
+            pitch("E1"),
+            pitch("A1"),
+            pitch("D2"),
+            PlayedNote::Silence,
+            pitch("G2")
+        ]
+    );
+
+    assert_eq!(notes.len(), 4);
+    assert_eq!(notes[1].beat, 1);
+    assert_eq!(notes[1].offset, -0.0625);
+    assert!((notes[2].offset - 1. / 12.).abs() < 1e-6);
+    assert_eq!(notes[3].beat, 4);
+    assert_eq!(notes[3].offset, 0.);
+    assert_eq!(notes[3].velocity, 80);
+}
+
+#[test]
+fn test_quantize_live_notes() {
+    let on = |position, p: &str| MidiEvent::NoteOn {
+        position,
+        pitch: p.parse().unwrap(),
+        velocity: 90,
+    };
+    let off = |position, p: &str| MidiEvent::NoteOff {
+        position,
+        pitch: p.parse().unwrap(),
+    };
+
+    // A ghost note just before the second beat is taken over by the lower note on it,
+    // and a late note is held over the next beat
+    let events = [
+        on(0.04, "A1"),
+        off(0.9, "A1"),
+        on(0.92, "G2"),
+        on(1.02, "C2"),
+        off(1.1, "G2"),
+        off(1.8, "C2"),
+        on(3.45, "F1"),
+        off(4.6, "F1"),
+    ];
+    let (transcription, notes) = Transcription::transcribe_midi_events(events);
+
+    assert_eq!(
+        transcription.notes,
+        vec![
+            pitch("A1"),
+            pitch("C2"),
+            PlayedNote::Silence,
+            pitch("F1"),
+            pitch("F1")
+        ]
+    );
+    assert!((notes[1].offset - 0.02).abs() < 1e-6);
+    assert!((notes[2].offset - 0.45).abs() < 1e-6);
+}
+
+#[test]
+fn test_held_notes() {
+    let form = Form::new(
+        120,
+        Key::new(C, Quality::Major).flat(),
+        vec![bar(C.maj7()), bar(F.maj7())],
+    );
+
+    // Half notes, let go just before the next one
+    let events = [(8., "C2"), (10., "E2"), (12., "F2"), (14., "A2")]
+        .into_iter()
+        .flat_map(|(position, p)| {
+            let pitch = p.parse().unwrap();
+            [
+                MidiEvent::NoteOn {
+                    position,
+                    pitch,
+                    velocity: 90,
+                },
+                MidiEvent::NoteOff {
+                    position: position + 1.9,
+                    pitch,
+                },
+            ]
+        });
+    let (transcription, notes) = Transcription::transcribe_midi_events(events);
+    assert_eq!(notes.len(), 4);
+    assert_eq!(
+        transcription.notes[8..],
+        ["C2", "C2", "E2", "E2", "F2", "F2", "A2", "A2"].map(pitch)
+    );
+
+    let analysis = Analysis::analyze(transcription, &form);
+    let correction = two_beat_thirds().correct(&analysis);
+    assert_eq!(correction.score(), 1., "{}", correction);
+}
+
+#[test]
+fn test_notes_between_beats() {
+    let on = |position, p: &str| MidiEvent::NoteOn {
+        position,
+        pitch: p.parse().unwrap(),
+        velocity: 90,
+    };
+
+    // A skip into the second beat, a triplet on the third and a lone eighth note
+    let events = [
+        on(0., "A1"),
+        on(0.68, "A1"),
+        on(1.01, "C2"),
+        on(2., "E2"),
+        on(2.32, "D2"),
+        on(2.65, "C2"),
+        on(3., "B1"),
+        on(3.48, "G1"),
+        on(5., "A1"),
+    ];
+    let (transcription, _) = Transcription::transcribe_midi_events(events);
+
+    // The lone eighth note is nearest the fifth beat, but the note on it wins
+    assert_eq!(
+        transcription.notes,
+        vec![
+            pitch("A1"),
+            pitch("C2"),
+            pitch("E2"),
+            pitch("B1"),
+            PlayedNote::Silence,
+            pitch("A1")
+        ]
+    );
+    let ornaments: Vec<_> = transcription
+        .ornaments
+        .iter()
+        .map(|ornament| (ornament.beat, ornament.subdivision, ornament.note))
+        .collect();
+    assert_eq!(
+        ornaments,
+        vec![
+            (0, Subdivision::SecondTriplet, pitch("A1")),
+            (2, Subdivision::FirstTriplet, pitch("D2")),
+            (2, Subdivision::SecondTriplet, pitch("C2")),
+            (3, Subdivision::Eighth, pitch("G1")),
+        ]
+    );
+}
+
+#[test]
+fn test_midi_round_trip() {
+    let form = Form::new(
+        120,
+        Key::new(C, Quality::Major).flat(),
+        vec![bar(C.maj7()), half_bar(D.min7(), G.dominant7())],
+    );
+    let mut line = vec![PlayedNote::Silence; 8];
+    line.extend([
+        pitch("C2"),
+        pitch("E2"),
+        pitch("G2"),
+        pitch("B2"),
+        pitch("D2"),
+        pitch("F2"),
+        pitch("G2"),
+        PlayedNote::Unknown,
+    ]);
+
+    let bytes = to_midi(&form, Some(&line)).to_bytes();
+    let smf = Smf::parse(&bytes).unwrap();
+    assert_eq!(smf.to_bytes(), bytes);
+
+    // Only the bass channel, the comping is on another channel
+    let (transcription, _) = Transcription::transcribe_midi(&smf, 120., Some(1));
+    assert_eq!(transcription.notes[..], line[..15]);
+
+    let analysis = Analysis::analyze(transcription, &form);
+    let correction = ChordTones {}.correct(&analysis);
+    assert!(correction.mistakes.is_empty());
+}
+
+#[test]
+fn test_invalid_midi() {
+    assert_eq!(Smf::parse(b"RIFF\0\0\0\0WAVE"), Err(SmfError::NotAMidiFile));
+    assert_eq!(Smf::parse(&take()[..30]), Err(SmfError::UnexpectedEnd));
+
+    let mut smpte = take();
+    smpte[12] = 0xE7;
+    assert_eq!(Smf::parse(&smpte), Err(SmfError::UnsupportedTiming));
+}
@@ -0,0 +1,144 @@
+use walkanalysis::{
+    analysis::{analysis::Analysis, mistake::MistakeKind},
+    exercise::{written_line::WrittenLine, Exercise},
+    form::{
+        chord::Chord,
+        form::{bar, half_bar, split_bar, Form, FormPiece},
+        key::{Key, Quality},
+        musicxml::{MusicXmlError, MusicXmlScore},
+        note::{A, B_FLAT, C, D, E_FLAT, F},
+        pitch::Pitch,
+    },
+    transcribe::transcribe::{PlayedNote, Transcription},
+};
+
+fn lead_sheet() -> MusicXmlScore {
+    MusicXmlScore::open("tests/data/musicxml/lead_sheet.musicxml").unwrap()
+}
+
+#[test]
+fn test_read_harmony() {
+    let score = lead_sheet();
+    assert_eq!(score.title, Some("Lead Sheet".into()));
+
+    let d7b9: Chord = "D7b9".parse().unwrap();
+    let expected = Form::new(
+        132,
+        Key::new(B_FLAT, Quality::Major).flat(),
+        vec![
+            bar(C.min7()),
+            bar(F.dominant7()),
+            half_bar(B_FLAT.maj7(), E_FLAT.maj7()),
+            split_bar(vec![(A.m7b5(), 3), (d7b9, 1)]),
+            FormPiece::LineBreak,
+        ],
+    );
+    assert_eq!(score.form, expected);
+}
+
+#[test]
+fn test_read_bass_line() {
+    let line = lead_sheet().bass_line.unwrap();
+    let pitch = |p: &str| Some(p.parse::<Pitch>().unwrap());
+
+    assert_eq!(
+        line,
+        vec![
+            pitch("C3"),
+            pitch("Eb3"),
+            pitch("G3"),
+            pitch("Bb2"),
+            pitch("F2"),
+            None,
+            pitch("A2"),
+            None,
+            pitch("Bb2"),
+            None,
+            pitch("Eb2"),
+            // Tied, so no new note starts here
+            None,
+            pitch("A2"),
+            None,
+            None,
+            pitch("D2"),
+        ]
+    );
+}
+
+#[test]
+fn test_grade_written_line() {
+    let score = lead_sheet();
+    let line = score.bass_line.clone().unwrap();
+
+    let mut notes = vec![PlayedNote::Silence; 8];
+    notes.extend(line.iter().map(|pitch| match pitch {
+        Some(pitch) => PlayedNote::Surely(*pitch),
+        None => PlayedNote::Silence,
+    }));
+    // Play a D instead of the A in the last bar
+    notes[8 + 12] = PlayedNote::Surely(D.at_octave(2));
+
+    let analysis = Analysis::analyze(Transcription::new(notes), &score.form);
+    let correction = WrittenLine { notes: line }.correct(&analysis);
+
+    assert_eq!(correction.mistakes.len(), 1);
+    let MistakeKind::WrongNote { played, expected } = correction.mistakes[&20].mistake else {
+        panic!("Expected a wrong note")
+    };
+    assert_eq!(played, D);
+    assert_eq!(expected, A);
+}
+
+#[test]
+fn test_invalid_musicxml() {
+    assert!(matches!(
+        MusicXmlScore::parse("<score-partwise>"),
+        Err(MusicXmlError::Xml(_))
+    ));
+    assert_eq!(
+        MusicXmlScore::parse("<score-timewise/>"),
+        Err(MusicXmlError::NotPartwise)
+    );
+    assert_eq!(
+        MusicXmlScore::parse("<score-partwise/>"),
+        Err(MusicXmlError::NoParts)
+    );
+
+    let no_chord = r#"<score-partwise><part id="P1"><measure number="1">
+        <note><rest/><duration>4</duration></note>
+    </measure></part></score-partwise>"#;
+    assert_eq!(
+        MusicXmlScore::parse(no_chord),
+        Err(MusicXmlError::NoChord { measure: 1 })
+    );
+
+    let with_attributes = |attributes: &str| {
+        format!(
+            r#"<score-partwise><part id="P1">
+            <measure number="1"><harmony><root><root-step>C</root-step></root><kind>major</kind></harmony></measure>
+            <measure number="2"><attributes>{}</attributes></measure>
+        </part></score-partwise>"#,
+            attributes
+        )
+    };
+    assert_eq!(
+        MusicXmlScore::parse(&with_attributes("<divisions>0</divisions>")),
+        Err(MusicXmlError::InvalidDivisions {
+            measure: 2,
+            divisions: "0".into()
+        })
+    );
+    for (beats, beat_type) in [("0", "4"), ("4", "0"), ("3+2", "8")] {
+        let time = format!(
+            "<time><beats>{}</beats><beat-type>{}</beat-type></time>",
+            beats, beat_type
+        );
+        assert_eq!(
+            MusicXmlScore::parse(&with_attributes(&time)),
+            Err(MusicXmlError::InvalidTimeSignature {
+                measure: 2,
+                time: format!("{}/{}", beats, beat_type)
+            })
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use walkanalysis::form::note::{Note, F};
+use test_case::test_case;
+use walkanalysis::form::{
+    note::{Note, A, B, B_FLAT, C, E, F},
+    pitch::Pitch,
+};
 
 #[test]
 fn test_notes() {
@@ -30,3 +34,45 @@ fn freq_errors() {
         println!("{freq:.2}Hz => {:?} {}", n.flat(), err)
     }
 }
+
+#[test]
+fn test_pitches() {
+    let low_e = E.at_octave(1);
+    assert_eq!(low_e.midi(), 28);
+    assert_eq!(low_e.note(), E);
+    assert_eq!(low_e.octave(), 1);
+    assert_eq!(A.at_octave(4).frequency(), 440.);
+    assert_eq!(C.at_octave(4).midi(), 60);
+    assert_eq!(B.at_octave(3).add_steps(1), C.at_octave(4));
+    assert_eq!(low_e.steps_to(E.at_octave(2)), 12);
+    assert_ne!(low_e, E.at_octave(2));
+}
+
+#[test]
+fn pitch_from_freq() {
+    let (pitch, error) = Pitch::from_frequency(41.2).unwrap();
+    assert_eq!(pitch, E.at_octave(1));
+    assert!(error.abs() < 0.01);
+
+    assert_eq!(Pitch::from_frequency(43.64).unwrap().0, F.at_octave(1));
+    assert_eq!(Pitch::from_frequency(0.), None);
+}
+
+#[test_case("E1", 28)]
+#[test_case("A2", 45)]
+#[test_case("Bb2", 46)]
+#[test_case("F♯3", 54)]
+#[test_case("Cb4", 59)]
+#[test_case("C-1", 0)]
+fn test_parse_pitch(s: &str, midi: i32) {
+    let pitch: Pitch = s.parse().unwrap();
+    assert_eq!(pitch.midi(), midi);
+}
+
+#[test]
+fn test_display_pitch() {
+    assert_eq!(E.at_octave(1).to_string(), "E1");
+    assert_eq!(B_FLAT.at_octave(2).to_string(), "B♭2");
+    assert!("H2".parse::<Pitch>().is_err());
+    assert!("E".parse::<Pitch>().is_err());
+}
@@ -0,0 +1,117 @@
+use walkanalysis::{
+    exercise::{chord_tones::ChordTones, Exercise},
+    form::{pitch::Pitch, songs::autumn_leaves::autumn_leaves},
+    synth::{bass::BassSynth, mix, samples_per_beat},
+    transcribe::{
+        onset::detect_onsets,
+        transcribe::{AudioSettings, PlayedNote, Transcription, DEFAULT_SETTINGS},
+    },
+};
+
+const TEMPO: f32 = 110.;
+
+/// Renders every note starting `offset` beats from its beat, until the next note starts
+fn render_with_feel(synth: &BassSynth, notes: &[(PlayedNote, f32)]) -> Vec<f32> {
+    let beat = samples_per_beat(TEMPO, synth.sample_rate);
+    let starts: Vec<usize> = notes
+        .iter()
+        .enumerate()
+        .map(|(number, (_, offset))| ((number as f32 + offset) * beat as f32) as usize)
+        .collect();
+
+    let mut samples = vec![0.; (notes.len() + 1) * beat];
+    for (number, (note, _)) in notes.iter().enumerate() {
+        if let PlayedNote::Surely(pitch) = note {
+            let start = starts[number];
+            let end = starts.get(number + 1).copied().unwrap_or(start + beat);
+            let mut sound = vec![0.; end - start];
+            synth.pluck(*pitch, &mut sound);
+            mix(&mut samples, start, &sound, 1.);
+        }
+    }
+    samples
+}
+
+fn transcribe(samples: &[f32], synth: &BassSynth) -> (Transcription, Vec<Option<f32>>) {
+    let audio_settings = AudioSettings {
+        sample_rate: synth.sample_rate,
+    };
+    let (transcription, data) =
+        Transcription::transcribe(samples, TEMPO, DEFAULT_SETTINGS, audio_settings);
+    let offsets = data
+        .onsets()
+        .iter()
+        .map(|onset| onset.map(|onset| onset.offset))
+        .collect();
+    (transcription, offsets)
+}
+
+#[test]
+fn test_onsets_on_the_beat() {
+    let synth = BassSynth::default();
+    let notes = ChordTones {}.generate(&autumn_leaves());
+    let samples = synth.render(&notes, TEMPO);
+    let beat = samples_per_beat(TEMPO, synth.sample_rate);
+
+    let onsets = detect_onsets(
+        &samples,
+        synth.sample_rate,
+        DEFAULT_SETTINGS.onset_threshold,
+    );
+    let played = notes
+        .iter()
+        .filter(|note| matches!(note, PlayedNote::Surely(_)))
+        .count();
+    assert_eq!(onsets.len(), played);
+
+    // Within 15 ms of the beat
+    for onset in onsets {
+        let from_beat = (onset.sample + beat / 2) % beat;
+        assert!(from_beat.abs_diff(beat / 2) < 15 * synth.sample_rate as usize / 1000);
+    }
+}
+
+#[test]
+fn test_early_and_late_notes() {
+    let synth = BassSynth::default();
+    let feel = [0.2, -0.15, 0.1, -0.25, 0.3, 0., -0.1, 0.25];
+    let notes: Vec<(PlayedNote, f32)> = (28..60)
+        .map(|midi| PlayedNote::Surely(Pitch::from_midi(midi)))
+        .zip(feel.iter().copied().cycle())
+        .collect();
+
+    let (transcription, offsets) = transcribe(&render_with_feel(&synth, &notes), &synth);
+
+    for (beat, (note, offset)) in notes.iter().enumerate() {
+        assert_eq!(transcription.notes[beat], *note, "beat {}", beat);
+        let found = offsets[beat].unwrap();
+        assert!((found - offset).abs() < 0.03, "beat {}: {}", beat, found);
+    }
+}
+
+#[test]
+fn test_ghost_notes() {
+    let synth = BassSynth::default();
+    let ghost = BassSynth {
+        decay: 0.05,
+        ..Default::default()
+    };
+    let notes = ChordTones {}.generate(&autumn_leaves());
+    let mut samples = synth.render(&notes, TEMPO);
+
+    // A quiet, short note half way through every other beat
+    let beat = samples_per_beat(TEMPO, synth.sample_rate);
+    for number in (8..notes.len() - 1).step_by(2) {
+        let mut sound = vec![0.; beat / 4];
+        ghost.pluck(Pitch::from_midi(30), &mut sound);
+        mix(&mut samples, number * beat + beat / 2, &sound, 0.5);
+    }
+
+    let (transcription, offsets) = transcribe(&samples, &synth);
+    assert_eq!(transcription.notes, notes);
+    for (offset, note) in offsets.iter().zip(&notes) {
+        if let PlayedNote::Surely(_) = note {
+            assert!(offset.unwrap().abs() < 0.03);
+        }
+    }
+}
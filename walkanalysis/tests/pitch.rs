@@ -0,0 +1,130 @@
+use walkanalysis::{
+    exercise::{chord_tones::ChordTones, Exercise},
+    form::{pitch::Pitch, songs::autumn_leaves::autumn_leaves},
+    synth::bass::BassSynth,
+    transcribe::{
+        pitch::{FftPeaks, HarmonicProductSpectrum, Mcleod, PitchDetector, Yin},
+        transcribe::{AudioSettings, Transcription, TranscriptionSettings, DEFAULT_SETTINGS},
+    },
+};
+
+/// Half a beat of the note at 110 beats per minute, like the transcriber looks at
+fn pluck(synth: &BassSynth, midi: i32) -> Vec<f32> {
+    let mut samples = vec![0.; synth.sample_rate as usize * 3 / 11];
+    synth.pluck(Pitch::from_midi(midi), &mut samples);
+    samples
+}
+
+fn heard(detector: &dyn PitchDetector, synth: &BassSynth, midi: i32) -> Option<Pitch> {
+    let frequency = detector.detect(&pluck(synth, midi), synth.sample_rate)?;
+    let (pitch, error) = Pitch::from_frequency(frequency)?;
+    (error.abs() < 0.25).then_some(pitch)
+}
+
+#[test]
+fn test_detectors_on_four_strings() {
+    let synth = BassSynth::default();
+    let detectors: [&dyn PitchDetector; 4] = [
+        &FftPeaks,
+        &HarmonicProductSpectrum::DEFAULT,
+        &Yin::DEFAULT,
+        &Mcleod::DEFAULT,
+    ];
+    for detector in detectors {
+        for midi in 28..=55 {
+            assert_eq!(heard(detector, &synth, midi), Some(Pitch::from_midi(midi)));
+        }
+    }
+}
+
+#[test]
+fn test_low_b_string() {
+    let synth = BassSynth::default();
+    let detectors: [&dyn PitchDetector; 3] = [
+        &HarmonicProductSpectrum::DEFAULT,
+        &Yin::DEFAULT,
+        &Mcleod::DEFAULT,
+    ];
+    for detector in detectors {
+        for midi in 23..28 {
+            assert_eq!(heard(detector, &synth, midi), Some(Pitch::from_midi(midi)));
+        }
+    }
+}
+
+#[test]
+fn test_weak_fundamental() {
+    let synth = BassSynth {
+        harmonics: vec![0.1, 1.0, 0.8, 0.5, 0.3, 0.2],
+        ..Default::default()
+    };
+    let detectors: [&dyn PitchDetector; 3] = [
+        &HarmonicProductSpectrum::DEFAULT,
+        &Yin::DEFAULT,
+        &Mcleod::DEFAULT,
+    ];
+    for detector in detectors {
+        for midi in 28..=55 {
+            assert_eq!(heard(detector, &synth, midi), Some(Pitch::from_midi(midi)));
+        }
+    }
+}
+
+#[test]
+fn test_strong_third_harmonic() {
+    let synth = BassSynth {
+        harmonics: vec![0.1, 0.3, 1.0, 0.4, 0.2],
+        ..Default::default()
+    };
+    // The lowest strong peak is a fifth above the octave
+    assert_eq!(heard(&FftPeaks, &synth, 28), Some(Pitch::from_midi(47)));
+
+    let detectors: [&dyn PitchDetector; 3] = [
+        &HarmonicProductSpectrum::DEFAULT,
+        &Yin::DEFAULT,
+        &Mcleod::DEFAULT,
+    ];
+    for detector in detectors {
+        for midi in 28..=55 {
+            assert_eq!(heard(detector, &synth, midi), Some(Pitch::from_midi(midi)));
+        }
+    }
+}
+
+#[test]
+fn test_silence() {
+    let samples = vec![0.; 12_000];
+    let detectors: [&dyn PitchDetector; 3] = [
+        &HarmonicProductSpectrum::DEFAULT,
+        &Yin::DEFAULT,
+        &Mcleod::DEFAULT,
+    ];
+    for detector in detectors {
+        assert_eq!(detector.detect(&samples, 44_100), None);
+    }
+}
+
+#[test]
+fn test_transcribe_with_detector() {
+    let synth = BassSynth::default();
+    let notes = ChordTones {}.generate(&autumn_leaves());
+    let samples = synth.render(&notes, 110.);
+
+    let detectors: [&'static dyn PitchDetector; 3] = [
+        &HarmonicProductSpectrum::DEFAULT,
+        &Yin::DEFAULT,
+        &Mcleod::DEFAULT,
+    ];
+    for pitch_detector in detectors {
+        let settings = TranscriptionSettings {
+            pitch_detector,
+            ..DEFAULT_SETTINGS
+        };
+        let audio_settings = AudioSettings {
+            sample_rate: synth.sample_rate,
+        };
+        let (transcription, _) =
+            Transcription::transcribe(&samples, 110., settings, audio_settings);
+        assert_eq!(transcription.notes, notes);
+    }
+}
@@ -0,0 +1,156 @@
+use walkanalysis::{
+    analysis::{
+        analysis::{Analysis, NoteAnalysis},
+        mistake::MistakeKind,
+    },
+    exercise::{skips::Skips, Exercise},
+    form::{
+        chord::ChordTone,
+        form::{bar, Form},
+        key::{Key, Quality},
+        note::*,
+        songs::autumn_leaves::autumn_leaves,
+    },
+    synth::{bass::BassSynth, mix, samples_per_beat},
+    transcribe::transcribe::{
+        AudioSettings, Ornament, PlayedNote, Subdivision, Transcription, DEFAULT_SETTINGS,
+    },
+};
+
+const TEMPO: f32 = 110.;
+
+/// Renders the notes on the beats and between them, every note until the next one starts
+fn render(synth: &BassSynth, notes: &[PlayedNote], ornaments: &[Ornament]) -> Vec<f32> {
+    let beat = samples_per_beat(TEMPO, synth.sample_rate);
+    let mut starts: Vec<(f32, PlayedNote)> = (0..)
+        .zip(notes)
+        .map(|(number, &note)| (number as f32, note))
+        .chain(
+            ornaments
+                .iter()
+                .map(|ornament| (ornament.position(), ornament.note)),
+        )
+        .collect();
+    starts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut samples = vec![0.; notes.len() * beat];
+    for (number, &(position, note)) in starts.iter().enumerate() {
+        let PlayedNote::Surely(pitch) = note else {
+            continue;
+        };
+        let start = (position * beat as f32) as usize;
+        let end = starts
+            .get(number + 1)
+            .map_or(samples.len(), |(next, _)| (next * beat as f32) as usize);
+        let mut sound = vec![0.; end - start];
+        synth.pluck(pitch, &mut sound);
+        mix(&mut samples, start, &sound, 1.);
+    }
+    samples
+}
+
+fn transcribe(samples: &[f32], synth: &BassSynth) -> Transcription {
+    let audio_settings = AudioSettings {
+        sample_rate: synth.sample_rate,
+    };
+    Transcription::transcribe(samples, TEMPO, DEFAULT_SETTINGS, audio_settings).0
+}
+
+#[test]
+fn test_skips() {
+    let synth = BassSynth::default();
+    let form = autumn_leaves();
+    let mut exercise = Skips {};
+    let notes = exercise.generate(&form);
+    let skips = exercise.generate_ornaments(&form);
+
+    let transcription = transcribe(&render(&synth, &notes, &skips), &synth);
+    assert_eq!(transcription.notes, notes);
+    assert_eq!(transcription.ornaments, skips);
+
+    let analysis = Analysis::analyze(transcription, &form);
+    let correction = exercise.correct(&analysis);
+    assert!(correction.mistakes.is_empty(), "{}", correction);
+}
+
+#[test]
+fn test_triplets_and_eighths() {
+    let synth = BassSynth::default();
+    let form = Form::new(
+        110,
+        Key::new(C, Quality::Major).sharp(),
+        vec![bar(C.maj7())],
+    );
+
+    let mut notes = vec![PlayedNote::Silence; 8];
+    notes.extend([C, E, G, B].map(|note| PlayedNote::Surely(note.at_octave(2))));
+    let ornament = |beat, subdivision, note: Note| Ornament {
+        beat,
+        subdivision,
+        note: PlayedNote::Surely(note.at_octave(2)),
+    };
+    let ornaments = vec![
+        ornament(8, Subdivision::FirstTriplet, D),
+        ornament(8, Subdivision::SecondTriplet, D),
+        ornament(9, Subdivision::Eighth, F),
+        ornament(10, Subdivision::SecondTriplet, A),
+    ];
+
+    let transcription = transcribe(&render(&synth, &notes, &ornaments), &synth);
+    assert_eq!(transcription.notes, notes);
+    assert_eq!(transcription.ornaments, ornaments);
+
+    // Notes between the beats are analyzed in the chord of the beat before them
+    let analysis = Analysis::analyze(transcription, &form);
+    let roles: Vec<_> = [8, 9, 10]
+        .iter()
+        .flat_map(|beat| &analysis.ornaments[beat])
+        .map(|ornament| match ornament.note {
+            Some(NoteAnalysis::Note { role_in_chord, .. }) => role_in_chord,
+            other => panic!("{:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        roles,
+        [
+            ChordTone::NoChordTone,
+            ChordTone::NoChordTone,
+            ChordTone::NoChordTone,
+            ChordTone::NoChordTone
+        ]
+    );
+
+    // The straight eighth note does not swing, the skip into the last beat is not needed
+    let correction = Skips {}.correct(&analysis);
+    assert_eq!(correction.mistakes.len(), 1);
+    assert!(matches!(
+        correction.mistakes[&9].mistake,
+        MistakeKind::UnexpectedOrnament {
+            subdivision: Subdivision::Eighth
+        }
+    ));
+}
+
+#[test]
+fn test_missing_skips() {
+    let synth = BassSynth::default();
+    let form = autumn_leaves();
+    let mut exercise = Skips {};
+    let notes = exercise.generate(&form);
+    let skips = exercise.generate_ornaments(&form);
+
+    let transcription = transcribe(&synth.render(&notes, TEMPO), &synth);
+    assert!(transcription.ornaments.is_empty());
+
+    let analysis = Analysis::analyze(transcription, &form);
+    let correction = exercise.correct(&analysis);
+    assert_eq!(correction.mistakes.len(), skips.len());
+    for skip in skips {
+        assert!(matches!(
+            correction.mistakes[&skip.beat].mistake,
+            MistakeKind::ExpectedOrnament {
+                subdivision: Subdivision::SecondTriplet
+            }
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+use walkanalysis::{
+    exercise::{chord_tones::ChordTones, Exercise},
+    form::{pitch::Pitch, songs::autumn_leaves::autumn_leaves},
+    synth::{bass::BassSynth, samples_per_beat, save_wav},
+    transcribe::transcribe::{AudioSettings, PlayedNote, Transcription, DEFAULT_SETTINGS},
+};
+
+fn transcribe(synth: &BassSynth, notes: &[PlayedNote], tempo: f32) -> Vec<PlayedNote> {
+    let samples = synth.render(notes, tempo);
+    let audio_settings = AudioSettings {
+        sample_rate: synth.sample_rate,
+    };
+    let (transcription, _) =
+        Transcription::transcribe(&samples, tempo, DEFAULT_SETTINGS, audio_settings);
+    transcription.notes
+}
+
+/// Every note from the low E string up two octaves
+fn chromatic() -> Vec<PlayedNote> {
+    (28..=52)
+        .map(|midi| PlayedNote::Surely(Pitch::from_midi(midi)))
+        .collect()
+}
+
+#[test]
+fn test_render() {
+    let synth = BassSynth::default();
+    let notes = [
+        PlayedNote::Silence,
+        PlayedNote::Surely("A1".parse().unwrap()),
+    ];
+    let samples = synth.render(&notes, 120.);
+
+    let beat = samples_per_beat(120., synth.sample_rate);
+    assert_eq!(beat, 22_050);
+    assert_eq!(samples.len(), 2 * beat);
+    assert!(samples[..beat].iter().all(|&sample| sample == 0.));
+
+    let peak = samples
+        .iter()
+        .fold(0f32, |peak, sample| peak.max(sample.abs()));
+    assert!(peak > 0.1 && peak <= 1.);
+    // The note fades out before the next beat
+    assert!(samples.last().unwrap().abs() < 1e-3);
+}
+
+#[test]
+fn test_transcribe_rendered_line() {
+    let form = autumn_leaves();
+    let notes = ChordTones {}.generate(&form);
+
+    assert_eq!(transcribe(&BassSynth::default(), &notes, 110.), notes);
+}
+
+#[test]
+fn test_transcribe_rendered_wav() {
+    let synth = BassSynth::default();
+    let notes = chromatic();
+    let path = std::env::temp_dir().join("walkanalysis_synth_chromatic.wav");
+    save_wav(&path, &synth.render(&notes, 110.), synth.sample_rate).unwrap();
+
+    let (transcription, _) =
+        Transcription::transcribe_from_wav(&path, 110., DEFAULT_SETTINGS).unwrap();
+    assert_eq!(transcription.notes, notes);
+}
+
+#[test]
+fn test_noise_and_detuning() {
+    let notes = chromatic();
+    for (noise, detune) in [(0.02, 12.), (0.05, -15.)] {
+        let synth = BassSynth {
+            noise,
+            detune,
+            ..Default::default()
+        };
+
+        // The noise is seeded, so every render hears the same, and it should hear every note
+        assert_eq!(
+            transcribe(&synth, &notes, 110.),
+            notes,
+            "noise {}, detune {}",
+            noise,
+            detune
+        );
+    }
+}
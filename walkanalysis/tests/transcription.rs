@@ -7,7 +7,10 @@ use walkanalysis::{
         form::Form,
         songs::{autumn_leaves::autumn_leaves, test::longer_test},
     },
-    transcribe::transcribe::{Transcription, DEFAULT_SETTINGS},
+    transcribe::{
+        pitch::{FftPeaks, HarmonicProductSpectrum, Mcleod, PitchDetector, Yin},
+        transcribe::{Transcription, TranscriptionSettings, DEFAULT_SETTINGS},
+    },
 };
 
 fn test_transcription(form: Form, wav: &str, mut exercise: Box<dyn Exercise>) -> Correction {
@@ -73,3 +76,68 @@ fn test_longer_test_arpeggios_up2() {
     );
     println!("{}", correction);
 }
+
+#[test]
+fn test_pitch_detectors_on_recordings() {
+    let detectors: [(&str, &'static dyn PitchDetector); 4] = [
+        ("FFT peaks", &FftPeaks),
+        (
+            "Harmonic product spectrum",
+            &HarmonicProductSpectrum::DEFAULT,
+        ),
+        ("YIN", &Yin::DEFAULT),
+        ("McLeod", &Mcleod::DEFAULT),
+    ];
+    let mut recordings: [(&str, Box<dyn Exercise>); 3] = [
+        ("longer_test_twobeat_thirds", Box::new(two_beat_thirds())),
+        ("longer_test_arpeggios_up", Box::new(ArpeggiosUp {})),
+        ("longer_test_arpeggios_up_2", Box::new(ArpeggiosUp {})),
+    ];
+
+    // Scores of every detector, in the order of the recordings
+    let mut scores = vec![];
+    for (name, pitch_detector) in detectors {
+        let mut detector_scores = vec![];
+        for (recording, exercise) in recordings.iter_mut() {
+            let wav = format!("tests/data/audio/{}.wav", recording);
+            let settings = TranscriptionSettings {
+                pitch_detector,
+                ..DEFAULT_SETTINGS
+            };
+            let (transcription, _) =
+                Transcription::transcribe_from_wav(Path::new(&wav), 110., settings).unwrap();
+            let correction = exercise.correct(&Analysis::analyze(transcription, &longer_test()));
+            println!(
+                "{}: {:.1}% correct on {}",
+                name,
+                correction.score() * 100.,
+                recording
+            );
+            detector_scores.push(correction.score());
+        }
+        scores.push((name, detector_scores));
+    }
+
+    // Every detector hears nearly all notes, and the time-domain ones do at least as well
+    // as the FFT peaks on every recording
+    let fft_peaks = scores[0].1.clone();
+    for (name, detector_scores) in &scores {
+        for (score, (recording, _)) in detector_scores.iter().zip(&recordings) {
+            assert!(*score >= 0.95, "{}: {} on {}", name, score, recording);
+        }
+    }
+    for (name, detector_scores) in &scores[2..] {
+        for ((score, fft_score), (recording, _)) in
+            detector_scores.iter().zip(&fft_peaks).zip(&recordings)
+        {
+            assert!(
+                score >= fft_score,
+                "{} is worse than FFT peaks on {}: {} < {}",
+                name,
+                recording,
+                score,
+                fft_score
+            );
+        }
+    }
+}